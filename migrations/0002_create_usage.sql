@@ -0,0 +1,9 @@
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS workspace_id VARCHAR(64) NOT NULL DEFAULT 'default';
+
+CREATE TABLE IF NOT EXISTS usage (
+    workspace_id   VARCHAR(64)  NOT NULL,
+    period_start   DATE         NOT NULL,
+    links_created  BIGINT       NOT NULL DEFAULT 0,
+    clicks_tracked BIGINT       NOT NULL DEFAULT 0,
+    PRIMARY KEY (workspace_id, period_start)
+);
@@ -0,0 +1,31 @@
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP NULL;
+
+-- Deleted links free up their original_url so it can be shortened again
+ALTER TABLE urls DROP CONSTRAINT IF EXISTS urls_original_url_key;
+CREATE UNIQUE INDEX IF NOT EXISTS urls_original_url_active ON urls (original_url) WHERE deleted_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS audit_events (
+    id          BIGSERIAL    PRIMARY KEY,
+    actor       VARCHAR(128) NOT NULL,
+    action      VARCHAR(32)  NOT NULL,
+    -- Domain of the link; NULL for events that are not about a link, like key revocations
+    domain      VARCHAR(253) NULL,
+    short_key   VARCHAR(20)  NOT NULL,
+    before_url  TEXT         NULL,
+    after_url   TEXT         NULL,
+    request_id  VARCHAR(64)  NULL,
+    created_at  TIMESTAMP    NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_short_key ON audit_events (short_key, id);
+
+CREATE OR REPLACE FUNCTION audit_events_immutable() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_events_no_update ON audit_events;
+CREATE TRIGGER audit_events_no_update
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_immutable();
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS click_events (
+    id           BIGSERIAL    PRIMARY KEY,
+    -- A key exists once per domain, so history is kept per (domain, short_key)
+    domain       VARCHAR(253) NOT NULL,
+    short_key    VARCHAR(20)  NOT NULL,
+    workspace_id VARCHAR(64)  NOT NULL,
+    clicked_at   TIMESTAMP    NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS click_events_link ON click_events (domain, short_key, clicked_at);
+CREATE INDEX IF NOT EXISTS click_events_workspace ON click_events (workspace_id, id);
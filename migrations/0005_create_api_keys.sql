@@ -0,0 +1,12 @@
+-- Only the SHA-256 of each secret is stored; the plaintext is shown once at mint time
+CREATE TABLE IF NOT EXISTS api_keys (
+    id           BIGSERIAL    PRIMARY KEY,
+    workspace_id VARCHAR(64)  NOT NULL,
+    name         VARCHAR(128) NOT NULL,
+    prefix       VARCHAR(16)  NOT NULL,
+    secret_hash  CHAR(64)     NOT NULL UNIQUE,
+    created_at   TIMESTAMP    NOT NULL DEFAULT NOW(),
+    revoked_at   TIMESTAMP    NULL
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_workspace ON api_keys (workspace_id, id);
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS domains (
+    host         VARCHAR(253) PRIMARY KEY,
+    workspace_id VARCHAR(64)  NULL,
+    created_at   TIMESTAMP    NOT NULL DEFAULT NOW()
+);
+
+-- Existing links move onto the shared default domain: the one the migrating process is
+-- configured with (redirect.default_domain), or 'localhost:8080'. Rename it later with
+-- UPDATE domains SET host = '...' and the links follow
+DO $$
+DECLARE
+    configured TEXT := COALESCE(NULLIF(current_setting('redirect.default_domain', true), ''), 'localhost:8080');
+BEGIN
+    INSERT INTO domains (host) VALUES (configured) ON CONFLICT DO NOTHING;
+    EXECUTE format(
+        'ALTER TABLE urls ADD COLUMN IF NOT EXISTS domain VARCHAR(253) NOT NULL DEFAULT %L '
+        'REFERENCES domains (host) ON UPDATE CASCADE',
+        configured);
+END
+$$;
+ALTER TABLE urls ALTER COLUMN domain DROP DEFAULT;
+
+-- The same key, and the same destination, may now exist once per domain
+ALTER TABLE urls DROP CONSTRAINT IF EXISTS urls_pkey;
+ALTER TABLE urls ADD PRIMARY KEY (domain, short_key);
+DROP INDEX IF EXISTS urls_original_url_active;
+CREATE UNIQUE INDEX IF NOT EXISTS urls_original_url_active ON urls (domain, original_url) WHERE deleted_at IS NULL;
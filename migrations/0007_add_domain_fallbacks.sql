@@ -0,0 +1,4 @@
+-- What a domain answers for its root and for unknown keys; NULL everywhere keeps the plain 404
+ALTER TABLE domains ADD COLUMN IF NOT EXISTS root_url       TEXT NULL;
+ALTER TABLE domains ADD COLUMN IF NOT EXISTS not_found_url  TEXT NULL;
+ALTER TABLE domains ADD COLUMN IF NOT EXISTS not_found_page TEXT NULL;
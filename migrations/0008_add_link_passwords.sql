@@ -0,0 +1,2 @@
+-- Argon2 hash (PHC string) of the password visitors must enter; NULL for public links
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS password_hash TEXT NULL;
@@ -0,0 +1,5 @@
+-- Links that stop redirecting after max_clicks visits. clicks_remaining is decremented
+-- with a conditional UPDATE so concurrent visits can't overshoot; NULL means unlimited
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS max_clicks       INTEGER NULL;
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS clicks_remaining INTEGER NULL CHECK (clicks_remaining >= 0);
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS exhausted_url    TEXT    NULL;
@@ -0,0 +1,7 @@
+-- Scheduled links: outside [active_from, active_until) a link answers "not yet active" or
+-- "ended" instead of redirecting, optionally sending visitors to the matching fallback URL
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS active_from         TIMESTAMP NULL;
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS active_until        TIMESTAMP NULL;
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS not_yet_active_url  TEXT      NULL;
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS ended_url           TEXT      NULL;
+ALTER TABLE urls ADD CONSTRAINT urls_active_window_check CHECK (active_from < active_until);
@@ -0,0 +1,4 @@
+-- Per-platform destinations chosen from the visitor's User-Agent; NULL falls back to original_url
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS ios_url     TEXT NULL;
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS android_url TEXT NULL;
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS desktop_url TEXT NULL;
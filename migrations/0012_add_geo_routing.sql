@@ -0,0 +1,4 @@
+-- Per-country destinations as {"DE": "https://..."}; an empty object means no country routing.
+-- Clicks record the visitor's country when geolocation found one
+ALTER TABLE urls         ADD COLUMN IF NOT EXISTS country_urls JSONB   NOT NULL DEFAULT '{}';
+ALTER TABLE click_events ADD COLUMN IF NOT EXISTS country      CHAR(2) NULL;
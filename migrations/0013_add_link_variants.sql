@@ -0,0 +1,4 @@
+-- Weighted split destinations as [{"url": "https://...", "weight": 50}]; empty means no split.
+-- Clicks record which variant, by position, a split link sent the visitor to
+ALTER TABLE urls         ADD COLUMN IF NOT EXISTS variants JSONB   NOT NULL DEFAULT '[]';
+ALTER TABLE click_events ADD COLUMN IF NOT EXISTS variant  INTEGER NULL;
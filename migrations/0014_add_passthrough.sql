@@ -0,0 +1,4 @@
+-- How a link merges the visitor's query string into its destination; NULL drops it.
+-- forward_path links also answer /{short_key}/more/path and append the extra path
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS forward_query TEXT    NULL CHECK (forward_query IN ('keep', 'replace', 'append'));
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS forward_path  BOOLEAN NOT NULL DEFAULT FALSE;
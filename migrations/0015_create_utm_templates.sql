@@ -0,0 +1,13 @@
+-- Named UTM defaults per workspace; links are tagged at shorten time, so editing a
+-- template never changes existing links
+CREATE TABLE IF NOT EXISTS utm_templates (
+    workspace_id VARCHAR(64)  NOT NULL,
+    name         VARCHAR(64)  NOT NULL,
+    utm_source   VARCHAR(200) NULL,
+    utm_medium   VARCHAR(200) NULL,
+    utm_campaign VARCHAR(200) NULL,
+    utm_term     VARCHAR(200) NULL,
+    utm_content  VARCHAR(200) NULL,
+    updated_at   TIMESTAMP    NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workspace_id, name)
+);
@@ -0,0 +1,4 @@
+-- Seconds a link's or domain's warning page waits before continuing on its own; 0 waits for
+-- the visitor to click through, NULL redirects straight away
+ALTER TABLE urls    ADD COLUMN IF NOT EXISTS interstitial_countdown INTEGER NULL CHECK (interstitial_countdown >= 0);
+ALTER TABLE domains ADD COLUMN IF NOT EXISTS interstitial_countdown INTEGER NULL CHECK (interstitial_countdown >= 0);
@@ -0,0 +1,4 @@
+-- Shortening is idempotent per workspace: another workspace shortening the same destination
+-- on the same domain gets a link of its own
+CREATE UNIQUE INDEX IF NOT EXISTS urls_original_url_live ON urls (domain, workspace_id, original_url) WHERE deleted_at IS NULL;
+DROP INDEX IF EXISTS urls_original_url_active;
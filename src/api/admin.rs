@@ -0,0 +1,153 @@
+use crate::model::DomainError;
+use crate::service::api_key_service::ApiKeyService;
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+/// Actor recorded for changes made with the admin token.
+pub const ADMIN_ACTOR: &str = "admin";
+
+/// Bearer token guarding `/admin/*` and link management. `None` disables those routes entirely.
+#[derive(Debug, Clone, Default)]
+pub struct AdminToken(pub Option<Arc<str>>);
+
+impl AdminToken {
+    pub fn new(token: Option<String>) -> Self {
+        Self(token.filter(|t| !t.is_empty()).map(Arc::from))
+    }
+
+    fn matches(&self, presented: &str) -> bool {
+        self.0
+            .as_deref()
+            .is_some_and(|expected| constant_time_eq(presented.as_bytes(), expected.as_bytes()))
+    }
+}
+
+/// What `require_credentials` accepts: the admin token, or an unrevoked API key.
+#[derive(Clone)]
+pub struct Credentials {
+    admin: AdminToken,
+    keys: Arc<ApiKeyService>,
+}
+
+impl Credentials {
+    pub fn new(admin: AdminToken, keys: ApiKeyService) -> Self {
+        Self { admin, keys: Arc::new(keys) }
+    }
+
+    async fn identify(&self, headers: &HeaderMap) -> Result<Option<Caller>, DomainError> {
+        let presented = bearer_token(headers);
+        if self.admin.matches(presented) {
+            return Ok(Some(Caller::Admin));
+        }
+        let key = self.keys.authenticate(presented).await?;
+        Ok(key.map(|key| Caller::ApiKey { id: key.id, workspace_id: key.workspace_id }))
+    }
+}
+
+/// The authenticated caller of a request, set by the auth middleware. Audit records name it
+/// as the actor, so it never comes from anything the client can write freely.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// Presented the admin token.
+    Admin,
+    /// Presented an unrevoked API key, which only acts on its own workspace.
+    ApiKey { id: i64, workspace_id: String },
+}
+
+impl Caller {
+    pub fn actor(&self) -> String {
+        match self {
+            Caller::Admin => ADMIN_ACTOR.to_string(),
+            Caller::ApiKey { id, .. } => format!("api-key:{id}"),
+        }
+    }
+
+    /// The workspace the caller is confined to; `None` for the admin, who may act on any.
+    pub fn workspace(&self) -> Option<&str> {
+        match self {
+            Caller::Admin => None,
+            Caller::ApiKey { workspace_id, .. } => Some(workspace_id),
+        }
+    }
+
+    /// Fails unless the caller may act on `workspace_id`.
+    pub fn authorize(&self, workspace_id: &str) -> Result<(), DomainError> {
+        match self.workspace() {
+            Some(own) if own != workspace_id => Err(DomainError::Forbidden(workspace_id.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = DomainError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Caller>().cloned().ok_or(DomainError::Unauthorized)
+    }
+}
+
+// `Option<Caller>` on routes behind `identify_caller`, where anonymous requests are fine
+impl<S: Send + Sync> OptionalFromRequestParts<S> for Caller {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Caller>().cloned())
+    }
+}
+
+// Compare without short-circuiting so response timing doesn't reveal a matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn bearer_token(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or("")
+}
+
+pub async fn require_admin(
+    State(token): State<AdminToken>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, DomainError> {
+    if token.matches(bearer_token(req.headers())) {
+        req.extensions_mut().insert(Caller::Admin);
+        Ok(next.run(req).await)
+    } else {
+        Err(DomainError::Unauthorized)
+    }
+}
+
+/// Like `require_admin`, but also lets API keys through; handlers confine those to their workspace.
+pub async fn require_credentials(
+    State(credentials): State<Credentials>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, DomainError> {
+    let caller = credentials.identify(req.headers()).await?.ok_or(DomainError::Unauthorized)?;
+    req.extensions_mut().insert(caller);
+    Ok(next.run(req).await)
+}
+
+/// For open routes that show more to a known caller: records who is asking when the request
+/// carries valid credentials, and lets everyone else through anonymously.
+pub async fn identify_caller(
+    State(credentials): State<Credentials>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, DomainError> {
+    if let Some(caller) = credentials.identify(req.headers()).await? {
+        req.extensions_mut().insert(caller);
+    }
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,53 @@
+use crate::api::extract::{ApiJson, ApiPath};
+use crate::model::{DomainError, DomainFallbacks, Interstitial};
+use crate::service::domain_service::DomainService;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct RegisterDomainReq {
+    pub host: String,
+    /// Owning workspace; the domain is shared with every workspace when omitted.
+    pub workspace: Option<String>,
+}
+
+pub async fn register_domain(
+    State(domains): State<Arc<DomainService>>,
+    ApiJson(body): ApiJson<RegisterDomainReq>,
+) -> Result<Response, DomainError> {
+    let domain = domains.register(&body.host, body.workspace.as_deref()).await?;
+    Ok((StatusCode::CREATED, Json(domain)).into_response())
+}
+
+pub async fn list_domains(State(domains): State<Arc<DomainService>>) -> Result<Response, DomainError> {
+    Ok(Json(domains.list().await?).into_response())
+}
+
+pub async fn set_domain_fallbacks(
+    State(domains): State<Arc<DomainService>>,
+    ApiPath(host): ApiPath<String>,
+    ApiJson(body): ApiJson<DomainFallbacks>,
+) -> Result<Response, DomainError> {
+    Ok(Json(domains.set_fallbacks(&host, &body).await?).into_response())
+}
+
+pub async fn set_domain_interstitial(
+    State(domains): State<Arc<DomainService>>,
+    ApiPath(host): ApiPath<String>,
+    ApiJson(body): ApiJson<Interstitial>,
+) -> Result<Response, DomainError> {
+    Ok(Json(domains.set_interstitial(&host, Some(body)).await?).into_response())
+}
+
+pub async fn clear_domain_interstitial(
+    State(domains): State<Arc<DomainService>>,
+    ApiPath(host): ApiPath<String>,
+) -> Result<Response, DomainError> {
+    Ok(Json(domains.set_interstitial(&host, None).await?).into_response())
+}
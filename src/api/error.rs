@@ -0,0 +1,163 @@
+use crate::api::request_context::current_request_id;
+use crate::model::{DomainError, QuotaKind};
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// RFC 7807 body. `code` is the stable identifier clients should branch on;
+/// `type` is the same identifier as a URN.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    pub code: &'static str,
+    pub request_id: Option<String>,
+}
+
+impl DomainError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            DomainError::UrlNotFound => StatusCode::NOT_FOUND,
+            DomainError::ApiKeyNotFound => StatusCode::NOT_FOUND,
+            DomainError::InvalidShortKey => StatusCode::BAD_REQUEST,
+            DomainError::InvalidUrl => StatusCode::BAD_REQUEST,
+            DomainError::InvalidDomain => StatusCode::BAD_REQUEST,
+            DomainError::UnknownDomain(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            DomainError::UnknownUtmTemplate(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            DomainError::DomainTaken(_) => StatusCode::CONFLICT,
+            DomainError::InvalidPassword => StatusCode::BAD_REQUEST,
+            DomainError::PasswordRequired => StatusCode::UNAUTHORIZED,
+            DomainError::WrongPassword => StatusCode::FORBIDDEN,
+            DomainError::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            DomainError::ProtectionConflict => StatusCode::CONFLICT,
+            DomainError::LinkExhausted(_) => StatusCode::GONE,
+            DomainError::ClickLimitConflict => StatusCode::CONFLICT,
+            DomainError::LinkNotYetActive(_) => StatusCode::NOT_FOUND,
+            DomainError::LinkEnded(_) => StatusCode::GONE,
+            DomainError::ScheduleConflict => StatusCode::CONFLICT,
+            DomainError::RoutingConflict => StatusCode::CONFLICT,
+            DomainError::DestinationTaken => StatusCode::CONFLICT,
+            DomainError::BatchTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            DomainError::UnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            DomainError::MalformedInput(_) => StatusCode::BAD_REQUEST,
+            DomainError::Unauthorized => StatusCode::UNAUTHORIZED,
+            DomainError::Forbidden(_) => StatusCode::FORBIDDEN,
+            DomainError::QuotaExceeded(QuotaKind::Links) => StatusCode::PAYMENT_REQUIRED,
+            DomainError::QuotaExceeded(QuotaKind::Clicks) => StatusCode::TOO_MANY_REQUESTS,
+            DomainError::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            DomainError::UrlNotFound => "url_not_found",
+            DomainError::ApiKeyNotFound => "api_key_not_found",
+            DomainError::InvalidShortKey => "invalid_short_key",
+            DomainError::InvalidUrl => "invalid_url",
+            DomainError::InvalidDomain => "invalid_domain",
+            DomainError::UnknownDomain(_) => "unknown_domain",
+            DomainError::UnknownUtmTemplate(_) => "unknown_utm_template",
+            DomainError::DomainTaken(_) => "domain_taken",
+            DomainError::InvalidPassword => "invalid_password",
+            DomainError::PasswordRequired => "password_required",
+            DomainError::WrongPassword => "wrong_password",
+            DomainError::TooManyAttempts(_) => "too_many_attempts",
+            DomainError::ProtectionConflict => "protection_conflict",
+            DomainError::LinkExhausted(_) => "link_exhausted",
+            DomainError::ClickLimitConflict => "click_limit_conflict",
+            DomainError::LinkNotYetActive(_) => "link_not_yet_active",
+            DomainError::LinkEnded(_) => "link_ended",
+            DomainError::ScheduleConflict => "schedule_conflict",
+            DomainError::RoutingConflict => "routing_conflict",
+            DomainError::DestinationTaken => "destination_taken",
+            DomainError::BatchTooLarge(_) => "batch_too_large",
+            DomainError::UnsupportedFormat => "unsupported_format",
+            DomainError::MalformedInput(_) => "malformed_input",
+            DomainError::Unauthorized => "unauthorized",
+            DomainError::Forbidden(_) => "forbidden",
+            DomainError::QuotaExceeded(QuotaKind::Links) => "link_quota_exceeded",
+            DomainError::QuotaExceeded(QuotaKind::Clicks) => "click_quota_exceeded",
+            DomainError::DatabaseError(_) => "internal_error",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            DomainError::UrlNotFound => "URL not found",
+            DomainError::ApiKeyNotFound => "API key not found",
+            DomainError::InvalidShortKey => "Invalid short key",
+            DomainError::InvalidUrl => "Invalid URL",
+            DomainError::InvalidDomain => "Invalid domain",
+            DomainError::UnknownDomain(_) => "Unknown domain",
+            DomainError::UnknownUtmTemplate(_) => "Unknown UTM template",
+            DomainError::DomainTaken(_) => "Domain taken",
+            DomainError::InvalidPassword => "Invalid password",
+            DomainError::PasswordRequired => "Password required",
+            DomainError::WrongPassword => "Wrong password",
+            DomainError::TooManyAttempts(_) => "Too many attempts",
+            DomainError::ProtectionConflict => "Protection conflict",
+            DomainError::LinkExhausted(_) => "Link exhausted",
+            DomainError::ClickLimitConflict => "Click limit conflict",
+            DomainError::LinkNotYetActive(_) => "Link not yet active",
+            DomainError::LinkEnded(_) => "Link ended",
+            DomainError::ScheduleConflict => "Schedule conflict",
+            DomainError::RoutingConflict => "Routing conflict",
+            DomainError::DestinationTaken => "Destination taken",
+            DomainError::BatchTooLarge(_) => "Batch too large",
+            DomainError::UnsupportedFormat => "Unsupported format",
+            DomainError::MalformedInput(_) => "Malformed input",
+            DomainError::Unauthorized => "Unauthorized",
+            DomainError::Forbidden(_) => "Forbidden",
+            DomainError::QuotaExceeded(_) => "Quota exceeded",
+            DomainError::DatabaseError(_) => "Internal server error",
+        }
+    }
+
+    /// Message that is safe to show a client — never includes driver or SQL text.
+    fn public_detail(&self) -> String {
+        match self {
+            DomainError::InvalidShortKey => {
+                "Short keys are at least 6 alphanumeric characters.".to_string()
+            }
+            DomainError::DatabaseError(_) => "An unexpected error occurred.".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn to_problem(&self) -> Problem {
+        Problem {
+            problem_type: format!("urn:redirect-service:problem:{}", self.code()),
+            title: self.title(),
+            status: self.status().as_u16(),
+            detail: self.public_detail(),
+            code: self.code(),
+            request_id: current_request_id(),
+        }
+    }
+}
+
+impl IntoResponse for DomainError {
+    fn into_response(self) -> Response {
+        let problem = self.to_problem();
+
+        if self.status().is_server_error() {
+            // Full error text stays in the logs; the enclosing request span carries the request id
+            tracing::error!(error = %self, code = problem.code, "request failed");
+        }
+
+        let mut response = (self.status(), Json(problem)).into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        response
+    }
+}
@@ -0,0 +1,51 @@
+use crate::model::DomainError;
+use axum::extract::rejection::{FormRejection, JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts};
+
+// Axum's own extractors reject with text/plain bodies. These wrappers turn the rejection into a
+// DomainError so a malformed body, query or path answers with the same problem+json as any other
+// client error.
+
+/// `axum::Json` that rejects with a problem body.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(DomainError))]
+pub struct ApiJson<T>(pub T);
+
+/// `axum::extract::Query` that rejects with a problem body.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(DomainError))]
+pub struct ApiQuery<T>(pub T);
+
+/// `axum::extract::Path` that rejects with a problem body.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(DomainError))]
+pub struct ApiPath<T>(pub T);
+
+/// `axum::Form` that rejects with a problem body.
+#[derive(FromRequest)]
+#[from_request(via(axum::Form), rejection(DomainError))]
+pub struct ApiForm<T>(pub T);
+
+impl From<JsonRejection> for DomainError {
+    fn from(rejection: JsonRejection) -> Self {
+        DomainError::MalformedInput(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for DomainError {
+    fn from(rejection: QueryRejection) -> Self {
+        DomainError::MalformedInput(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for DomainError {
+    fn from(rejection: PathRejection) -> Self {
+        DomainError::MalformedInput(rejection.body_text())
+    }
+}
+
+impl From<FormRejection> for DomainError {
+    fn from(rejection: FormRejection) -> Self {
+        DomainError::MalformedInput(rejection.body_text())
+    }
+}
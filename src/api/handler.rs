@@ -1,54 +1,681 @@
 use std::sync::Arc;
 use serde::Deserialize;
 use crate::service::url_service::UrlService;
-use crate::model::{DomainError, ShortKey, UrlResponse, Url};
+use crate::model::audit::{AuditAction, AuditFilter, DEFAULT_AUDIT_PAGE_SIZE, MAX_AUDIT_PAGE_SIZE};
+use crate::model::url::DEFAULT_WORKSPACE;
+use crate::api::admin::Caller;
+use crate::api::error::Problem;
+use crate::api::extract::{ApiForm, ApiJson, ApiPath, ApiQuery};
+use crate::api::metrics::RedirectOutcome;
+use crate::api::pages::{interstitial_page, password_form, preview_page};
+use crate::api::request_context::{new_visitor_id, RequestHost, UNLOCK_COOKIE, VISITOR_COOKIE, VISITOR_COOKIE_MAX_AGE};
+use crate::model::domain::{is_local_host, normalize_host};
+use crate::service::unlock::UNLOCK_TTL;
+use crate::model::export::{click_line, export_header, link_line};
+use crate::model::import::DEFAULT_IMPORT_CHUNK_SIZE;
+use crate::model::qr::{qr_etag, render_qr};
+use crate::model::{
+    AuditContext, AuditPageResponse, CountryRoutes, DataFormat, DomainError, ExportKind, Fallback, NewLink, PlatformRoutes,
+    Interstitial, QrColor, QrFormat, QrOptions, QueryPolicy, ShortKey, UrlResponse, UsageResponse, Utm, Variants, Visit, Warning,
+};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use tokio::io::BufReader;
+use tokio_util::io::StreamReader;
+use serde::Serialize;
+use chrono::{DateTime, Utc};
 use axum::{
     Json,
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Redirect},
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
 };
 
 pub type AppState = Arc<UrlService>;
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ShortenReq {
     pub url: String,
+    pub workspace: Option<String>,
+    pub domain: Option<String>,
+    pub password: Option<String>,
+    pub max_clicks: Option<u32>,
+    pub exhausted_url: Option<String>,
+    pub active_from: Option<DateTime<Utc>>,
+    pub active_until: Option<DateTime<Utc>>,
+    pub not_yet_active_url: Option<String>,
+    pub ended_url: Option<String>,
+    #[serde(default)]
+    pub platform_urls: PlatformRoutes,
+    #[serde(default)]
+    pub country_urls: CountryRoutes,
+    #[serde(default)]
+    pub variants: Variants,
+    pub forward_query: Option<QueryPolicy>,
+    #[serde(default)]
+    pub forward_path: bool,
+    pub interstitial: Option<Interstitial>,
+    #[serde(default)]
+    pub utm: Utm,
+    pub utm_template: Option<String>,
+}
+
+impl From<ShortenReq> for NewLink {
+    fn from(req: ShortenReq) -> Self {
+        let mut link = NewLink::new(req.url);
+        if let Some(workspace) = req.workspace {
+            link = link.in_workspace(workspace);
+        }
+        if let Some(domain) = req.domain {
+            link = link.on_domain(domain);
+        }
+        if let Some(password) = req.password {
+            link = link.with_password(password);
+        }
+        if let Some(max_clicks) = req.max_clicks {
+            link = link.with_max_clicks(max_clicks);
+        }
+        if let Some(exhausted_url) = req.exhausted_url {
+            link = link.with_exhausted_url(exhausted_url);
+        }
+        if let Some(active_from) = req.active_from {
+            link = link.active_from(active_from);
+        }
+        if let Some(active_until) = req.active_until {
+            link = link.active_until(active_until);
+        }
+        if let Some(not_yet_active_url) = req.not_yet_active_url {
+            link = link.with_not_yet_active_url(not_yet_active_url);
+        }
+        if let Some(ended_url) = req.ended_url {
+            link = link.with_ended_url(ended_url);
+        }
+        link.platform_routes = req.platform_urls;
+        link.country_routes = req.country_urls.normalized();
+        link.variants = req.variants;
+        link.forward_query = req.forward_query;
+        link.forward_path = req.forward_path;
+        link.interstitial = req.interstitial;
+        link.utm = req.utm;
+        link.utm_template = req.utm_template;
+        link
+    }
+}
+
+/// One entry of a batch response: either the link or the problem that prevented it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResponse {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<UrlResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Problem>,
+}
+
+#[derive(Serialize)]
+pub struct BatchResponse {
+    pub results: Vec<BatchItemResponse>,
+}
+
+#[derive(Deserialize)]
+pub struct UnlockForm {
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    pub workspace: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RetargetReq {
+    pub url: String,
+}
+
+/// Selects the domain of the link being managed; the default domain when omitted.
+#[derive(Deserialize)]
+pub struct LinkQuery {
+    pub domain: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub domain: Option<String>,
+    pub short_key: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportQuery {
+    pub format: Option<String>,
+    pub chunk_size: Option<usize>,
 }
 
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+    pub kind: Option<String>,
+    pub workspace: Option<String>,
+}
+
+/// `?format=` wins; otherwise the Content-Type decides.
+fn requested_format(format: Option<&str>, headers: &HeaderMap) -> Result<DataFormat, DomainError> {
+    let from_header = || {
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(DataFormat::from_content_type)
+    };
+    match format {
+        Some(name) => DataFormat::from_name(name),
+        None => from_header(),
+    }
+    .ok_or(DomainError::UnsupportedFormat)
+}
+
+// Errors are rendered as application/problem+json by DomainError's IntoResponse impl
+
 pub async fn shorten_url(
     State(service): State<AppState>,
-    Json(body): Json<ShortenReq>,
-) -> impl IntoResponse {
-    let result: Result<Url, DomainError> = service.shorten(&body.url).await;
-    match result {
+    caller: Option<Caller>,
+    ctx: AuditContext,
+    ApiJson(mut body): ApiJson<ShortenReq>,
+) -> Result<Response, DomainError> {
+    // Anyone may shorten into the shared default workspace; other workspaces need their API key
+    // or the admin token, like the batch endpoint
+    let workspace = body
+        .workspace
+        .get_or_insert_with(|| caller.as_ref().and_then(Caller::workspace).unwrap_or(DEFAULT_WORKSPACE).to_string());
+    match &caller {
+        Some(caller) => caller.authorize(workspace)?,
+        None if workspace != DEFAULT_WORKSPACE => return Err(DomainError::Unauthorized),
+        None => {}
+    }
+    let url = service.shorten_link(&ctx, &NewLink::from(body)).await?;
+    let response = UrlResponse::new(url);
+    Ok((StatusCode::CREATED, Json(response)).into_response())
+}
+
+pub async fn shorten_batch(
+    State(service): State<AppState>,
+    caller: Caller,
+    ctx: AuditContext,
+    ApiJson(mut body): ApiJson<Vec<ShortenReq>>,
+) -> Result<Response, DomainError> {
+    // API keys shorten into their own workspace unless told otherwise, and never into another
+    for item in &mut body {
+        let workspace = item
+            .workspace
+            .get_or_insert_with(|| caller.workspace().unwrap_or(DEFAULT_WORKSPACE).to_string());
+        caller.authorize(workspace)?;
+    }
+    let links: Vec<NewLink> = body.into_iter().map(NewLink::from).collect();
+    let outcomes = service.shorten_batch(&ctx, &links).await?;
+
+    let results = outcomes
+        .into_iter()
+        .enumerate()
+        .map(|(index, outcome)| match outcome {
+            Ok(url) => BatchItemResponse {
+                index,
+                status: StatusCode::CREATED.as_u16(),
+                link: Some(UrlResponse::new(url)),
+                error: None,
+            },
+            Err(e) => BatchItemResponse {
+                index,
+                status: e.status().as_u16(),
+                link: None,
+                error: Some(e.to_problem()),
+            },
+        })
+        .collect();
+
+    Ok(Json(BatchResponse { results }).into_response())
+}
+
+pub async fn redirect_to_url(
+    State(service): State<AppState>,
+    caller: Option<Caller>,
+    visit: Visit,
+    ApiPath(key): ApiPath<String>,
+) -> Result<Response, DomainError> {
+    // Route templates can't match a suffix within a segment, so `/{short_key}+` arrives here too;
+    // `+` is never part of a key. Previews of unknown keys are previews too, not redirect misses
+    if let Some(key) = key.strip_suffix('+') {
+        let mut response = preview_url(&service, caller.as_ref(), visit.host.as_deref(), key).await.into_response();
+        response.extensions_mut().insert(RedirectOutcome("preview"));
+        return Ok(response);
+    }
+    // First-time visitors get an id up front so a split link can place them right away
+    let new_visitor = visit.visitor_id.is_none();
+    let visit = if new_visitor { visit.as_visitor(new_visitor_id()) } else { visit };
+    let resolved = match ShortKey::new(key.clone()) {
+        Ok(short_key) => service.visit(&visit, &short_key).await,
+        Err(e) => Err(e),
+    };
+    match resolved {
         Ok(url) => {
-            let response = UrlResponse::new(url, "http://localhost:8080");
-            (StatusCode::CREATED, Json(response)).into_response()
+            let location = url.location(&visit);
+            let mut response = if let Some(warning) = service.warning(&url, &location) {
+                warning_page(&location, warning)
+            } else if url.is_cacheable() {
+                // axum's Redirect::permanent is a 308; the service promises a 301
+                // Forwarded paths and queries are part of the request URL, so the redirect can still be cached
+                return Ok((StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response());
+            } else {
+                // Unlocks, click limits, schedules, routes and variants are checked per visit,
+                // so browsers and CDNs must not keep the redirect
+                uncached_redirect(location, "hit")
+            };
+            if new_visitor
+                && !url.variants.is_empty()
+                && let Some(visitor_id) = &visit.visitor_id
+            {
+                let secure = if visit.host.as_deref().is_some_and(is_local_host) { "" } else { "; Secure" };
+                let cookie = format!(
+                    "{VISITOR_COOKIE}={visitor_id}; Path=/; Max-Age={VISITOR_COOKIE_MAX_AGE}; HttpOnly; SameSite=Lax{secure}"
+                );
+                response.headers_mut().insert(header::SET_COOKIE, cookie.parse().expect("visitor ids are alphanumeric"));
+            }
+            Ok(response)
         }
-        Err(e) => {
-            eprintln!("Error: {e}");
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        Err(DomainError::PasswordRequired) => Ok(password_page(StatusCode::OK, &key, None)),
+        Err(e @ (DomainError::LinkNotYetActive(_) | DomainError::LinkEnded(_) | DomainError::LinkExhausted(_))) => {
+            Ok(unavailable_response(e))
         }
+        Err(e @ (DomainError::UrlNotFound | DomainError::InvalidShortKey)) => {
+            match service.fallbacks(visit.host.as_deref()).await?.not_found(&key) {
+                Some(fallback) => Ok(fallback_response(fallback)),
+                None => Err(e),
+            }
+        }
+        Err(e) => Err(e),
     }
 }
 
-pub async fn redirect_to_url(
+/// `/{short_key}+`: a page describing where the link goes, in place of the redirect. Owner and
+/// clicks are only shown to the admin and to API keys of the link's workspace.
+async fn preview_url(
+    service: &UrlService,
+    caller: Option<&Caller>,
+    host: Option<&str>,
+    key: &str,
+) -> Result<Response, DomainError> {
+    let preview = service.preview(host, &ShortKey::new(key.to_string())?).await?;
+    let authorized = match (caller, &preview.owner) {
+        (Some(caller), Some(owner)) => caller.authorize(owner).is_ok(),
+        _ => false,
+    };
+    let preview = if authorized { preview } else { preview.public() };
+    Ok(([(header::CACHE_CONTROL, NO_STORE)], Html(preview_page(&preview))).into_response())
+}
+
+/// `/{short_key}/more/path` on a link that forwards paths; other links answer as if the
+/// key were unknown.
+pub async fn redirect_with_path(
+    state: State<AppState>,
+    visit: Visit,
+    ApiPath((key, extra_path)): ApiPath<(String, String)>,
+) -> Result<Response, DomainError> {
+    redirect_to_url(state, None, visit.with_extra_path(extra_path), ApiPath(key)).await
+}
+
+#[derive(Deserialize)]
+pub struct QrQuery {
+    pub format: Option<String>,
+    pub size: Option<u32>,
+    pub margin: Option<u32>,
+    pub ec: Option<String>,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+}
+
+impl QrQuery {
+    fn options(&self) -> Result<QrOptions, DomainError> {
+        let mut options = QrOptions::default();
+        if let Some(size) = self.size {
+            options.size = size;
+        }
+        if let Some(margin) = self.margin {
+            options.margin = margin;
+        }
+        if let Some(ec) = &self.ec {
+            options.ec_level = QrOptions::parse_ec_level(ec)?;
+        }
+        if let Some(fg) = &self.fg {
+            options.foreground = QrColor::parse(fg)?;
+        }
+        if let Some(bg) = &self.bg {
+            options.background = QrColor::parse(bg)?;
+        }
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+/// A link's short URL never changes, so its QR images can be cached for a day and revalidated by ETag.
+const QR_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// QR code for the link's short URL. `?format=` wins; otherwise `Accept` decides, falling back to SVG.
+pub async fn qr_code(
     State(service): State<AppState>,
-    Path(key): Path<String>,
-) -> impl IntoResponse {
-    let short_key = match ShortKey::new(key) {
-        Ok(k) => k,
-        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    RequestHost(host): RequestHost,
+    ApiPath(key): ApiPath<String>,
+    ApiQuery(query): ApiQuery<QrQuery>,
+    headers: HeaderMap,
+) -> Result<Response, DomainError> {
+    let format = match query.format.as_deref() {
+        Some(name) => QrFormat::from_name(name)
+            .ok_or_else(|| DomainError::MalformedInput("format must be svg or png".to_string()))?,
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .map_or(QrFormat::Svg, QrFormat::from_accept),
     };
+    let options = query.options()?;
+    let short_url = service.short_url(host.as_deref(), &ShortKey::new(key)?).await?;
+
+    let etag = qr_etag(&short_url, &options, format);
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, QR_CACHE_CONTROL.to_string()),
+        (header::VARY, header::ACCEPT.to_string()),
+    ];
+    let revalidated = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|tags| tags.split(',').map(str::trim).any(|tag| tag == "*" || tag == etag));
+    if revalidated {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    let image = render_qr(&short_url, &options, format)?;
+    Ok((cache_headers, [(header::CONTENT_TYPE, format.content_type())], image).into_response())
+}
 
-    let result: Result<Url, DomainError> = service.resolve(&short_key).await;
-    match result {
-        Ok(url) => Redirect::permanent(&url.original_url).into_response(),
-        Err(DomainError::UrlNotFound) => StatusCode::NOT_FOUND.into_response(),
-        Err(e) => {
-            eprintln!("Error: {e}");
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+/// Password form submission for a protected link. A correct password sets the unlock
+/// cookie and sends the visitor back to the link; a wrong one shows the form again.
+pub async fn unlock_url(
+    State(service): State<AppState>,
+    RequestHost(host): RequestHost,
+    ApiPath(key): ApiPath<String>,
+    ApiForm(form): ApiForm<UnlockForm>,
+) -> Result<Response, DomainError> {
+    let short_key = ShortKey::new(key)?;
+    let token = match service.unlock(host.as_deref(), &short_key, &form.password).await {
+        Ok(token) => token,
+        Err(e @ DomainError::WrongPassword) => {
+            return Ok(password_page(e.status(), short_key.as_str(), Some(&e.to_string())));
+        }
+        Err(e @ DomainError::TooManyAttempts(wait)) => {
+            let mut response = password_page(e.status(), short_key.as_str(), Some(&e.to_string()));
+            response.headers_mut().insert(header::RETRY_AFTER, wait.into());
+            return Ok(response);
         }
+        Err(e) => return Err(e),
+    };
+
+    let secure = if host.as_deref().is_some_and(is_local_host) { "" } else { "; Secure" };
+    let cookie = format!(
+        "{UNLOCK_COOKIE}={token}; Path=/{key}; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}",
+        key = short_key.as_str(),
+        max_age = UNLOCK_TTL.num_seconds(),
+    );
+    Ok((
+        StatusCode::SEE_OTHER,
+        [
+            (header::LOCATION, format!("/{}", short_key.as_str())),
+            (header::SET_COOKIE, cookie),
+            (header::CACHE_CONTROL, NO_STORE.to_string()),
+        ],
+    )
+        .into_response())
+}
+
+const NO_STORE: &str = "private, no-store";
+
+/// Links that are outside their window or out of clicks send visitors to their fallback if they
+/// have one, and answer with the problem otherwise.
+fn unavailable_response(e: DomainError) -> Response {
+    let (outcome, fallback) = match &e {
+        DomainError::LinkNotYetActive(fallback) => ("not_yet_active", fallback.clone()),
+        DomainError::LinkEnded(fallback) => ("ended", fallback.clone()),
+        DomainError::LinkExhausted(fallback) => ("exhausted", fallback.clone()),
+        _ => ("miss", None),
+    };
+    if let Some(fallback) = fallback {
+        return uncached_redirect(fallback, outcome);
+    }
+    let mut response = e.into_response();
+    response.extensions_mut().insert(RedirectOutcome(outcome));
+    response
+}
+
+fn uncached_redirect(location: String, outcome: &'static str) -> Response {
+    let mut response = (
+        StatusCode::FOUND,
+        [(header::LOCATION, location), (header::CACHE_CONTROL, NO_STORE.to_string())],
+    )
+        .into_response();
+    response.extensions_mut().insert(RedirectOutcome(outcome));
+    response
+}
+
+/// The page shown instead of the redirect when the link, its domain or the warning list ask
+/// for one. Settings can change at any time, so it is not cached.
+fn warning_page(location: &str, warning: Warning) -> Response {
+    let outcome = match warning {
+        Warning::Leaving(_) => "interstitial",
+        Warning::Flagged => "flagged",
+    };
+    let mut response = ([(header::CACHE_CONTROL, NO_STORE)], Html(interstitial_page(location, warning))).into_response();
+    response.extensions_mut().insert(RedirectOutcome(outcome));
+    response
+}
+
+fn password_page(status: StatusCode, short_key: &str, error: Option<&str>) -> Response {
+    let mut response = (
+        status,
+        [(header::CACHE_CONTROL, NO_STORE)],
+        Html(password_form(short_key, error)),
+    )
+        .into_response();
+    response.extensions_mut().insert(RedirectOutcome("locked"));
+    response
+}
+
+/// The bare domain: its configured homepage, or its branded 404 page.
+pub async fn redirect_root(
+    State(service): State<AppState>,
+    RequestHost(host): RequestHost,
+) -> Result<Response, DomainError> {
+    let fallback = service.fallbacks(host.as_deref()).await?.root().ok_or(DomainError::UrlNotFound)?;
+    Ok(fallback_response(fallback))
+}
+
+// Fallback targets are configuration, not links, so they redirect with a 302 browsers won't cache
+fn fallback_response(fallback: Fallback) -> Response {
+    match fallback {
+        Fallback::Redirect(url) => {
+            let mut response = (StatusCode::FOUND, [(header::LOCATION, url)]).into_response();
+            response.extensions_mut().insert(RedirectOutcome("fallback"));
+            response
+        }
+        Fallback::Page(html) => (StatusCode::NOT_FOUND, Html(html)).into_response(),
+    }
+}
+
+pub async fn get_usage(
+    State(service): State<AppState>,
+    caller: Caller,
+    ApiQuery(query): ApiQuery<UsageQuery>,
+) -> Result<Response, DomainError> {
+    let workspace = query.workspace.as_deref().or(caller.workspace()).unwrap_or(DEFAULT_WORKSPACE);
+    caller.authorize(workspace)?;
+    let (usage, limits) = service.usage(workspace).await?;
+    Ok(Json(UsageResponse::new(usage, limits)).into_response())
+}
+
+// API keys manage only their own workspace's links; anyone else's look missing to them
+async fn authorize_link(service: &UrlService, caller: &Caller, domain: Option<&str>, short_key: &ShortKey) -> Result<(), DomainError> {
+    if let Some(own) = caller.workspace()
+        && service.lookup(domain, short_key).await?.workspace_id != own
+    {
+        return Err(DomainError::UrlNotFound);
     }
-}
\ No newline at end of file
+    Ok(())
+}
+
+pub async fn retarget_url(
+    State(service): State<AppState>,
+    caller: Caller,
+    ctx: AuditContext,
+    ApiPath(key): ApiPath<String>,
+    ApiQuery(query): ApiQuery<LinkQuery>,
+    ApiJson(body): ApiJson<RetargetReq>,
+) -> Result<Response, DomainError> {
+    let short_key = ShortKey::new(key)?;
+    authorize_link(&service, &caller, query.domain.as_deref(), &short_key).await?;
+    let url = service.retarget(&ctx, query.domain.as_deref(), &short_key, &body.url).await?;
+    Ok(Json(UrlResponse::new(url)).into_response())
+}
+
+pub async fn delete_url(
+    State(service): State<AppState>,
+    caller: Caller,
+    ctx: AuditContext,
+    ApiPath(key): ApiPath<String>,
+    ApiQuery(query): ApiQuery<LinkQuery>,
+) -> Result<Response, DomainError> {
+    let short_key = ShortKey::new(key)?;
+    authorize_link(&service, &caller, query.domain.as_deref(), &short_key).await?;
+    service.delete(&ctx, query.domain.as_deref(), &short_key).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub async fn restore_url(
+    State(service): State<AppState>,
+    caller: Caller,
+    ctx: AuditContext,
+    ApiPath(key): ApiPath<String>,
+    ApiQuery(query): ApiQuery<LinkQuery>,
+) -> Result<Response, DomainError> {
+    let short_key = ShortKey::new(key)?;
+    authorize_link(&service, &caller, query.domain.as_deref(), &short_key).await?;
+    let url = service.restore(&ctx, query.domain.as_deref(), &short_key).await?;
+    Ok(Json(UrlResponse::new(url)).into_response())
+}
+
+/// Clicks per variant of a split link, for comparing landing pages.
+pub async fn variant_stats(
+    State(service): State<AppState>,
+    caller: Caller,
+    ApiPath(key): ApiPath<String>,
+    ApiQuery(query): ApiQuery<LinkQuery>,
+) -> Result<Response, DomainError> {
+    let short_key = ShortKey::new(key)?;
+    authorize_link(&service, &caller, query.domain.as_deref(), &short_key).await?;
+    let variants = service.variant_clicks(query.domain.as_deref(), &short_key).await?;
+    Ok(Json(variants).into_response())
+}
+
+pub async fn get_audit(
+    State(service): State<AppState>,
+    caller: Caller,
+    ApiQuery(query): ApiQuery<AuditQuery>,
+) -> Result<Response, DomainError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)
+        .clamp(1, MAX_AUDIT_PAGE_SIZE);
+
+    // Audit events carry no workspace, so an API key only sees the changes it made itself
+    let actor = match caller.workspace() {
+        Some(_) => Some(caller.actor()),
+        None => query.actor,
+    };
+
+    let filter = AuditFilter {
+        actor,
+        action: query.action,
+        domain: query.domain.map(|host| normalize_host(&host).unwrap_or(host)),
+        short_key: query.short_key,
+        since: query.since,
+        until: query.until,
+        before_id: query.cursor,
+        limit,
+    };
+
+    let records = service.audit_log(&filter).await?;
+    Ok(Json(AuditPageResponse::new(records, limit)).into_response())
+}
+
+pub async fn import_links(
+    State(service): State<AppState>,
+    ctx: AuditContext,
+    ApiQuery(query): ApiQuery<ImportQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, DomainError> {
+    let format = requested_format(query.format.as_deref(), &headers)?;
+    let chunk_size = query.chunk_size.unwrap_or(DEFAULT_IMPORT_CHUNK_SIZE);
+
+    // Stream the body line by line instead of buffering the whole upload
+    let stream = body.into_data_stream().map_err(std::io::Error::other);
+    let reader = BufReader::new(StreamReader::new(stream));
+
+    let report = service.import_links(&ctx, reader, format, chunk_size).await?;
+    Ok(Json(report).into_response())
+}
+
+pub async fn export(
+    State(service): State<AppState>,
+    ApiQuery(query): ApiQuery<ExportQuery>,
+) -> Result<Response, DomainError> {
+    let format = DataFormat::from_name(query.format.as_deref().unwrap_or("csv"))
+        .ok_or(DomainError::UnsupportedFormat)?;
+    let kind = ExportKind::from_name(query.kind.as_deref().unwrap_or("links"))
+        .ok_or_else(|| DomainError::MalformedInput("kind must be links or clicks".to_string()))?;
+    let workspace = query.workspace.as_deref();
+
+    let lines: BoxStream<'static, Result<String, DomainError>> = match kind {
+        ExportKind::Links => service
+            .export_links(workspace)
+            .map_ok(move |url| link_line(format, &url))
+            .boxed(),
+        ExportKind::Clicks => service
+            .export_clicks(workspace)
+            .map_ok(move |click| click_line(format, &click))
+            .boxed(),
+    };
+    let header = stream::iter(export_header(format, kind).map(Ok));
+
+    // Headers are already sent when a row fails, so the error can only cut the body short.
+    // The body is polled after the request span closes, so keep a handle to log into it.
+    let span = tracing::Span::current();
+    let body = header.chain(lines).map_err(move |e| {
+        span.in_scope(|| tracing::error!(error = %e, "export aborted"));
+        std::io::Error::other(e.to_string())
+    });
+
+    let filename = format!("{}.{format}", kind.as_str());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
@@ -0,0 +1,130 @@
+use crate::repository::MIGRATOR;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Upper bound on each readiness probe, so a hung database fails the check instead of the probe.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Shared readiness state. Clones observe the same draining flag.
+#[derive(Clone)]
+pub struct Health {
+    pool: PgPool,
+    draining: Arc<AtomicBool>,
+}
+
+impl Health {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            draining: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Makes `/readyz` fail from now on so load balancers stop routing here before shutdown.
+    pub fn begin_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    async fn check_database(&self) -> Result<(), String> {
+        match tokio::time::timeout(CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(&self.pool)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("timed out".to_string()),
+        }
+    }
+
+    async fn check_migrations(&self) -> Result<(), String> {
+        let query = sqlx::query_scalar::<_, i64>("SELECT version FROM _sqlx_migrations WHERE success");
+        let applied: HashSet<i64> = match tokio::time::timeout(CHECK_TIMEOUT, query.fetch_all(&self.pool)).await {
+            Ok(Ok(versions)) => versions.into_iter().collect(),
+            Ok(Err(e)) => return Err(e.to_string()),
+            Err(_) => return Err("timed out".to_string()),
+        };
+
+        let pending: Vec<String> = MIGRATOR
+            .iter()
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| format!("{}_{}", migration.version, migration.description))
+            .collect();
+        if pending.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("pending: {}", pending.join(", ")))
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CheckResult {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<Result<(), String>> for CheckResult {
+    fn from(result: Result<(), String>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub draining: bool,
+    pub database: CheckResult,
+    pub migrations: CheckResult,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionResponse {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub built_at: &'static str,
+}
+
+/// Liveness: the process is up and serving. Deliberately checks nothing else,
+/// so a database outage never gets healthy instances restarted.
+pub async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Readiness: the database answers, every embedded migration is applied and the
+/// instance is not draining. Answers 503 with the failing checks otherwise.
+pub async fn readyz(State(health): State<Health>) -> impl IntoResponse {
+    let draining = health.is_draining();
+    let (database, migrations) = tokio::join!(health.check_database(), health.check_migrations());
+    let ready = !draining && database.is_ok() && migrations.is_ok();
+
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = ReadinessResponse {
+        ready,
+        draining,
+        database: database.into(),
+        migrations: migrations.into(),
+    };
+    (status, Json(body))
+}
+
+/// Build information. `GIT_SHA` and `BUILD_TIMESTAMP` are read from the build environment.
+pub async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse {
+        name: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: option_env!("GIT_SHA").unwrap_or("unknown"),
+        built_at: option_env!("BUILD_TIMESTAMP").unwrap_or("unknown"),
+    })
+}
@@ -0,0 +1,58 @@
+use crate::metrics::Metrics;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Route template of the redirect handler, used to tell redirect hits from misses.
+pub const REDIRECT_ROUTE: &str = "/{short_key}";
+
+/// Route template of redirects that forward the rest of the path, counted like `REDIRECT_ROUTE`.
+pub const REDIRECT_PATH_ROUTE: &str = "/{short_key}/{*extra_path}";
+
+/// Route label for requests that matched no route, so scanners can't explode label cardinality.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Outcome label for redirect responses whose status alone doesn't tell, set as a response extension.
+#[derive(Debug, Clone, Copy)]
+pub struct RedirectOutcome(pub &'static str);
+
+const PROMETHEUS_TEXT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Records count and latency per route template, method and status.
+pub async fn track_metrics(State(metrics): State<Arc<Metrics>>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, |path| path.as_str())
+        .to_string();
+    let method = req.method().clone();
+    let started = Instant::now();
+
+    let response = next.run(req).await;
+
+    let status = response.status();
+    metrics.observe_request(&route, method.as_str(), status.as_u16(), started.elapsed().as_secs_f64());
+
+    if (route == REDIRECT_ROUTE || route == REDIRECT_PATH_ROUTE) && method == Method::GET {
+        let outcome = match (response.extensions().get::<RedirectOutcome>(), status) {
+            (Some(RedirectOutcome(outcome)), _) => Some(*outcome),
+            (None, StatusCode::MOVED_PERMANENTLY) => Some("hit"),
+            (None, StatusCode::NOT_FOUND) => Some("miss"),
+            _ => None,
+        };
+        if let Some(outcome) = outcome {
+            metrics.redirects.with_label_values(&[outcome]).inc();
+        }
+    }
+
+    response
+}
+
+pub async fn render_metrics(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, PROMETHEUS_TEXT)], metrics.render())
+}
@@ -1 +1,10 @@
-pub mod handler;
\ No newline at end of file
+pub mod admin;
+pub mod domains;
+pub mod error;
+pub mod extract;
+pub mod handler;
+pub mod health;
+pub mod metrics;
+pub mod pages;
+pub mod request_context;
+pub mod utm_templates;
@@ -0,0 +1,149 @@
+//! Small HTML pages served to people following links in a browser.
+
+use crate::model::{LinkPreview, Warning};
+
+const PAGE_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:24rem;margin:4rem auto;padding:0 1rem}\
+input,button,.button{font:inherit;padding:.4rem;width:100%;box-sizing:border-box;margin-top:.5rem}\
+.button{display:block;text-align:center}\
+.error{color:#b00020}dt{font-weight:600}dd{margin:0 0 .75rem;overflow-wrap:anywhere}code{overflow-wrap:anywhere}";
+
+/// Escapes text for use inside HTML elements and quoted attributes.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether `destination` may go into an `href`. Other schemes (`javascript:`, `data:`, ...) would
+/// run in the page or the browser instead of navigating, so they are only ever shown as text.
+fn is_web_url(destination: &str) -> bool {
+    ::url::Url::parse(destination).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Prompt for a protected link's password. The form posts back to the link itself.
+pub fn password_form(short_key: &str, error: Option<&str>) -> String {
+    let key = escape_html(short_key);
+    let error = error
+        .map(|message| format!(r#"<p class="error" role="alert">{}</p>"#, escape_html(message)))
+        .unwrap_or_default();
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1">
+<meta name="robots" content="noindex"><title>Password required</title><style>{PAGE_STYLE}</style></head>
+<body>
+<h1>Password required</h1>
+<p>This link is protected. Enter its password to continue.</p>
+{error}<form method="post" action="/{key}">
+<label for="password">Password</label>
+<input id="password" name="password" type="password" autocomplete="current-password" required autofocus>
+<button type="submit">Continue</button>
+</form>
+</body>
+</html>
+"#
+    )
+}
+
+/// Where a link goes, for visitors who add `+` to it before following it.
+pub fn preview_page(preview: &LinkPreview) -> String {
+    let short_url = escape_html(&preview.short_url);
+    let destination = match &preview.destination {
+        Some(destination) if is_web_url(destination) => {
+            let destination = escape_html(destination);
+            format!(r#"<a href="{destination}" rel="nofollow noopener noreferrer">{destination}</a>"#)
+        }
+        Some(destination) => format!("<code>{}</code>", escape_html(destination)),
+        None => "Hidden: this link is password protected".to_string(),
+    };
+    let routed = if preview.routed {
+        "<p>Some visitors are sent elsewhere, depending on their device, their country or a traffic split.</p>\n"
+    } else {
+        ""
+    };
+    let created = preview.created_at.format("%Y-%m-%d");
+    let owner = preview
+        .owner
+        .as_deref()
+        .map(|owner| format!("<dt>Owner</dt><dd>{}</dd>\n", escape_html(owner)))
+        .unwrap_or_default();
+    let clicks = preview.clicks.map(|clicks| format!("<dt>Clicks</dt><dd>{clicks}</dd>\n")).unwrap_or_default();
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1">
+<meta name="robots" content="noindex"><title>Link preview</title><style>{PAGE_STYLE}</style></head>
+<body>
+<h1>Link preview</h1>
+<dl>
+<dt>Short link</dt><dd>{short_url}</dd>
+<dt>Destination</dt><dd>{destination}</dd>
+<dt>Created</dt><dd>{created}</dd>
+{owner}{clicks}</dl>
+{routed}<p><a href="{short_url}" rel="nofollow">Continue to the link</a></p>
+</body>
+</html>
+"#
+    )
+}
+
+/// Warning shown before sending a visitor on to `destination`, with a button to continue. Pages
+/// with a countdown continue by themselves; flagged destinations always wait for the button.
+/// Destinations that aren't web URLs get neither, only their text.
+pub fn interstitial_page(destination: &str, warning: Warning) -> String {
+    let host = ::url::Url::parse(destination)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| destination.to_string());
+    let host = escape_html(&host);
+    let href = escape_html(destination);
+    let linkable = is_web_url(destination);
+    let (title, message, refresh, countdown) = match warning {
+        Warning::Leaving(interstitial) if linkable && interstitial.countdown > 0 => (
+            "You are leaving",
+            format!("This link takes you to <strong>{host}</strong>."),
+            format!(r#"<meta http-equiv="refresh" content="{};url={href}">"#, interstitial.countdown),
+            format!("<p>Continuing in {} seconds.</p>\n", interstitial.countdown),
+        ),
+        Warning::Leaving(_) => (
+            "You are leaving",
+            format!("This link takes you to <strong>{host}</strong>."),
+            String::new(),
+            String::new(),
+        ),
+        Warning::Flagged => (
+            "Check this destination",
+            format!("This link takes you to <strong>{host}</strong>, which is on our warning list. Only continue if you trust it."),
+            String::new(),
+            String::new(),
+        ),
+    };
+    let button = if linkable {
+        format!(r#"<a class="button" href="{href}" rel="nofollow noopener noreferrer">Continue to {host}</a>"#)
+    } else {
+        "<p>This destination can't be opened from here.</p>".to_string()
+    };
+    format!(
+        r#"<!doctype html>
+<html lang="en">
+<head><meta charset="utf-8"><meta name="viewport" content="width=device-width,initial-scale=1">
+<meta name="robots" content="noindex">{refresh}<title>{title}</title><style>{PAGE_STYLE}</style></head>
+<body>
+<h1>{title}</h1>
+<p>{message}</p>
+<p><code>{href}</code></p>
+{countdown}{button}
+</body>
+</html>
+"#
+    )
+}
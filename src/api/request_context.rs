@@ -0,0 +1,265 @@
+use crate::api::admin::Caller;
+use crate::geo::GeoLocator;
+use crate::model::{AuditContext, Platform, Visit};
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, MatchedPath, RawPathParams, Request},
+    http::{header, request::Parts, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::field::Empty;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Actor recorded for requests that present no credentials.
+pub const ANONYMOUS_ACTOR: &str = "anonymous";
+
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// W3C trace context (`traceparent`) of the request being handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    pub trace_id: String,
+    pub parent_id: String,
+    pub flags: String,
+}
+
+fn is_lower_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+impl TraceParent {
+    /// Parses `version-traceid-parentid-flags`; all-zero ids and version `ff` are invalid.
+    pub fn parse(header: &str) -> Option<Self> {
+        let mut parts = header.trim().split('-');
+        let (version, trace_id, parent_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+
+        // Version 00 has exactly four fields; later versions may append more
+        if version == "00" && parts.next().is_some() {
+            return None;
+        }
+        let valid = is_lower_hex(version, 2)
+            && version != "ff"
+            && is_lower_hex(trace_id, 32)
+            && trace_id.bytes().any(|b| b != b'0')
+            && is_lower_hex(parent_id, 16)
+            && parent_id.bytes().any(|b| b != b'0')
+            && is_lower_hex(flags, 2);
+
+        valid.then(|| Self {
+            trace_id: trace_id.to_string(),
+            parent_id: parent_id.to_string(),
+            flags: flags.to_string(),
+        })
+    }
+
+    /// Starts a new sampled trace for requests that arrive without a usable `traceparent`.
+    pub fn generate() -> Self {
+        Self {
+            trace_id: hex::encode(rand::random::<[u8; 16]>()),
+            parent_id: hex::encode(rand::random::<[u8; 8]>()),
+            flags: "01".to_string(),
+        }
+    }
+
+    pub fn to_header(&self) -> String {
+        format!("00-{}-{}-{}", self.trace_id, self.parent_id, self.flags)
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// Request id of the request being handled on this task, if the middleware is installed.
+/// Lets error responses carry the id without threading it through every handler.
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn is_acceptable_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_graphic())
+}
+
+fn generate_request_id() -> String {
+    thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(20)
+        .map(char::from)
+        .collect()
+}
+
+/// Middleware that reuses the caller's `X-Request-Id` and `traceparent` (or mints them),
+/// exposes them to handlers as extensions, runs the request inside a `request` span
+/// and echoes both headers back.
+pub async fn request_id(req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_acceptable_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(generate_request_id);
+    let trace = req
+        .headers()
+        .get(TRACEPARENT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TraceParent::parse)
+        .unwrap_or_else(TraceParent::generate);
+
+    let (mut parts, body) = req.into_parts();
+    let route = parts.extensions.get::<MatchedPath>().map_or("unmatched", |path| path.as_str()).to_string();
+    let short_key = match RawPathParams::from_request_parts(&mut parts, &()).await {
+        Ok(params) => params.iter().find(|(name, _)| *name == "short_key").map(|(_, value)| value.to_string()),
+        Err(_) => None,
+    };
+    let mut req = Request::from_parts(parts, body);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        trace_id = %trace.trace_id,
+        method = %req.method(),
+        route = %route,
+        short_key = short_key.as_deref(),
+        status = Empty,
+        latency_ms = Empty,
+        otel.name = Empty,
+    );
+    #[cfg(feature = "otlp")]
+    {
+        span.record("otel.name", format!("{} {route}", req.method()));
+        crate::telemetry::set_remote_parent(&span, &trace.to_header());
+    }
+
+    req.extensions_mut().insert(RequestId(id.clone()));
+    req.extensions_mut().insert(trace.clone());
+    let started = Instant::now();
+    let mut response = CURRENT_REQUEST_ID
+        .scope(id.clone(), next.run(req))
+        .instrument(span.clone())
+        .await;
+
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", started.elapsed().as_secs_f64() * 1000.0);
+    span.in_scope(|| tracing::info!("request completed"));
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&trace.to_header()) {
+        response.headers_mut().insert(TRACEPARENT_HEADER, value);
+    }
+    response
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = parts
+            .extensions
+            .get::<Caller>()
+            .map_or_else(|| ANONYMOUS_ACTOR.to_string(), Caller::actor);
+
+        // Prefer the id chosen by the middleware; fall back to the raw header when it is not installed
+        let request_id = match parts.extensions.get::<RequestId>() {
+            Some(RequestId(id)) => Some(id.clone()),
+            None => parts
+                .headers
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .filter(|id| is_acceptable_id(id))
+                .map(str::to_string),
+        };
+
+        Ok(AuditContext::new(actor, request_id))
+    }
+}
+
+/// Host the client addressed: the `Host` header, or the URI authority for HTTP/2.
+#[derive(Debug, Clone)]
+pub struct RequestHost(pub Option<String>);
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestHost {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let host = parts
+            .headers
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| parts.uri.authority().map(|authority| authority.as_str()))
+            .map(str::to_string);
+        Ok(RequestHost(host))
+    }
+}
+
+/// Cookie holding a protected link's unlock token, scoped to the link's path.
+pub const UNLOCK_COOKIE: &str = "rs_unlock";
+
+/// Cookie holding the visitor id that keeps split links on the same variant, for every
+/// link on the domain.
+pub const VISITOR_COOKIE: &str = "rs_vid";
+
+/// How long a visitor keeps their variants: a year.
+pub const VISITOR_COOKIE_MAX_AGE: i64 = 365 * 24 * 60 * 60;
+
+/// A fresh random visitor id for `VISITOR_COOKIE`.
+pub fn new_visitor_id() -> String {
+    thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(22)
+        .map(char::from)
+        .collect()
+}
+
+// Ids are only hashed, but a cookie we'd never have issued is treated as no cookie at all
+fn is_visitor_id(value: &str) -> bool {
+    (1..=64).contains(&value.len()) && value.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// First value of the cookie `name` in the request's `Cookie` headers.
+pub fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Visit {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let RequestHost(host) = RequestHost::from_request_parts(parts, state).await?;
+        Ok(Visit {
+            host,
+            unlock_token: cookie_value(&parts.headers, UNLOCK_COOKIE),
+            platform: parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .and_then(Platform::from_user_agent),
+            country: parts.extensions.get::<Arc<GeoLocator>>().and_then(|geo| {
+                let client_ip = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+                geo.country(&parts.headers, client_ip)
+            }),
+            visitor_id: cookie_value(&parts.headers, VISITOR_COOKIE).filter(|id| is_visitor_id(id)),
+            query: parts.uri.query().map(str::to_string),
+            extra_path: None,
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use crate::api::extract::{ApiJson, ApiPath, ApiQuery};
+use crate::model::{DomainError, Utm};
+use crate::service::utm_template_service::UtmTemplateService;
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct SaveUtmTemplateReq {
+    /// Owning workspace; the default workspace when omitted.
+    pub workspace: Option<String>,
+    #[serde(flatten)]
+    pub utm: Utm,
+}
+
+#[derive(Deserialize)]
+pub struct UtmTemplateQuery {
+    pub workspace: Option<String>,
+}
+
+pub async fn save_utm_template(
+    State(templates): State<Arc<UtmTemplateService>>,
+    ApiPath(name): ApiPath<String>,
+    ApiJson(body): ApiJson<SaveUtmTemplateReq>,
+) -> Result<Response, DomainError> {
+    let template = templates.save(body.workspace.as_deref(), &name, body.utm).await?;
+    Ok(Json(template).into_response())
+}
+
+pub async fn list_utm_templates(
+    State(templates): State<Arc<UtmTemplateService>>,
+    ApiQuery(query): ApiQuery<UtmTemplateQuery>,
+) -> Result<Response, DomainError> {
+    Ok(Json(templates.list(query.workspace.as_deref()).await?).into_response())
+}
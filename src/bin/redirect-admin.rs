@@ -0,0 +1,537 @@
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use redirect_service::model::export::{click_line, export_header, link_line};
+use redirect_service::model::domain::{normalize_host, DEFAULT_DOMAIN};
+use redirect_service::model::import::DEFAULT_IMPORT_CHUNK_SIZE;
+use redirect_service::model::url::DEFAULT_WORKSPACE;
+use redirect_service::model::{
+    AuditContext, DataFormat, DomainError, DomainFallbacks, ExportKind, Interstitial, NewLink, PlatformRoutes, QueryPolicy,
+    ShortKey, Utm, Url,
+};
+use redirect_service::repository::api_key_repository::PostgresApiKeyRepository;
+use redirect_service::repository::click_repository::PostgresClickRepository;
+use redirect_service::repository::domain_repository::PostgresDomainRepository;
+use redirect_service::repository::url_repository::PostgresUrlRepository;
+use redirect_service::repository::utm_template_repository::PostgresUtmTemplateRepository;
+use redirect_service::repository::migrate;
+use redirect_service::service::api_key_service::ApiKeyService;
+use redirect_service::service::domain_service::DomainService;
+use redirect_service::service::utm_template_service::UtmTemplateService;
+use redirect_service::service::url_service::UrlService;
+use serde_json::json;
+use sqlx::PgPool;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+
+/// Operational tooling that talks to `DATABASE_URL` directly.
+#[derive(Parser)]
+#[command(name = "redirect-admin", version)]
+struct Cli {
+    /// Actor recorded in the audit log for every change this command makes
+    #[arg(long, global = true, default_value = "redirect-admin")]
+    actor: String,
+
+    /// Domain of the link to create or manage; `DEFAULT_DOMAIN` when omitted
+    #[arg(long, global = true)]
+    domain: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Shorten a URL, or return the existing link for it
+    Create(Box<CreateArgs>),
+
+    /// Show a link, including deleted ones
+    Lookup { short_key: String },
+
+    /// Point an existing link at a new destination
+    Retarget { short_key: String, url: String },
+
+    /// Soft-delete a link
+    Delete { short_key: String },
+
+    /// Bring back a deleted link
+    Restore { short_key: String },
+
+    /// Import key -> URL mappings from CSV or JSON Lines, preserving keys and timestamps
+    Import {
+        /// File to read, or `-` for stdin
+        path: PathBuf,
+
+        /// csv or jsonl; inferred from the file extension when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// Rows committed per transaction, at most 10000
+        #[arg(long, default_value_t = DEFAULT_IMPORT_CHUNK_SIZE)]
+        chunk_size: usize,
+    },
+
+    /// Export live links or recorded clicks as CSV or JSON Lines
+    Export {
+        /// File to write, or `-` for stdout
+        #[arg(default_value = "-")]
+        path: PathBuf,
+
+        /// csv or jsonl; inferred from the file extension when omitted
+        #[arg(long)]
+        format: Option<String>,
+
+        /// links or clicks
+        #[arg(long, default_value = "links")]
+        kind: String,
+
+        #[arg(long)]
+        workspace: Option<String>,
+    },
+
+    /// Create an API key for a workspace and print its secret once
+    MintKey {
+        #[arg(long, default_value = DEFAULT_WORKSPACE)]
+        workspace: String,
+
+        /// Label to tell keys apart, e.g. "ci" or "marketing-site"
+        #[arg(long)]
+        name: String,
+    },
+
+    /// List API keys, newest first
+    ListKeys {
+        #[arg(long)]
+        workspace: Option<String>,
+    },
+
+    /// Revoke an API key by id
+    RevokeKey { id: i64 },
+
+    /// Register a short domain links can be created on
+    AddDomain {
+        host: String,
+
+        /// Workspace that owns the domain; shared with every workspace when omitted
+        #[arg(long)]
+        workspace: Option<String>,
+    },
+
+    /// List registered domains
+    ListDomains,
+
+    /// Set what a domain answers for its root and for unknown keys; omitted options are cleared
+    SetFallbacks {
+        host: String,
+
+        /// Where the bare domain redirects to
+        #[arg(long)]
+        root_url: Option<String>,
+
+        /// Where unknown keys redirect to, with the key appended as `?key=`
+        #[arg(long)]
+        not_found_url: Option<String>,
+
+        /// HTML file served with a 404 for unknown keys
+        #[arg(long)]
+        not_found_page: Option<PathBuf>,
+    },
+
+    /// Show a warning page before every link on a domain that doesn't set its own
+    SetInterstitial {
+        host: String,
+
+        /// Continue to the destination after this many seconds; without it visitors click through
+        #[arg(long)]
+        countdown: Option<u32>,
+
+        /// Go back to redirecting straight away
+        #[arg(long, conflicts_with = "countdown")]
+        off: bool,
+    },
+
+    /// Create or replace a workspace's UTM template; links already tagged with it don't change
+    SetUtmTemplate {
+        name: String,
+
+        #[arg(long)]
+        workspace: Option<String>,
+
+        #[arg(long)]
+        source: Option<String>,
+
+        #[arg(long)]
+        medium: Option<String>,
+
+        #[arg(long)]
+        campaign: Option<String>,
+
+        #[arg(long)]
+        term: Option<String>,
+
+        #[arg(long)]
+        content: Option<String>,
+    },
+
+    /// List a workspace's UTM templates
+    ListUtmTemplates {
+        #[arg(long)]
+        workspace: Option<String>,
+    },
+
+    /// Apply pending database migrations
+    Migrate,
+}
+
+#[derive(Args)]
+struct CreateArgs {
+    url: String,
+
+    #[arg(long, default_value = DEFAULT_WORKSPACE)]
+    workspace: String,
+
+    /// Password visitors must enter before being redirected
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Redirects allowed before the link stops working; 1 makes a one-time link
+    #[arg(long)]
+    max_clicks: Option<u32>,
+
+    /// Where visitors go once the clicks are used up, instead of a 410
+    #[arg(long)]
+    exhausted_url: Option<String>,
+
+    /// RFC 3339 time the link starts redirecting, e.g. 2026-11-01T09:00:00Z
+    #[arg(long)]
+    active_from: Option<DateTime<Utc>>,
+
+    /// RFC 3339 time the link stops redirecting
+    #[arg(long)]
+    active_until: Option<DateTime<Utc>>,
+
+    /// Where visitors go before --active-from, instead of a 404
+    #[arg(long)]
+    not_yet_active_url: Option<String>,
+
+    /// Where visitors go from --active-until on, instead of a 410
+    #[arg(long)]
+    ended_url: Option<String>,
+
+    /// Destination for visitors on iPhone, iPad or iPod, e.g. an App Store page
+    #[arg(long)]
+    ios_url: Option<String>,
+
+    /// Destination for visitors on Android, e.g. a Play Store page
+    #[arg(long)]
+    android_url: Option<String>,
+
+    /// Destination for visitors on Windows, macOS, Linux or ChromeOS
+    #[arg(long)]
+    desktop_url: Option<String>,
+
+    /// Destination for visitors from one country, as CC=URL, e.g. DE=https://example.de; repeatable
+    #[arg(long = "country-url", value_parser = country_route)]
+    country_urls: Vec<(String, String)>,
+
+    /// Split-traffic destination, as WEIGHT=URL, e.g. 70=https://example.com/a; repeat for each variant
+    #[arg(long = "variant", value_parser = weighted_variant)]
+    variants: Vec<(u32, String)>,
+
+    /// Merge the visitor's query string into the destination: keep, replace or append on conflicts
+    #[arg(long)]
+    forward_query: Option<QueryPolicy>,
+
+    /// Also answer /KEY/more/path, appending the extra path to the destination
+    #[arg(long)]
+    forward_path: bool,
+
+    /// Show a warning page first, continuing after SECS seconds; 0 waits for the visitor
+    #[arg(long, value_name = "SECS")]
+    interstitial: Option<u32>,
+
+    /// utm_source to tag the destination with
+    #[arg(long)]
+    utm_source: Option<String>,
+
+    /// utm_medium to tag the destination with
+    #[arg(long)]
+    utm_medium: Option<String>,
+
+    /// utm_campaign to tag the destination with
+    #[arg(long)]
+    utm_campaign: Option<String>,
+
+    /// utm_term to tag the destination with
+    #[arg(long)]
+    utm_term: Option<String>,
+
+    /// utm_content to tag the destination with
+    #[arg(long)]
+    utm_content: Option<String>,
+
+    /// Workspace UTM template filling in the parameters not given above
+    #[arg(long)]
+    utm_template: Option<String>,
+}
+
+fn country_route(arg: &str) -> Result<(String, String), String> {
+    let (country, url) = arg.split_once('=').ok_or("expected CC=URL")?;
+    Ok((country.to_ascii_uppercase(), url.to_string()))
+}
+
+fn weighted_variant(arg: &str) -> Result<(u32, String), String> {
+    let (weight, url) = arg.split_once('=').ok_or("expected WEIGHT=URL")?;
+    let weight = weight.parse().map_err(|_| format!("invalid weight {weight:?}"))?;
+    Ok((weight, url.to_string()))
+}
+
+fn data_format(path: &Path, format: Option<&str>) -> Result<DataFormat, String> {
+    let name = match format {
+        Some(name) => name.to_string(),
+        None => path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_string)
+            .ok_or("cannot infer format, pass --format csv|jsonl")?,
+    };
+    DataFormat::from_name(&name).ok_or_else(|| format!("unsupported format {name:?}"))
+}
+
+async fn open_input(path: &Path) -> std::io::Result<Box<dyn AsyncBufRead + Unpin + Send>> {
+    if path.as_os_str() == "-" {
+        Ok(Box::new(BufReader::new(tokio::io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(tokio::fs::File::open(path).await?)))
+    }
+}
+
+async fn open_output(path: &Path) -> std::io::Result<Box<dyn AsyncWrite + Unpin + Send>> {
+    if path.as_os_str() == "-" {
+        Ok(Box::new(BufWriter::new(tokio::io::stdout())))
+    } else {
+        Ok(Box::new(BufWriter::new(tokio::fs::File::create(path).await?)))
+    }
+}
+
+fn link_json(url: &Url) -> serde_json::Value {
+    json!({
+        "domain": url.domain,
+        "shortKey": url.short_key.as_str(),
+        "originalUrl": url.original_url,
+        "workspaceId": url.workspace_id,
+        "createdAt": url.created_at,
+        "deletedAt": url.deleted_at,
+        "passwordProtected": url.is_protected(),
+        "maxClicks": url.click_limit.as_ref().map(|limit| limit.max_clicks),
+        "clicksRemaining": url.click_limit.as_ref().map(|limit| limit.remaining),
+        "activeFrom": url.active_window.as_ref().and_then(|window| window.active_from),
+        "activeUntil": url.active_window.as_ref().and_then(|window| window.active_until),
+        "platformUrls": url.platform_routes,
+        "countryUrls": url.country_routes,
+        "variants": url.variants,
+        "forwardQuery": url.forward_query,
+        "forwardPath": url.forward_path,
+        "interstitial": url.interstitial,
+    })
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+
+    let database_connect = std::env::var("DATABASE_URL").expect("DATABASE_URL must be in .env file");
+    let pool = PgPool::connect(&database_connect).await?;
+    // Normalized the way the server does it, so `migrate` places existing links on the same host
+    let default_domain = match std::env::var("DEFAULT_DOMAIN") {
+        Ok(host) => normalize_host(&host).ok_or_else(|| format!("DEFAULT_DOMAIN must be a host name, got {host:?}"))?,
+        Err(_) => DEFAULT_DOMAIN.to_string(),
+    };
+    let service = UrlService::new(PostgresUrlRepository::new(pool.clone()))
+        .with_click_log(PostgresClickRepository::new(pool.clone()))
+        .with_domains(PostgresDomainRepository::new(pool.clone()), default_domain.clone())
+        .with_utm_templates(PostgresUtmTemplateRepository::new(pool.clone()));
+    let keys = ApiKeyService::new(PostgresApiKeyRepository::new(pool.clone()));
+    let domains = DomainService::new(PostgresDomainRepository::new(pool.clone()));
+    let utm_templates = UtmTemplateService::new(PostgresUtmTemplateRepository::new(pool.clone()));
+    let ctx = AuditContext::new(cli.actor, None);
+    let domain = cli.domain.as_deref();
+
+    match cli.command {
+        Command::Create(args) => {
+            let CreateArgs {
+                url,
+                workspace,
+                password,
+                max_clicks,
+                exhausted_url,
+                active_from,
+                active_until,
+                not_yet_active_url,
+                ended_url,
+                ios_url,
+                android_url,
+                desktop_url,
+                country_urls,
+                variants,
+                forward_query,
+                forward_path,
+                interstitial,
+                utm_source,
+                utm_medium,
+                utm_campaign,
+                utm_term,
+                utm_content,
+                utm_template,
+            } = *args;
+            let mut link = NewLink::new(url).in_workspace(workspace);
+            if let Some(domain) = domain {
+                link = link.on_domain(domain);
+            }
+            if let Some(password) = password {
+                link = link.with_password(password);
+            }
+            if let Some(max_clicks) = max_clicks {
+                link = link.with_max_clicks(max_clicks);
+            }
+            if let Some(exhausted_url) = exhausted_url {
+                link = link.with_exhausted_url(exhausted_url);
+            }
+            if let Some(active_from) = active_from {
+                link = link.active_from(active_from);
+            }
+            if let Some(active_until) = active_until {
+                link = link.active_until(active_until);
+            }
+            if let Some(not_yet_active_url) = not_yet_active_url {
+                link = link.with_not_yet_active_url(not_yet_active_url);
+            }
+            if let Some(ended_url) = ended_url {
+                link = link.with_ended_url(ended_url);
+            }
+            link.platform_routes = PlatformRoutes {
+                ios: ios_url,
+                android: android_url,
+                desktop: desktop_url,
+            };
+            for (country, url) in country_urls {
+                link = link.route_country(country, url);
+            }
+            for (weight, url) in variants {
+                link = link.with_variant(url, weight);
+            }
+            link.forward_query = forward_query;
+            link.forward_path = forward_path;
+            link.interstitial = interstitial.map(Interstitial::new);
+            link.utm = Utm {
+                source: utm_source,
+                medium: utm_medium,
+                campaign: utm_campaign,
+                term: utm_term,
+                content: utm_content,
+            };
+            link.utm_template = utm_template;
+            let url = service.shorten_link(&ctx, &link).await?;
+            print_json(&link_json(&url))?;
+        }
+        Command::Lookup { short_key } => {
+            let url = service.lookup(domain, &ShortKey::new(short_key)?).await?;
+            print_json(&link_json(&url))?;
+        }
+        Command::Retarget { short_key, url } => {
+            let url = service.retarget(&ctx, domain, &ShortKey::new(short_key)?, &url).await?;
+            print_json(&link_json(&url))?;
+        }
+        Command::Delete { short_key } => {
+            let short_key = ShortKey::new(short_key)?;
+            service.delete(&ctx, domain, &short_key).await?;
+            println!("deleted {}", short_key.as_str());
+        }
+        Command::Restore { short_key } => {
+            let url = service.restore(&ctx, domain, &ShortKey::new(short_key)?).await?;
+            print_json(&link_json(&url))?;
+        }
+        Command::Import { path, format, chunk_size } => {
+            let format = data_format(&path, format.as_deref())?;
+            let reader = open_input(&path).await?;
+            let report = service.import_links(&ctx, reader, format, chunk_size).await?;
+            print_json(&report)?;
+        }
+        Command::Export { path, format, kind, workspace } => {
+            let format = match format {
+                Some(name) => data_format(&path, Some(&name))?,
+                None if path.as_os_str() == "-" => DataFormat::Csv,
+                None => data_format(&path, None)?,
+            };
+            let kind = ExportKind::from_name(&kind).ok_or_else(|| format!("unsupported kind {kind:?}"))?;
+            let workspace = workspace.as_deref();
+            let mut lines: BoxStream<'static, Result<String, DomainError>> = match kind {
+                ExportKind::Links => service
+                    .export_links(workspace)
+                    .map_ok(move |url| link_line(format, &url))
+                    .boxed(),
+                ExportKind::Clicks => service
+                    .export_clicks(workspace)
+                    .map_ok(move |click| click_line(format, &click))
+                    .boxed(),
+            };
+
+            let mut out = open_output(&path).await?;
+            if let Some(header) = export_header(format, kind) {
+                out.write_all(header.as_bytes()).await?;
+            }
+            while let Some(line) = lines.try_next().await? {
+                out.write_all(line.as_bytes()).await?;
+            }
+            out.flush().await?;
+        }
+        Command::MintKey { workspace, name } => {
+            let minted = keys.mint(&workspace, &name).await?;
+            print_json(&minted)?;
+            eprintln!("Store the secret now; it cannot be shown again.");
+        }
+        Command::ListKeys { workspace } => {
+            print_json(&keys.list(workspace.as_deref()).await?)?;
+        }
+        Command::RevokeKey { id } => {
+            print_json(&keys.revoke(&ctx, id).await?)?;
+        }
+        Command::AddDomain { host, workspace } => {
+            print_json(&domains.register(&host, workspace.as_deref()).await?)?;
+        }
+        Command::ListDomains => {
+            print_json(&domains.list().await?)?;
+        }
+        Command::SetFallbacks { host, root_url, not_found_url, not_found_page } => {
+            let not_found_page = match not_found_page {
+                Some(path) => Some(tokio::fs::read_to_string(&path).await?),
+                None => None,
+            };
+            let fallbacks = DomainFallbacks { root_url, not_found_url, not_found_page };
+            print_json(&domains.set_fallbacks(&host, &fallbacks).await?)?;
+        }
+        Command::SetInterstitial { host, countdown, off } => {
+            let interstitial = (!off).then(|| Interstitial::new(countdown.unwrap_or(0)));
+            print_json(&domains.set_interstitial(&host, interstitial).await?)?;
+        }
+        Command::SetUtmTemplate { name, workspace, source, medium, campaign, term, content } => {
+            let utm = Utm { source, medium, campaign, term, content };
+            print_json(&utm_templates.save(workspace.as_deref(), &name, utm).await?)?;
+        }
+        Command::ListUtmTemplates { workspace } => {
+            print_json(&utm_templates.list(workspace.as_deref()).await?)?;
+        }
+        Command::Migrate => {
+            migrate(&pool, &default_domain).await?;
+            println!("migrations up to date");
+        }
+    }
+
+    Ok(())
+}
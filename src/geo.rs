@@ -0,0 +1,50 @@
+use crate::model::country::normalize_country;
+use axum::http::{HeaderMap, HeaderName};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Works out which country a visitor is in, from a header set by a trusted proxy or CDN
+/// (such as Cloudflare's `CF-IPCountry`), or else by looking the client address up in a
+/// MaxMind-format database loaded at startup. Without either, every country is unknown.
+#[derive(Default)]
+pub struct GeoLocator {
+    database: Option<Reader<Vec<u8>>>,
+    country_header: Option<HeaderName>,
+}
+
+impl GeoLocator {
+    /// Loads a GeoIP2 or GeoLite2 Country (or City) database file into memory.
+    pub fn with_database(mut self, path: impl AsRef<Path>) -> Result<Self, MaxMindDBError> {
+        self.database = Some(Reader::open_readfile(path)?);
+        Ok(self)
+    }
+
+    /// Trusts `header` to carry the visitor's country code. Only set this when a proxy in
+    /// front of the service overwrites the header, or visitors can pick their own country.
+    pub fn with_country_header(mut self, header: HeaderName) -> Self {
+        self.country_header = Some(header);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.database.is_some() || self.country_header.is_some()
+    }
+
+    /// The visitor's country code; the trusted header wins over the database.
+    pub fn country(&self, headers: &HeaderMap, client_ip: Option<IpAddr>) -> Option<String> {
+        let from_header = self
+            .country_header
+            .as_ref()
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(normalize_country);
+        from_header.or_else(|| client_ip.and_then(|ip| self.lookup(ip)))
+    }
+
+    /// Looks an address up in the database. Addresses the database doesn't cover are `None`.
+    pub fn lookup(&self, ip: IpAddr) -> Option<String> {
+        let record: geoip2::Country = self.database.as_ref()?.lookup(ip.to_canonical()).ok()?;
+        record.country?.iso_code.and_then(normalize_country)
+    }
+}
@@ -1,5 +1,8 @@
-
 pub mod api;
+pub mod geo;
+pub mod metrics;
 pub mod model;
 pub mod repository;
-pub mod service;
\ No newline at end of file
+pub mod service;
+pub mod shutdown;
+pub mod telemetry;
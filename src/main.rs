@@ -1,34 +1,227 @@
-mod api;
-mod model;
-mod repository;
-mod service;
-
-use api::handler::{redirect_to_url, shorten_url};
-use axum::{routing::{get, post}, Router};
-use repository::url_repository::PostgresUrlRepository;
-use service::url_service::UrlService;
+use axum::http::HeaderName;
+use axum::{middleware, routing::{get, patch, post, put}, Extension, Router};
+use redirect_service::api::handler::{
+    delete_url, export, get_audit, get_usage, import_links, qr_code, redirect_root, redirect_to_url, redirect_with_path, restore_url,
+    retarget_url, shorten_batch, shorten_url, unlock_url, variant_stats,
+};
+use redirect_service::api::admin::{identify_caller, require_admin, require_credentials, AdminToken, Credentials};
+use redirect_service::api::domains::{
+    clear_domain_interstitial, list_domains, register_domain, set_domain_fallbacks, set_domain_interstitial,
+};
+use redirect_service::api::utm_templates::{list_utm_templates, save_utm_template};
+use redirect_service::api::health::{healthz, readyz, version, Health};
+use redirect_service::api::metrics::{render_metrics, track_metrics};
+use redirect_service::api::request_context::request_id;
+use redirect_service::geo::GeoLocator;
+use redirect_service::metrics::Metrics;
+use redirect_service::model::domain::{normalize_host, DEFAULT_DOMAIN};
+use redirect_service::model::{QuotaLimits, WarningList};
+use redirect_service::repository::api_key_repository::PostgresApiKeyRepository;
+use redirect_service::repository::audit_repository::PostgresAuditRepository;
+use redirect_service::repository::buffered_click_repository::BufferedClickRepository;
+use redirect_service::repository::click_repository::PostgresClickRepository;
+use redirect_service::repository::domain_repository::PostgresDomainRepository;
+use redirect_service::repository::utm_template_repository::PostgresUtmTemplateRepository;
+use redirect_service::repository::metered_url_repository::MeteredUrlRepository;
+use redirect_service::repository::url_repository::PostgresUrlRepository;
+use redirect_service::repository::migrate;
+use redirect_service::repository::usage_repository::PostgresUsageRepository;
+use redirect_service::service::api_key_service::ApiKeyService;
+use redirect_service::service::domain_service::DomainService;
+use redirect_service::service::utm_template_service::UtmTemplateService;
+use redirect_service::service::url_service::{UrlService, DEFAULT_BATCH_LIMIT};
+use redirect_service::shutdown::{termination_signal, ShutdownCoordinator};
+use redirect_service::telemetry::{init_tracing, LogFormat};
 use sqlx::PgPool;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// How long `/readyz` reports draining before the listener stops, unless `SHUTDOWN_GRACE_SECONDS` says otherwise.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// How long in-flight requests, then background workers, get to finish once the listener
+/// stops, unless `SHUTDOWN_DRAIN_TIMEOUT_SECONDS` says otherwise.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reads an optional integer limit; an unset variable means unlimited.
+fn env_limit(name: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    match std::env::var(name) {
+        Ok(value) => Ok(Some(value.parse()?)),
+        Err(_) => Ok(None),
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv::dotenv().ok();
 
+    let log_format = match std::env::var("LOG_FORMAT") {
+        Ok(name) => LogFormat::from_name(&name).ok_or_else(|| format!("LOG_FORMAT must be json or pretty, got {name:?}"))?,
+        Err(_) => LogFormat::Pretty,
+    };
+    let otlp_endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok();
+    let telemetry = init_tracing(log_format, otlp_endpoint.as_deref());
+
     let database_connect = std::env::var("DATABASE_URL").expect("DATABASE_URL must be in .env file");
     let pool = PgPool::connect(&database_connect).await?;
-    sqlx::migrate!("./migrations").run(&pool).await?;
 
-    let repo = PostgresUrlRepository::new(pool);
-    let service = Arc::new(UrlService::new(repo));
+    let default_domain = match std::env::var("DEFAULT_DOMAIN") {
+        Ok(host) => normalize_host(&host).ok_or_else(|| format!("DEFAULT_DOMAIN must be a host name, got {host:?}"))?,
+        Err(_) => DEFAULT_DOMAIN.to_string(),
+    };
+    migrate(&pool, &default_domain).await?;
+
+    let limits = QuotaLimits {
+        max_links: env_limit("QUOTA_MAX_LINKS_PER_MONTH")?,
+        max_clicks: env_limit("QUOTA_MAX_CLICKS_PER_MONTH")?,
+    };
+
+    let admin_token = AdminToken::new(std::env::var("ADMIN_TOKEN").ok());
+    let batch_limit = env_limit("BATCH_SHORTEN_LIMIT")?.map_or(DEFAULT_BATCH_LIMIT, |limit| limit.max(0) as usize);
+
+    let domains = Arc::new(DomainService::new(PostgresDomainRepository::new(pool.clone())));
+    domains.ensure(&default_domain).await?;
+
+    let metrics = Arc::new(Metrics::new().with_pool(pool.clone()));
+
+    let repo = MeteredUrlRepository::new(PostgresUrlRepository::new(pool.clone()), metrics.clone());
+    let usage_repo = PostgresUsageRepository::new(pool.clone());
+    let audit_repo = PostgresAuditRepository::new(pool.clone());
+    let mut workers = ShutdownCoordinator::new();
+    let (click_repo, click_writer) = BufferedClickRepository::spawn(PostgresClickRepository::new(pool.clone()), workers.token());
+    workers.register("click writer", click_writer);
+    let mut service = UrlService::new(repo)
+        .with_quota(usage_repo, limits)
+        .with_audit_log(audit_repo)
+        .with_click_log(click_repo)
+        .with_domains(PostgresDomainRepository::new(pool.clone()), default_domain)
+        .with_utm_templates(PostgresUtmTemplateRepository::new(pool.clone()))
+        .with_batch_limit(batch_limit)
+        .with_key_retry_counter(metrics.key_generation_retries.clone());
+    if let Ok(hosts) = std::env::var("INTERSTITIAL_WARN_HOSTS") {
+        service = service.with_warning_list(WarningList::parse(&hosts));
+    }
+    match std::env::var("LINK_COOKIE_SECRET") {
+        Ok(secret) => service = service.with_unlock_secret(secret),
+        Err(_) => tracing::warn!("LINK_COOKIE_SECRET is not set; password unlocks will not survive a restart"),
+    }
+    let service = Arc::new(service);
+
+    let mut geo = GeoLocator::default();
+    if let Ok(path) = std::env::var("GEOIP_DATABASE") {
+        geo = geo.with_database(&path).map_err(|e| format!("cannot load GEOIP_DATABASE {path:?}: {e}"))?;
+    }
+    if let Ok(name) = std::env::var("GEOIP_COUNTRY_HEADER") {
+        let header = HeaderName::try_from(name.as_str()).map_err(|_| format!("GEOIP_COUNTRY_HEADER is not a header name: {name:?}"))?;
+        geo = geo.with_country_header(header);
+    }
+    if !geo.is_enabled() {
+        tracing::info!("Neither GEOIP_DATABASE nor GEOIP_COUNTRY_HEADER is set; country routes will not apply");
+    }
+
+    let domain_admin = Router::new()
+        .route("/domains", get(list_domains).post(register_domain))
+        .route("/domains/{host}/fallbacks", put(set_domain_fallbacks))
+        .route("/domains/{host}/interstitial", put(set_domain_interstitial).delete(clear_domain_interstitial))
+        .with_state(domains);
+    let utm_admin = Router::new()
+        .route("/utm-templates", get(list_utm_templates))
+        .route("/utm-templates/{name}", put(save_utm_template))
+        .with_state(Arc::new(UtmTemplateService::new(PostgresUtmTemplateRepository::new(pool.clone()))));
+    let admin = Router::new()
+        .route("/import", post(import_links))
+        .merge(domain_admin)
+        .merge(utm_admin)
+        .route_layer(middleware::from_fn_with_state(admin_token.clone(), require_admin));
+
+    let health = Health::new(pool.clone());
+    let shutdown_grace = env_limit("SHUTDOWN_GRACE_SECONDS")?
+        .map_or(DEFAULT_SHUTDOWN_GRACE, |secs| Duration::from_secs(secs.max(0) as u64));
+    let drain_timeout = env_limit("SHUTDOWN_DRAIN_TIMEOUT_SECONDS")?
+        .map_or(DEFAULT_DRAIN_TIMEOUT, |secs| Duration::from_secs(secs.max(0) as u64));
+    let health_routes = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+        .with_state(health.clone());
+
+    let metrics_routes = Router::new()
+        .route("/metrics", get(render_metrics))
+        .with_state(metrics.clone());
+
+    // Shortening and following links is open; changing links and reading their history needs the
+    // admin token or an API key
+    let credentials = Credentials::new(admin_token.clone(), ApiKeyService::new(PostgresApiKeyRepository::new(pool.clone())));
+    let management = Router::new()
+        .route("/shorten/batch", post(shorten_batch))
+        .route("/usage", get(get_usage))
+        .route("/audit", get(get_audit))
+        .route("/links/{short_key}", patch(retarget_url).delete(delete_url))
+        .route("/links/{short_key}/restore", post(restore_url))
+        .route("/links/{short_key}/variants", get(variant_stats))
+        .route_layer(middleware::from_fn_with_state(credentials.clone(), require_credentials))
+        .route("/export", get(export).route_layer(middleware::from_fn_with_state(admin_token, require_admin)));
 
     let app = Router::new()
-    .route("/shorten", post(shorten_url))
-    .route("/{short_key}", get(redirect_to_url))  
+    // Open to all for the default workspace; credentials pick and authorize any other
+    .route(
+        "/shorten",
+        post(shorten_url).route_layer(middleware::from_fn_with_state(credentials.clone(), identify_caller)),
+    )
+    .merge(management)
+    .nest("/admin", admin)
+    .route("/", get(redirect_root))
+    // Credentials are optional here; they only reveal a preview's owner and clicks
+    .route(
+        "/{short_key}",
+        get(redirect_to_url)
+            .post(unlock_url)
+            .route_layer(middleware::from_fn_with_state(credentials, identify_caller)),
+    )
+    .route("/{short_key}/qr", get(qr_code))
+    .route("/{short_key}/{*extra_path}", get(redirect_with_path))
+    .merge(health_routes)
+    .merge(metrics_routes)
+    .layer(Extension(Arc::new(geo)))
+    .layer(middleware::from_fn_with_state(metrics, track_metrics))
+    .layer(middleware::from_fn(request_id))
     .with_state(service);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
-    println!("Server running on http://localhost:8080");
-    axum::serve(listener, app).await?;
+    tracing::info!("Server running on http://localhost:8080");
+    let stop_listening = CancellationToken::new();
+    let mut server = tokio::spawn(
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(stop_listening.clone().cancelled_owned())
+            .into_future(),
+    );
+
+    // Shutdown order: fail readiness, keep serving through the grace period, stop accepting,
+    // let in-flight requests finish, flush background workers, then close the pool.
+    tokio::select! {
+        result = &mut server => result??,
+        _ = termination_signal() => {
+            health.begin_draining();
+            tracing::info!(grace_secs = shutdown_grace.as_secs(), "Shutdown requested, draining");
+            tokio::time::sleep(shutdown_grace).await;
+            stop_listening.cancel();
+
+            match tokio::time::timeout(drain_timeout, &mut server).await {
+                Ok(result) => result??,
+                Err(_) => {
+                    tracing::warn!(timeout_secs = drain_timeout.as_secs(), "In-flight requests still running, abandoning them");
+                    server.abort();
+                }
+            }
+        }
+    }
+
+    workers.shutdown(drain_timeout).await;
+    pool.close().await;
+    tracing::info!("Shutdown complete");
+    telemetry.shutdown();
 
     Ok(())
-}
\ No newline at end of file
+}
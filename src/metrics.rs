@@ -0,0 +1,166 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::PgPool;
+
+/// Every metric the service exports, registered on one private registry.
+///
+/// Collection happens at the edges — the HTTP middleware in `api::metrics` and the
+/// `MeteredUrlRepository` decorator — so handlers and services never touch it directly.
+/// With the `otlp` feature, request and query latencies are also recorded on the global
+/// OpenTelemetry meter, so `telemetry::init_tracing` must run before `Metrics::new`.
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub redirects: IntCounterVec,
+    pub key_generation_retries: IntCounter,
+    pub db_query_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+    pool: Option<PgPool>,
+    #[cfg(feature = "otlp")]
+    otel: OtelInstruments,
+}
+
+#[cfg(feature = "otlp")]
+struct OtelInstruments {
+    request_duration: opentelemetry::metrics::Histogram<f64>,
+    query_duration: opentelemetry::metrics::Histogram<f64>,
+}
+
+#[cfg(feature = "otlp")]
+impl OtelInstruments {
+    fn new() -> Self {
+        let meter = opentelemetry::global::meter("redirect-service");
+        Self {
+            request_duration: meter
+                .f64_histogram("http.server.request.duration")
+                .with_unit("s")
+                .with_description("HTTP request latency")
+                .build(),
+            query_duration: meter
+                .f64_histogram("db.client.operation.duration")
+                .with_unit("s")
+                .with_description("UrlRepository call latency")
+                .build(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route, method and status"),
+            &["route", "method", "status"],
+        )
+        .expect("valid metric");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route, method and status"),
+            &["route", "method", "status"],
+        )
+        .expect("valid metric");
+        let redirects = IntCounterVec::new(
+            Opts::new("redirects_total", "Redirect lookups by outcome (hit, miss, fallback, locked, not_yet_active, ended, exhausted, preview, interstitial or flagged)"),
+            &["outcome"],
+        )
+        .expect("valid metric");
+        let key_generation_retries = IntCounter::new(
+            "key_generation_retries_total",
+            "Generated short keys discarded because they were already taken",
+        )
+        .expect("valid metric");
+        let db_query_duration = HistogramVec::new(
+            HistogramOpts::new("db_query_duration_seconds", "UrlRepository call latency by method"),
+            &["method"],
+        )
+        .expect("valid metric");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state (idle or in_use)"),
+            &["state"],
+        )
+        .expect("valid metric");
+        let db_pool_max_connections =
+            IntGauge::new("db_pool_max_connections", "Configured database pool size").expect("valid metric");
+
+        let registry = Registry::new();
+        registry.register(Box::new(http_requests.clone())).expect("unique metric");
+        registry.register(Box::new(http_request_duration.clone())).expect("unique metric");
+        registry.register(Box::new(redirects.clone())).expect("unique metric");
+        registry.register(Box::new(key_generation_retries.clone())).expect("unique metric");
+        registry.register(Box::new(db_query_duration.clone())).expect("unique metric");
+        registry.register(Box::new(db_pool_connections.clone())).expect("unique metric");
+        registry.register(Box::new(db_pool_max_connections.clone())).expect("unique metric");
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            redirects,
+            key_generation_retries,
+            db_query_duration,
+            db_pool_connections,
+            db_pool_max_connections,
+            pool: None,
+            #[cfg(feature = "otlp")]
+            otel: OtelInstruments::new(),
+        }
+    }
+
+    /// Counts one finished HTTP request and its latency.
+    pub fn observe_request(&self, route: &str, method: &str, status: u16, seconds: f64) {
+        let status = status.to_string();
+        let labels = [route, method, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration.with_label_values(&labels).observe(seconds);
+
+        #[cfg(feature = "otlp")]
+        self.otel.request_duration.record(
+            seconds,
+            &[
+                opentelemetry::KeyValue::new("http.route", route.to_string()),
+                opentelemetry::KeyValue::new("http.request.method", method.to_string()),
+                opentelemetry::KeyValue::new("http.response.status_code", status),
+            ],
+        );
+    }
+
+    /// Records the latency of one repository call.
+    pub fn observe_query(&self, method: &str, seconds: f64) {
+        self.db_query_duration.with_label_values(&[method]).observe(seconds);
+
+        #[cfg(feature = "otlp")]
+        self.otel
+            .query_duration
+            .record(seconds, &[opentelemetry::KeyValue::new("db.operation.name", method.to_string())]);
+    }
+
+    /// Reports saturation of `pool` on every scrape.
+    pub fn with_pool(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        if let Some(pool) = &self.pool {
+            let idle = pool.num_idle() as i64;
+            self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+            self.db_pool_connections.with_label_values(&["in_use"]).set(pool.size() as i64 - idle);
+            self.db_pool_max_connections.set(pool.options().get_max_connections() as i64);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding into a Vec cannot fail");
+        String::from_utf8(buffer).expect("Prometheus text format is UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
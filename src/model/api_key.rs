@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Every minted secret starts with this, so leaked keys are easy to grep for.
+pub const API_KEY_PREFIX: &str = "rs_";
+
+/// Characters of the secret (after `API_KEY_PREFIX`) kept in clear to identify a key.
+pub const VISIBLE_PREFIX_LEN: usize = 8;
+
+/// A stored API key. The secret itself is never kept, only its hash.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: i64,
+    pub workspace_id: String,
+    pub name: String,
+    pub prefix: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// A freshly minted key together with its plaintext secret, which is shown exactly once.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MintedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub secret: String,
+}
+
+/// Hex-encoded SHA-256 of a secret, as stored in `api_keys.secret_hash`.
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// The identifying prefix stored alongside the hash, e.g. `rs_AbCd1234`.
+pub fn visible_prefix(secret: &str) -> String {
+    secret.chars().take(API_KEY_PREFIX.len() + VISIBLE_PREFIX_LEN).collect()
+}
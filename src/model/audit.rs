@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Mutations that leave a trail in `audit_events`. Key revocations record the key's prefix as
+/// the subject, where link mutations record the short key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Retarget,
+    Delete,
+    Restore,
+    Import,
+    RevokeKey,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Retarget => "retarget",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Import => "import",
+            AuditAction::RevokeKey => "revoke_key",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(AuditAction::Create),
+            "retarget" => Ok(AuditAction::Retarget),
+            "delete" => Ok(AuditAction::Delete),
+            "restore" => Ok(AuditAction::Restore),
+            "import" => Ok(AuditAction::Import),
+            "revoke_key" => Ok(AuditAction::RevokeKey),
+            other => Err(format!("unknown audit action: {other}")),
+        }
+    }
+}
+
+/// Who is performing a mutation, and under which request.
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: String,
+    pub request_id: Option<String>,
+}
+
+impl AuditContext {
+    pub fn new(actor: impl Into<String>, request_id: Option<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            request_id,
+        }
+    }
+
+    /// Context for mutations that do not originate from a caller (tests, maintenance).
+    pub fn system() -> Self {
+        Self::new("system", None)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub actor: String,
+    pub action: AuditAction,
+    /// Domain of the link; `None` for events that are not about a link.
+    pub domain: Option<String>,
+    pub short_key: String,
+    pub before_url: Option<String>,
+    pub after_url: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(
+        ctx: &AuditContext,
+        action: AuditAction,
+        domain: &str,
+        short_key: &str,
+        before_url: Option<String>,
+        after_url: Option<String>,
+    ) -> Self {
+        Self {
+            actor: ctx.actor.clone(),
+            action,
+            domain: Some(domain.to_string()),
+            short_key: short_key.to_string(),
+            before_url,
+            after_url,
+            request_id: ctx.request_id.clone(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// An API key was revoked; the key's prefix stands in for the short key.
+    pub fn key_revoked(ctx: &AuditContext, prefix: &str) -> Self {
+        Self {
+            actor: ctx.actor.clone(),
+            action: AuditAction::RevokeKey,
+            domain: None,
+            short_key: prefix.to_string(),
+            before_url: None,
+            after_url: None,
+            request_id: ctx.request_id.clone(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A persisted event; `id` doubles as the pagination cursor.
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub id: i64,
+    pub event: AuditEvent,
+}
+
+pub const DEFAULT_AUDIT_PAGE_SIZE: i64 = 50;
+pub const MAX_AUDIT_PAGE_SIZE: i64 = 500;
+
+/// Filters for `GET /audit`. Results are newest first; `before_id` pages backwards.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub domain: Option<String>,
+    pub short_key: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub before_id: Option<i64>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEventResponse {
+    pub id: i64,
+    pub actor: String,
+    pub action: AuditAction,
+    pub domain: Option<String>,
+    pub short_key: String,
+    pub before_url: Option<String>,
+    pub after_url: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditRecord> for AuditEventResponse {
+    fn from(record: AuditRecord) -> Self {
+        Self {
+            id: record.id,
+            actor: record.event.actor,
+            action: record.event.action,
+            domain: record.event.domain,
+            short_key: record.event.short_key,
+            before_url: record.event.before_url,
+            after_url: record.event.after_url,
+            request_id: record.event.request_id,
+            created_at: record.event.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditPageResponse {
+    pub events: Vec<AuditEventResponse>,
+    pub next_cursor: Option<i64>,
+}
+
+impl AuditPageResponse {
+    pub fn new(records: Vec<AuditRecord>, limit: i64) -> Self {
+        // A full page means there may be more; hand back the oldest id as the cursor
+        let next_cursor = if records.len() as i64 == limit {
+            records.last().map(|record| record.id)
+        } else {
+            None
+        };
+        Self {
+            events: records.into_iter().map(AuditEventResponse::from).collect(),
+            next_cursor,
+        }
+    }
+}
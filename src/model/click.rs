@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+
+/// One successful redirect, as recorded for analytics and export.
+#[derive(Debug, Clone)]
+pub struct ClickEvent {
+    pub domain: String,
+    pub short_key: String,
+    pub workspace_id: String,
+    pub clicked_at: DateTime<Utc>,
+    /// Visitor's ISO 3166-1 alpha-2 country, when geolocation found one.
+    pub country: Option<String>,
+    /// Position of the variant a split link sent the visitor to.
+    pub variant: Option<i32>,
+}
+
+impl ClickEvent {
+    pub fn new(domain: &str, short_key: &str, workspace_id: &str) -> Self {
+        Self {
+            domain: domain.to_string(),
+            short_key: short_key.to_string(),
+            workspace_id: workspace_id.to_string(),
+            clicked_at: Utc::now(),
+            country: None,
+            variant: None,
+        }
+    }
+
+    pub fn with_country(mut self, country: Option<String>) -> Self {
+        self.country = country;
+        self
+    }
+
+    pub fn with_variant(mut self, variant: Option<usize>) -> Self {
+        self.variant = variant.and_then(|index| i32::try_from(index).ok());
+        self
+    }
+}
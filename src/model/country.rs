@@ -0,0 +1,53 @@
+use crate::model::errors::DomainError;
+use crate::model::url::validate_original_url;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Uppercases an ISO 3166-1 alpha-2 country code. Anything else, and `XX` (which proxies such
+/// as Cloudflare send when they don't know), is `None`.
+pub fn normalize_country(code: &str) -> Option<String> {
+    let code = code.trim().to_ascii_uppercase();
+    let is_code = code.len() == 2 && code.bytes().all(|b| b.is_ascii_uppercase());
+    (is_code && code != "XX").then_some(code)
+}
+
+/// Per-country destinations keyed by ISO 3166-1 alpha-2 code. Countries without an entry,
+/// and visitors whose country is unknown, go to the link's default destination.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CountryRoutes(BTreeMap<String, String>);
+
+impl CountryRoutes {
+    /// Adds a route as given; `validate` rejects codes that aren't normalized.
+    pub fn with(mut self, country: impl Into<String>, url: impl Into<String>) -> Self {
+        self.0.insert(country.into(), url.into());
+        self
+    }
+
+    /// Uppercases the country codes, so `de` and `DE` name the same route.
+    pub fn normalized(self) -> Self {
+        Self(self.0.into_iter().map(|(country, url)| (country.to_ascii_uppercase(), url)).collect())
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        for (country, url) in &self.0 {
+            if normalize_country(country).as_deref() != Some(country.as_str()) {
+                return Err(DomainError::MalformedInput(format!("{country:?} is not an ISO 3166-1 alpha-2 country code")));
+            }
+            validate_original_url(url)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn destination(&self, country: &str) -> Option<&str> {
+        self.0.get(country).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(country, url)| (country.as_str(), url.as_str()))
+    }
+}
@@ -0,0 +1,124 @@
+use crate::model::errors::DomainError;
+use crate::model::interstitial::Interstitial;
+use crate::model::url::validate_original_url;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Domain that links belong to when none is named, and the one that serves requests whose
+/// `Host` is not a registered domain. Links created before domains existed live here.
+pub const DEFAULT_DOMAIN: &str = "localhost:8080";
+
+/// Query parameter carrying the requested key when an unknown key is redirected to `not_found_url`.
+pub const ATTEMPTED_KEY_PARAM: &str = "key";
+
+/// A short domain links can be created on, e.g. `go.acme.io`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Domain {
+    pub host: String,
+    /// Workspace that owns the domain; `None` means every workspace may use it.
+    pub workspace_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub fallbacks: DomainFallbacks,
+    /// Warning page shown for every link on the domain that doesn't set its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interstitial: Option<Interstitial>,
+}
+
+impl Domain {
+    pub fn new(host: impl Into<String>, workspace_id: Option<String>) -> Self {
+        Self {
+            host: host.into(),
+            workspace_id,
+            created_at: Utc::now(),
+            fallbacks: DomainFallbacks::default(),
+            interstitial: None,
+        }
+    }
+
+    /// Whether links of `workspace_id` may be created on this domain.
+    pub fn serves(&self, workspace_id: &str) -> bool {
+        self.workspace_id.as_deref().is_none_or(|owner| owner == workspace_id)
+    }
+}
+
+/// What a domain answers when it has no link for the request. Nothing configured means a plain 404.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainFallbacks {
+    /// Where the bare domain redirects to, e.g. the company homepage.
+    pub root_url: Option<String>,
+    /// Where unknown keys redirect to, with the key appended as `?key=`.
+    pub not_found_url: Option<String>,
+    /// Branded HTML served with a 404 for unknown keys, and for the root without a `root_url`.
+    pub not_found_page: Option<String>,
+}
+
+/// Response to serve instead of a bare 404.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fallback {
+    Redirect(String),
+    Page(String),
+}
+
+impl DomainFallbacks {
+    /// Redirect targets must be absolute http(s) URLs, and unknown keys either redirect or render a page.
+    pub fn validate(&self) -> Result<(), DomainError> {
+        for url in [&self.root_url, &self.not_found_url].into_iter().flatten() {
+            validate_original_url(url)?;
+        }
+        if self.not_found_url.is_some() && self.not_found_page.is_some() {
+            return Err(DomainError::MalformedInput(
+                "notFoundUrl and notFoundPage cannot both be set".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn root(&self) -> Option<Fallback> {
+        match &self.root_url {
+            Some(url) => Some(Fallback::Redirect(url.clone())),
+            None => self.not_found_page.clone().map(Fallback::Page),
+        }
+    }
+
+    pub fn not_found(&self, attempted_key: &str) -> Option<Fallback> {
+        let Some(url) = &self.not_found_url else {
+            return self.not_found_page.clone().map(Fallback::Page);
+        };
+        let mut target = ::url::Url::parse(url).ok()?;
+        target.query_pairs_mut().append_pair(ATTEMPTED_KEY_PARAM, attempted_key);
+        Some(Fallback::Redirect(target.into()))
+    }
+}
+
+/// Canonical form of a `Host` header or configured domain: lowercase, no trailing dot,
+/// port kept unless it is the default. `None` if it is not a bare `host[:port]`.
+pub fn normalize_host(host: &str) -> Option<String> {
+    let host = host.trim();
+    if host.is_empty() || host.contains(['/', '@', '?', '#']) {
+        return None;
+    }
+    let parsed = ::url::Url::parse(&format!("http://{host}")).ok()?;
+    let name = parsed.host_str()?.trim_end_matches('.');
+    if name.is_empty() {
+        return None;
+    }
+    Some(match parsed.port() {
+        Some(port) => format!("{name}:{port}"),
+        None => name.to_string(),
+    })
+}
+
+/// Local development hosts, which are served over plain HTTP.
+pub fn is_local_host(host: &str) -> bool {
+    let name = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    matches!(name, "localhost" | "127.0.0.1")
+}
+
+/// Public URL of `short_key` on `host`.
+pub fn short_url(host: &str, short_key: &str) -> String {
+    let scheme = if is_local_host(host) { "http" } else { "https" };
+    format!("{scheme}://{host}/{short_key}")
+}
@@ -1,3 +1,4 @@
+use crate::model::usage::QuotaKind;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -5,9 +6,84 @@ pub enum DomainError {
     #[error("URL not found")]
     UrlNotFound,
 
+    #[error("API key not found or already revoked")]
+    ApiKeyNotFound,
+
     #[error("Invalid short key")]
     InvalidShortKey,
 
+    #[error("Invalid URL: only absolute http(s) URLs can be shortened")]
+    InvalidUrl,
+
+    #[error("Invalid domain: expected a host name such as go.example.com")]
+    InvalidDomain,
+
+    #[error("Domain {0} is not registered for this workspace")]
+    UnknownDomain(String),
+
+    #[error("Domain {0} is already registered")]
+    DomainTaken(String),
+
+    #[error("Invalid password: link passwords are 4 to 128 characters")]
+    InvalidPassword,
+
+    #[error("This link is password protected")]
+    PasswordRequired,
+
+    #[error("Incorrect password")]
+    WrongPassword,
+
+    #[error("Too many incorrect passwords; try again in {0} seconds")]
+    TooManyAttempts(u64),
+
+    #[error("A link to this URL already exists with different password protection")]
+    ProtectionConflict,
+
+    /// Carries the link's `exhausted_url`, if it has one.
+    #[error("This link has reached its click limit")]
+    LinkExhausted(Option<String>),
+
+    #[error("A link to this URL already exists with a different click limit")]
+    ClickLimitConflict,
+
+    /// Carries the link's `not_yet_active_url`, if it has one.
+    #[error("This link is not active yet")]
+    LinkNotYetActive(Option<String>),
+
+    /// Carries the link's `ended_url`, if it has one.
+    #[error("This link has ended")]
+    LinkEnded(Option<String>),
+
+    #[error("A link to this URL already exists with a different schedule")]
+    ScheduleConflict,
+
+    #[error("A link to this URL already exists with different routes, variants, passthrough or interstitial options")]
+    RoutingConflict,
+
+    #[error("Another live link in this workspace already points to this URL on this domain")]
+    DestinationTaken,
+
+    #[error("UTM template {0} does not exist in this workspace")]
+    UnknownUtmTemplate(String),
+
+    #[error("Batch exceeds the limit of {0} items")]
+    BatchTooLarge(usize),
+
+    #[error("Unsupported format: expected csv or jsonl")]
+    UnsupportedFormat,
+
+    #[error("Malformed input: {0}")]
+    MalformedInput(String),
+
+    #[error("Missing or invalid admin credentials")]
+    Unauthorized,
+
+    #[error("This API key cannot act on workspace {0}")]
+    Forbidden(String),
+
+    #[error("Monthly {0} quota exceeded")]
+    QuotaExceeded(QuotaKind),
+
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
-}
\ No newline at end of file
+}
@@ -0,0 +1,124 @@
+use crate::model::click::ClickEvent;
+use crate::model::transfer::DataFormat;
+use crate::model::url::Url;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+/// What `GET /export` streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Links,
+    Clicks,
+}
+
+impl ExportKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "links" => Some(ExportKind::Links),
+            "clicks" => Some(ExportKind::Clicks),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportKind::Links => "links",
+            ExportKind::Clicks => "clicks",
+        }
+    }
+}
+
+// Field names match what import accepts, so a links export can be re-imported as is
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkRecord<'a> {
+    short_key: &'a str,
+    original_url: &'a str,
+    created_at: String,
+    workspace_id: &'a str,
+    domain: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClickRecord<'a> {
+    short_key: &'a str,
+    workspace_id: &'a str,
+    clicked_at: String,
+    country: Option<&'a str>,
+    variant: Option<i32>,
+    domain: &'a str,
+}
+
+fn timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn csv_line(fields: &[&str]) -> String {
+    let mut writer = csv::WriterBuilder::new().terminator(csv::Terminator::Any(b'\n')).from_writer(Vec::new());
+    writer.write_record(fields).expect("writing CSV to memory cannot fail");
+    String::from_utf8(writer.into_inner().expect("flushing CSV to memory cannot fail"))
+        .expect("CSV of UTF-8 fields is UTF-8")
+}
+
+fn json_line<T: Serialize>(record: &T) -> String {
+    let mut line = serde_json::to_string(record).expect("export records always serialize");
+    line.push('\n');
+    line
+}
+
+/// CSV header line; JSON Lines has none.
+pub fn export_header(format: DataFormat, kind: ExportKind) -> Option<String> {
+    match (format, kind) {
+        (DataFormat::Csv, ExportKind::Links) => {
+            Some(csv_line(&["short_key", "original_url", "created_at", "workspace_id", "domain"]))
+        }
+        (DataFormat::Csv, ExportKind::Clicks) => {
+            Some(csv_line(&["short_key", "workspace_id", "clicked_at", "country", "variant", "domain"]))
+        }
+        (DataFormat::JsonLines, _) => None,
+    }
+}
+
+pub fn link_line(format: DataFormat, url: &Url) -> String {
+    let created_at = timestamp(&url.created_at);
+    match format {
+        DataFormat::Csv => csv_line(&[
+            url.short_key.as_str(),
+            &url.original_url,
+            &created_at,
+            &url.workspace_id,
+            &url.domain,
+        ]),
+        DataFormat::JsonLines => json_line(&LinkRecord {
+            short_key: url.short_key.as_str(),
+            original_url: &url.original_url,
+            created_at,
+            workspace_id: &url.workspace_id,
+            domain: &url.domain,
+        }),
+    }
+}
+
+pub fn click_line(format: DataFormat, click: &ClickEvent) -> String {
+    let clicked_at = timestamp(&click.clicked_at);
+    match format {
+        DataFormat::Csv => csv_line(&[
+            &click.short_key,
+            &click.workspace_id,
+            &clicked_at,
+            click.country.as_deref().unwrap_or_default(),
+            &click.variant.map(|variant| variant.to_string()).unwrap_or_default(),
+            &click.domain,
+        ]),
+        DataFormat::JsonLines => json_line(&ClickRecord {
+            short_key: &click.short_key,
+            workspace_id: &click.workspace_id,
+            clicked_at,
+            country: click.country.as_deref(),
+            variant: click.variant,
+            domain: &click.domain,
+        }),
+    }
+}
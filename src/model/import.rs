@@ -0,0 +1,164 @@
+use crate::model::domain::normalize_host;
+use crate::model::short_key::ShortKey;
+use crate::model::transfer::DataFormat;
+use crate::model::url::{validate_original_url, Url, DEFAULT_WORKSPACE};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Rows committed per transaction unless the caller asks otherwise.
+pub const DEFAULT_IMPORT_CHUNK_SIZE: usize = 1000;
+
+/// Largest chunk an import commits at once; bigger requests are cut down to it.
+pub const MAX_IMPORT_CHUNK_SIZE: usize = 10_000;
+
+/// Per-row problems kept in the report; counts keep going past this.
+pub const MAX_REPORTED_ISSUES: usize = 1000;
+
+/// One validated row of an import file.
+#[derive(Debug, Clone)]
+pub struct ImportRecord {
+    pub short_key: ShortKey,
+    pub original_url: String,
+    pub created_at: DateTime<Utc>,
+    pub workspace_id: String,
+    /// `None` when the row does not name one; it then lands on the importer's default domain.
+    pub domain: Option<String>,
+}
+
+impl ImportRecord {
+    pub fn into_url(self, default_domain: &str) -> Url {
+        let domain = self.domain.unwrap_or_else(|| default_domain.to_string());
+        let mut url = Url::new(self.short_key, self.original_url)
+            .with_workspace(self.workspace_id)
+            .with_domain(domain);
+        url.created_at = self.created_at;
+        url
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRecord {
+    #[serde(alias = "shortKey")]
+    short_key: String,
+    #[serde(alias = "originalUrl")]
+    original_url: String,
+    #[serde(default, alias = "createdAt")]
+    created_at: Option<String>,
+    #[serde(default, alias = "workspaceId")]
+    workspace_id: Option<String>,
+    #[serde(default)]
+    domain: Option<String>,
+}
+
+/// Whether a CSV line is the optional `short_key,original_url,...` header.
+pub fn is_csv_header(line: &str) -> bool {
+    line.trim_start().starts_with("short_key")
+}
+
+/// Parses and validates one line. Blank lines yield `Ok(None)`; the error is a
+/// human-readable reason that ends up in the import report.
+pub fn parse_import_line(format: DataFormat, line: &str) -> Result<Option<ImportRecord>, String> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let raw = match format {
+        DataFormat::Csv => parse_csv_line(line)?,
+        DataFormat::JsonLines => {
+            serde_json::from_str::<RawRecord>(line).map_err(|e| format!("malformed JSON: {e}"))?
+        }
+    };
+
+    let short_key = ShortKey::new(raw.short_key.trim().to_string())
+        .map_err(|_| format!("invalid short key {:?}", raw.short_key))?;
+
+    let original_url = raw.original_url.trim().to_string();
+    validate_original_url(&original_url).map_err(|e| e.to_string())?;
+
+    let created_at = match raw.created_at.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map_err(|_| format!("invalid created_at {value:?}, expected RFC 3339"))?
+            .with_timezone(&Utc),
+        None => Utc::now(),
+    };
+
+    let workspace_id = raw
+        .workspace_id
+        .map(|w| w.trim().to_string())
+        .filter(|w| !w.is_empty())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+
+    let domain = match raw.domain.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(value) => Some(normalize_host(value).ok_or_else(|| format!("invalid domain {value:?}"))?),
+        None => None,
+    };
+
+    Ok(Some(ImportRecord {
+        short_key,
+        original_url,
+        created_at,
+        workspace_id,
+        domain,
+    }))
+}
+
+fn parse_csv_line(line: &str) -> Result<RawRecord, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(line.as_bytes());
+
+    let record = reader
+        .records()
+        .next()
+        .ok_or_else(|| "empty row".to_string())?
+        .map_err(|e| format!("malformed CSV: {e}"))?;
+
+    if record.len() < 2 {
+        return Err("expected at least short_key,original_url".to_string());
+    }
+
+    let optional = |i: usize| record.get(i).map(str::to_string);
+    Ok(RawRecord {
+        short_key: record[0].to_string(),
+        original_url: record[1].to_string(),
+        created_at: optional(2),
+        workspace_id: optional(3),
+        domain: optional(4),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIssue {
+    pub line: usize,
+    pub short_key: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: usize,
+    pub chunks_committed: usize,
+    pub conflict_count: usize,
+    pub invalid_count: usize,
+    pub conflicts: Vec<ImportIssue>,
+    pub invalid: Vec<ImportIssue>,
+}
+
+impl ImportReport {
+    pub fn add_conflict(&mut self, issue: ImportIssue) {
+        self.conflict_count += 1;
+        if self.conflicts.len() < MAX_REPORTED_ISSUES {
+            self.conflicts.push(issue);
+        }
+    }
+
+    pub fn add_invalid(&mut self, issue: ImportIssue) {
+        self.invalid_count += 1;
+        if self.invalid.len() < MAX_REPORTED_ISSUES {
+            self.invalid.push(issue);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::model::errors::DomainError;
+use serde::{Deserialize, Serialize};
+
+/// Longest countdown before a warning page continues on its own.
+pub const MAX_INTERSTITIAL_COUNTDOWN: u32 = 30;
+
+/// A "you are leaving for ..." page shown instead of redirecting straight away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interstitial {
+    /// Seconds until the page continues to the destination by itself; 0 waits for the button.
+    #[serde(default)]
+    pub countdown: u32,
+}
+
+impl Interstitial {
+    pub fn new(countdown: u32) -> Self {
+        Self { countdown }
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.countdown > MAX_INTERSTITIAL_COUNTDOWN {
+            return Err(DomainError::MalformedInput(format!(
+                "interstitial countdown must be at most {MAX_INTERSTITIAL_COUNTDOWN} seconds"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Destination hosts that always get a warning page, e.g. from `INTERSTITIAL_WARN_HOSTS`.
+/// An entry matches the host itself and every subdomain of it.
+#[derive(Debug, Clone, Default)]
+pub struct WarningList(Vec<String>);
+
+impl WarningList {
+    pub fn new<'a>(hosts: impl IntoIterator<Item = &'a str>) -> Self {
+        Self(
+            hosts
+                .into_iter()
+                .map(|host| host.trim().trim_start_matches("*.").trim_matches('.').to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Comma- or whitespace-separated hosts.
+    pub fn parse(list: &str) -> Self {
+        Self::new(list.split(|c: char| c == ',' || c.is_whitespace()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether `destination`'s host is on the list. Unparseable destinations never match.
+    pub fn matches(&self, destination: &str) -> bool {
+        let Some(host) = ::url::Url::parse(destination)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.trim_end_matches('.').to_ascii_lowercase()))
+        else {
+            return false;
+        };
+        self.0.iter().any(|listed| {
+            host == *listed || host.strip_suffix(listed.as_str()).is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+}
+
+/// The warning page a visit gets instead of a redirect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// The link or its domain shows one for every visit.
+    Leaving(Interstitial),
+    /// The destination is on the warning list, so visitors have to click through.
+    Flagged,
+}
@@ -1,9 +1,44 @@
 pub mod url;
 pub mod short_key;
 pub mod errors;
+pub mod usage;
+pub mod audit;
+pub mod transfer;
+pub mod import;
+pub mod click;
+pub mod export;
+pub mod api_key;
+pub mod domain;
+pub mod password;
+pub mod visit;
+pub mod platform;
+pub mod country;
+pub mod variant;
+pub mod passthrough;
+pub mod utm;
+pub mod interstitial;
+pub mod qr;
 
-pub use url::Url;
+pub use url::{ActiveWindow, ClickLimit, LinkPreview, NewLink, Url};
 pub use short_key::ShortKey;
 pub use errors::DomainError;
+pub use usage::{QuotaKind, QuotaLimits, Usage};
+pub use audit::{AuditAction, AuditContext, AuditEvent, AuditFilter, AuditRecord};
+pub use transfer::DataFormat;
+pub use import::{ImportRecord, ImportReport};
+pub use click::ClickEvent;
+pub use export::ExportKind;
+pub use api_key::{ApiKey, MintedApiKey};
+pub use domain::{Domain, DomainFallbacks, Fallback};
+pub use visit::Visit;
+pub use platform::{Platform, PlatformRoutes};
+pub use country::CountryRoutes;
+pub use variant::{Variant, VariantClicks, Variants};
+pub use passthrough::QueryPolicy;
+pub use utm::{Utm, UtmTemplate};
+pub use interstitial::{Interstitial, Warning, WarningList};
+pub use qr::{QrColor, QrFormat, QrOptions};
 
 pub use url::UrlResponse;
+pub use usage::UsageResponse;
+pub use audit::AuditPageResponse;
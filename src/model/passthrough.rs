@@ -0,0 +1,95 @@
+use crate::model::errors::DomainError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use url::form_urlencoded;
+
+/// How a visitor's query parameters are merged into the destination when the destination
+/// already has a parameter of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryPolicy {
+    /// The destination's value wins; the visitor's is dropped.
+    Keep,
+    /// The visitor's value replaces the destination's.
+    Replace,
+    /// Both are kept, the destination's first.
+    Append,
+}
+
+impl QueryPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryPolicy::Keep => "keep",
+            QueryPolicy::Replace => "replace",
+            QueryPolicy::Append => "append",
+        }
+    }
+}
+
+impl fmt::Display for QueryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QueryPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(QueryPolicy::Keep),
+            "replace" => Ok(QueryPolicy::Replace),
+            "append" => Ok(QueryPolicy::Append),
+            other => Err(format!("unknown query policy: {other}")),
+        }
+    }
+}
+
+/// Rejects extra path segments that could climb out of the destination's path.
+pub fn validate_extra_path(extra_path: &str) -> Result<(), DomainError> {
+    if extra_path.split('/').any(|segment| segment == "." || segment == "..") {
+        return Err(DomainError::UrlNotFound);
+    }
+    Ok(())
+}
+
+/// `destination` with `extra_path` appended to its path and `query` merged into its query
+/// string under `policy`. Returns `destination` untouched when there is nothing to add.
+pub fn forward(destination: &str, extra_path: Option<&str>, query: Option<&str>, policy: Option<QueryPolicy>) -> String {
+    let extra_path = extra_path.filter(|path| !path.is_empty());
+    let incoming: Vec<(String, String)> = match (query, policy) {
+        (Some(query), Some(_)) => form_urlencoded::parse(query.as_bytes()).into_owned().collect(),
+        _ => Vec::new(),
+    };
+    if extra_path.is_none() && incoming.is_empty() {
+        return destination.to_string();
+    }
+    let Ok(mut url) = ::url::Url::parse(destination) else {
+        return destination.to_string();
+    };
+
+    if let Some(extra_path) = extra_path {
+        let path = format!("{}/{}", url.path().trim_end_matches('/'), extra_path.trim_start_matches('/'));
+        url.set_path(&path);
+    }
+    if !incoming.is_empty() {
+        let existing: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let has = |pairs: &[(String, String)], name: &str| pairs.iter().any(|(key, _)| key == name);
+        let merged: Vec<(String, String)> = match policy {
+            Some(QueryPolicy::Keep) => existing
+                .iter()
+                .cloned()
+                .chain(incoming.into_iter().filter(|(name, _)| !has(&existing, name)))
+                .collect(),
+            Some(QueryPolicy::Replace) => existing
+                .into_iter()
+                .filter(|(name, _)| !has(&incoming, name))
+                .chain(incoming.iter().cloned())
+                .collect(),
+            _ => existing.into_iter().chain(incoming).collect(),
+        };
+        url.query_pairs_mut().clear().extend_pairs(merged);
+    }
+    url.to_string()
+}
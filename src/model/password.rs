@@ -0,0 +1,35 @@
+use crate::model::errors::DomainError;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+/// Shortest password a link can be protected with.
+pub const MIN_PASSWORD_LEN: usize = 4;
+
+/// Longest password accepted, so a request can't make us hash megabytes.
+pub const MAX_PASSWORD_LEN: usize = 128;
+
+pub fn validate_password(password: &str) -> Result<(), DomainError> {
+    if (MIN_PASSWORD_LEN..=MAX_PASSWORD_LEN).contains(&password.chars().count()) {
+        Ok(())
+    } else {
+        Err(DomainError::InvalidPassword)
+    }
+}
+
+/// Argon2id hash of a link password in PHC format, as stored in `urls.password_hash`.
+/// Deliberately slow; call it off the async workers.
+pub fn hash_password(password: &str) -> Result<String, DomainError> {
+    validate_password(password)?;
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("default Argon2 parameters accept any bounded password");
+    Ok(hash.to_string())
+}
+
+/// Whether `password` matches a stored hash. A hash that doesn't parse never matches.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash)
+        .is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
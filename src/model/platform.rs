@@ -0,0 +1,82 @@
+use crate::model::errors::DomainError;
+use crate::model::url::validate_original_url;
+use serde::{Deserialize, Serialize};
+
+/// The kind of device a visitor is on, as far as routing cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Ios,
+    Android,
+    Desktop,
+}
+
+impl Platform {
+    /// Classifies a `User-Agent`. Bots, command-line clients and mobile platforms other than
+    /// iOS and Android are `None`, so they get the link's default destination.
+    pub fn from_user_agent(user_agent: &str) -> Option<Platform> {
+        // Windows Phone claims to be both Android and iPhone
+        if user_agent.contains("Windows Phone") {
+            return None;
+        }
+        if ["iPhone", "iPad", "iPod"].iter().any(|device| user_agent.contains(device)) {
+            return Some(Platform::Ios);
+        }
+        if user_agent.contains("Android") {
+            return Some(Platform::Android);
+        }
+        let is_bot = ["bot", "Bot", "crawler", "spider"].iter().any(|word| user_agent.contains(word));
+        let is_desktop = ["Windows NT", "Macintosh", "X11", "CrOS"].iter().any(|os| user_agent.contains(os));
+        (is_desktop && !is_bot).then_some(Platform::Desktop)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Ios => "ios",
+            Platform::Android => "android",
+            Platform::Desktop => "desktop",
+        }
+    }
+}
+
+/// Per-platform destinations that replace a link's `original_url`. Platforms without an
+/// entry, and visitors whose platform is unknown, go to `original_url`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlatformRoutes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ios: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub android: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desktop: Option<String>,
+}
+
+impl PlatformRoutes {
+    pub fn with(mut self, platform: Platform, url: impl Into<String>) -> Self {
+        let url = Some(url.into());
+        match platform {
+            Platform::Ios => self.ios = url,
+            Platform::Android => self.android = url,
+            Platform::Desktop => self.desktop = url,
+        }
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        for url in [&self.ios, &self.android, &self.desktop].into_iter().flatten() {
+            validate_original_url(url)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ios.is_none() && self.android.is_none() && self.desktop.is_none()
+    }
+
+    pub fn destination(&self, platform: Platform) -> Option<&str> {
+        match platform {
+            Platform::Ios => self.ios.as_deref(),
+            Platform::Android => self.android.as_deref(),
+            Platform::Desktop => self.desktop.as_deref(),
+        }
+    }
+}
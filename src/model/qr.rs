@@ -0,0 +1,259 @@
+use crate::model::errors::DomainError;
+use qrcode::{Color, EcLevel, QrCode};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+pub const DEFAULT_QR_SIZE: u32 = 256;
+pub const MIN_QR_SIZE: u32 = 64;
+pub const MAX_QR_SIZE: u32 = 2048;
+
+/// Quiet zone around the code, in modules; scanners expect at least 4.
+pub const DEFAULT_QR_MARGIN: u32 = 4;
+pub const MAX_QR_MARGIN: u32 = 16;
+
+/// Image formats a QR code is rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrFormat {
+    Svg,
+    Png,
+}
+
+impl QrFormat {
+    /// Parses the `format` query parameter.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "svg" => Some(QrFormat::Svg),
+            "png" => Some(QrFormat::Png),
+            _ => None,
+        }
+    }
+
+    /// The format an `Accept` header prefers: PNG only when it ranks `image/png` above
+    /// `image/svg+xml`, SVG otherwise.
+    pub fn from_accept(accept: &str) -> Self {
+        let (mut png, mut svg) = (0.0_f32, 0.0_f32);
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let mime = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            match mime.as_str() {
+                "image/png" => png = png.max(quality),
+                "image/svg+xml" => svg = svg.max(quality),
+                _ => {}
+            }
+        }
+        if png > svg { QrFormat::Png } else { QrFormat::Svg }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            QrFormat::Svg => "image/svg+xml",
+            QrFormat::Png => "image/png",
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            QrFormat::Svg => "svg",
+            QrFormat::Png => "png",
+        }
+    }
+}
+
+/// An RGBA colour, written as `RRGGBB` or `RRGGBBAA` hex with an optional leading `#`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QrColor([u8; 4]);
+
+impl QrColor {
+    pub const BLACK: QrColor = QrColor([0, 0, 0, 255]);
+    pub const WHITE: QrColor = QrColor([255, 255, 255, 255]);
+
+    pub fn parse(hex: &str) -> Result<Self, DomainError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        let invalid = || DomainError::MalformedInput(format!("{hex:?} is not a colour: use RRGGBB or RRGGBBAA hex"));
+        if !(digits.len() == 6 || digits.len() == 8) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid());
+        let alpha = if digits.len() == 8 { channel(6)? } else { 255 };
+        Ok(QrColor([channel(0)?, channel(2)?, channel(4)?, alpha]))
+    }
+
+    fn hex(&self) -> String {
+        let [r, g, b, _] = self.0;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    fn opacity(&self) -> f32 {
+        f32::from(self.0[3]) / 255.0
+    }
+}
+
+/// How a QR code is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QrOptions {
+    /// Largest width and height of the image, in pixels.
+    pub size: u32,
+    /// Quiet zone, in modules.
+    pub margin: u32,
+    pub ec_level: EcLevel,
+    pub foreground: QrColor,
+    pub background: QrColor,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_QR_SIZE,
+            margin: DEFAULT_QR_MARGIN,
+            ec_level: EcLevel::M,
+            foreground: QrColor::BLACK,
+            background: QrColor::WHITE,
+        }
+    }
+}
+
+impl QrOptions {
+    /// Parses an error-correction level: `L`, `M`, `Q` or `H`, recovering 7% to 30% of the code.
+    pub fn parse_ec_level(level: &str) -> Result<EcLevel, DomainError> {
+        match level.to_ascii_uppercase().as_str() {
+            "L" => Ok(EcLevel::L),
+            "M" => Ok(EcLevel::M),
+            "Q" => Ok(EcLevel::Q),
+            "H" => Ok(EcLevel::H),
+            _ => Err(DomainError::MalformedInput("ec must be L, M, Q or H".to_string())),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if !(MIN_QR_SIZE..=MAX_QR_SIZE).contains(&self.size) {
+            return Err(DomainError::MalformedInput(format!(
+                "size must be between {MIN_QR_SIZE} and {MAX_QR_SIZE} pixels"
+            )));
+        }
+        if self.margin > MAX_QR_MARGIN {
+            return Err(DomainError::MalformedInput(format!("margin must be at most {MAX_QR_MARGIN} modules")));
+        }
+        Ok(())
+    }
+
+    fn ec_letter(&self) -> char {
+        match self.ec_level {
+            EcLevel::L => 'L',
+            EcLevel::M => 'M',
+            EcLevel::Q => 'Q',
+            EcLevel::H => 'H',
+        }
+    }
+}
+
+/// Strong entity tag for the image of `data` drawn with `options` in `format`. The same
+/// inputs always render the same bytes, so the tag can be computed without rendering.
+pub fn qr_etag(data: &str, options: &QrOptions, format: QrFormat) -> String {
+    let digest = Sha256::digest(format!(
+        "{data}\n{}\n{}\n{}\n{}\n{:?}\n{:?}",
+        format.as_str(),
+        options.size,
+        options.margin,
+        options.ec_letter(),
+        options.foreground.0,
+        options.background.0,
+    ));
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Draws `data` as a QR code. Every module is the same whole number of pixels, so PNGs may
+/// come out slightly smaller than `options.size`; SVGs scale to exactly `options.size`.
+pub fn render_qr(data: &str, options: &QrOptions, format: QrFormat) -> Result<Vec<u8>, DomainError> {
+    let code = QrCode::with_error_correction_level(data, options.ec_level)
+        .map_err(|e| DomainError::MalformedInput(format!("cannot encode this link as a QR code: {e}")))?;
+    let modules = Modules {
+        width: code.width(),
+        margin: options.margin as usize,
+        colors: code.to_colors(),
+    };
+    match format {
+        QrFormat::Svg => Ok(render_svg(&modules, options).into_bytes()),
+        QrFormat::Png => Ok(render_png(&modules, options)),
+    }
+}
+
+struct Modules {
+    width: usize,
+    margin: usize,
+    colors: Vec<Color>,
+}
+
+impl Modules {
+    /// Modules across the whole image, quiet zone included.
+    fn total(&self) -> usize {
+        self.width + 2 * self.margin
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.colors[y * self.width + x] == Color::Dark
+    }
+}
+
+fn render_svg(modules: &Modules, options: &QrOptions) -> String {
+    let total = modules.total();
+    // One path of horizontal runs keeps the file small and free of hairline seams
+    let mut path = String::new();
+    for y in 0..modules.width {
+        let mut x = 0;
+        while x < modules.width {
+            if !modules.is_dark(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < modules.width && modules.is_dark(x, y) {
+                x += 1;
+            }
+            let run = x - start;
+            let _ = write!(path, "M{},{}h{run}v1h-{run}z", start + modules.margin, y + modules.margin);
+        }
+    }
+    let (foreground, background) = (options.foreground, options.background);
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {total} {total}" shape-rendering="crispEdges"><rect width="{total}" height="{total}" fill="{bg}" fill-opacity="{bg_opacity}"/><path fill="{fg}" fill-opacity="{fg_opacity}" d="{path}"/></svg>"#,
+        size = options.size,
+        bg = background.hex(),
+        bg_opacity = background.opacity(),
+        fg = foreground.hex(),
+        fg_opacity = foreground.opacity(),
+    )
+}
+
+fn render_png(modules: &Modules, options: &QrOptions) -> Vec<u8> {
+    let total = modules.total();
+    let scale = (options.size as usize / total).max(1);
+    let side = total * scale;
+    let mut pixels = Vec::with_capacity(side * side * 4);
+    for py in 0..side {
+        for px in 0..side {
+            let (x, y) = (px / scale, py / scale);
+            let inside = (modules.margin..modules.margin + modules.width).contains(&x)
+                && (modules.margin..modules.margin + modules.width).contains(&y);
+            let color = if inside && modules.is_dark(x - modules.margin, y - modules.margin) {
+                options.foreground
+            } else {
+                options.background
+            };
+            pixels.extend_from_slice(&color.0);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, side as u32, side as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // Writing to memory can't fail, and the buffer always matches the header
+    let mut writer = encoder.write_header().expect("PNG header is valid");
+    writer.write_image_data(&pixels).expect("pixel buffer matches the PNG header");
+    writer.finish().expect("PNG is complete");
+    png
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+/// Line-oriented formats accepted by import and produced by export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Csv,
+    JsonLines,
+}
+
+impl DataFormat {
+    /// Parses the `format` query parameter / CLI flag.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(DataFormat::Csv),
+            "jsonl" | "ndjson" | "jsonlines" => Some(DataFormat::JsonLines),
+            _ => None,
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "text/csv" => Some(DataFormat::Csv),
+            "application/x-ndjson" | "application/jsonl" | "application/x-jsonlines" => {
+                Some(DataFormat::JsonLines)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DataFormat::Csv => "text/csv",
+            DataFormat::JsonLines => "application/x-ndjson",
+        }
+    }
+}
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFormat::Csv => write!(f, "csv"),
+            DataFormat::JsonLines => write!(f, "jsonl"),
+        }
+    }
+}
@@ -1,38 +1,515 @@
+use crate::model::domain::{short_url, DEFAULT_DOMAIN};
+use crate::model::country::CountryRoutes;
+use crate::model::errors::DomainError;
+use crate::model::interstitial::Interstitial;
+use crate::model::passthrough::{forward, QueryPolicy};
+use crate::model::platform::{Platform, PlatformRoutes};
+use crate::model::utm::Utm;
+use crate::model::variant::Variants;
+use crate::model::visit::Visit;
 use crate::model::short_key::ShortKey;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+/// Workspace used when the caller does not name one.
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// Rejects anything that is not an absolute http(s) URL with a host.
+pub fn validate_original_url(original_url: &str) -> Result<(), DomainError> {
+    let parsed = ::url::Url::parse(original_url).map_err(|_| DomainError::InvalidUrl)?;
+    let is_web = matches!(parsed.scheme(), "http" | "https");
+    if !is_web || parsed.host_str().is_none_or(str::is_empty) {
+        return Err(DomainError::InvalidUrl);
+    }
+    Ok(())
+}
+
+/// A link someone asked us to create, before a key has been assigned.
+#[derive(Debug, Clone)]
+pub struct NewLink {
+    pub original_url: String,
+    pub workspace_id: String,
+    /// Requested domain; the service's default domain when `None`.
+    pub domain: Option<String>,
+    /// Visitors must enter this before being redirected.
+    pub password: Option<String>,
+    /// Redirects allowed before the link stops working; `Some(1)` makes a one-time link.
+    pub max_clicks: Option<u32>,
+    /// Where visitors go once `max_clicks` is used up, instead of a 410.
+    pub exhausted_url: Option<String>,
+    /// The link redirects from this moment on; before it, it is not yet active.
+    pub active_from: Option<DateTime<Utc>>,
+    /// The link stops redirecting at this moment.
+    pub active_until: Option<DateTime<Utc>>,
+    /// Where visitors go before `active_from`, instead of a 404.
+    pub not_yet_active_url: Option<String>,
+    /// Where visitors go from `active_until` on, instead of a 410.
+    pub ended_url: Option<String>,
+    /// Destinations that replace `original_url` for visitors on those platforms.
+    pub platform_routes: PlatformRoutes,
+    /// Destinations that replace `original_url` for visitors from those countries.
+    pub country_routes: CountryRoutes,
+    /// Weighted destinations that split the remaining visitors between them.
+    pub variants: Variants,
+    /// Merge the visitor's query parameters into the destination under this policy.
+    pub forward_query: Option<QueryPolicy>,
+    /// Append any path after the short key to the destination.
+    pub forward_path: bool,
+    /// Show a warning page before sending visitors on.
+    pub interstitial: Option<Interstitial>,
+    /// Tracking parameters added to `original_url` before it is shortened.
+    pub utm: Utm,
+    /// Workspace template filling in the UTM parameters `utm` leaves unset.
+    pub utm_template: Option<String>,
+}
+
+impl NewLink {
+    pub fn new(original_url: impl Into<String>) -> Self {
+        Self {
+            original_url: original_url.into(),
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
+            domain: None,
+            password: None,
+            max_clicks: None,
+            exhausted_url: None,
+            active_from: None,
+            active_until: None,
+            not_yet_active_url: None,
+            ended_url: None,
+            platform_routes: PlatformRoutes::default(),
+            country_routes: CountryRoutes::default(),
+            variants: Variants::default(),
+            forward_query: None,
+            forward_path: false,
+            interstitial: None,
+            utm: Utm::default(),
+            utm_template: None,
+        }
+    }
+
+    pub fn in_workspace(mut self, workspace_id: impl Into<String>) -> Self {
+        self.workspace_id = workspace_id.into();
+        self
+    }
+
+    pub fn on_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn with_max_clicks(mut self, max_clicks: u32) -> Self {
+        self.max_clicks = Some(max_clicks);
+        self
+    }
+
+    pub fn with_exhausted_url(mut self, exhausted_url: impl Into<String>) -> Self {
+        self.exhausted_url = Some(exhausted_url.into());
+        self
+    }
+
+    pub fn active_from(mut self, active_from: DateTime<Utc>) -> Self {
+        self.active_from = Some(active_from);
+        self
+    }
+
+    pub fn active_until(mut self, active_until: DateTime<Utc>) -> Self {
+        self.active_until = Some(active_until);
+        self
+    }
+
+    pub fn with_not_yet_active_url(mut self, not_yet_active_url: impl Into<String>) -> Self {
+        self.not_yet_active_url = Some(not_yet_active_url.into());
+        self
+    }
+
+    pub fn with_ended_url(mut self, ended_url: impl Into<String>) -> Self {
+        self.ended_url = Some(ended_url.into());
+        self
+    }
+
+    pub fn route_platform(mut self, platform: Platform, url: impl Into<String>) -> Self {
+        self.platform_routes = self.platform_routes.with(platform, url);
+        self
+    }
+
+    pub fn route_country(mut self, country: impl Into<String>, url: impl Into<String>) -> Self {
+        self.country_routes = self.country_routes.with(country, url);
+        self
+    }
+
+    pub fn with_variant(mut self, url: impl Into<String>, weight: u32) -> Self {
+        self.variants = self.variants.with(url, weight);
+        self
+    }
+
+    pub fn forwarding_query(mut self, policy: QueryPolicy) -> Self {
+        self.forward_query = Some(policy);
+        self
+    }
+
+    pub fn forwarding_path(mut self) -> Self {
+        self.forward_path = true;
+        self
+    }
+
+    pub fn with_interstitial(mut self, interstitial: Interstitial) -> Self {
+        self.interstitial = Some(interstitial);
+        self
+    }
+
+    pub fn with_utm(mut self, utm: Utm) -> Self {
+        self.utm = utm;
+        self
+    }
+
+    pub fn with_utm_template(mut self, name: impl Into<String>) -> Self {
+        self.utm_template = Some(name.into());
+        self
+    }
+
+    /// The window a new link redirects in. Errors if the settings make no sense together.
+    pub fn active_window(&self) -> Result<Option<ActiveWindow>, DomainError> {
+        let malformed = |reason: &str| Err(DomainError::MalformedInput(reason.to_string()));
+        if self.not_yet_active_url.is_some() && self.active_from.is_none() {
+            return malformed("notYetActiveUrl needs activeFrom");
+        }
+        if self.ended_url.is_some() && self.active_until.is_none() {
+            return malformed("endedUrl needs activeUntil");
+        }
+        if let (Some(from), Some(until)) = (self.active_from, self.active_until)
+            && from >= until
+        {
+            return malformed("activeUntil must be after activeFrom");
+        }
+        for url in [&self.not_yet_active_url, &self.ended_url].into_iter().flatten() {
+            validate_original_url(url)?;
+        }
+        if self.active_from.is_none() && self.active_until.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(ActiveWindow {
+            active_from: self.active_from,
+            active_until: self.active_until,
+            not_yet_active_url: self.not_yet_active_url.clone(),
+            ended_url: self.ended_url.clone(),
+        }))
+    }
+
+    /// The click limit a new link gets. Errors if the settings make no sense together.
+    pub fn click_limit(&self) -> Result<Option<ClickLimit>, DomainError> {
+        match (self.max_clicks, &self.exhausted_url) {
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(DomainError::MalformedInput("exhaustedUrl needs maxClicks".to_string())),
+            (Some(0), _) => Err(DomainError::MalformedInput("maxClicks must be at least 1".to_string())),
+            (Some(max_clicks), exhausted_url) => {
+                if let Some(url) = exhausted_url {
+                    validate_original_url(url)?;
+                }
+                let max_clicks = i32::try_from(max_clicks)
+                    .map_err(|_| DomainError::MalformedInput("maxClicks is too large".to_string()))?;
+                Ok(Some(ClickLimit::new(max_clicks, exhausted_url.clone())))
+            }
+        }
+    }
+}
+
+/// A link that stops redirecting after a number of clicks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickLimit {
+    pub max_clicks: i32,
+    /// Clicks left; spent atomically by the repository, never below zero.
+    pub remaining: i32,
+    pub exhausted_url: Option<String>,
+}
+
+impl ClickLimit {
+    pub fn new(max_clicks: i32, exhausted_url: Option<String>) -> Self {
+        Self {
+            max_clicks,
+            remaining: max_clicks,
+            exhausted_url,
+        }
+    }
+
+    /// Whether two limits were configured alike, regardless of clicks spent since.
+    pub fn same_settings(&self, other: &ClickLimit) -> bool {
+        self.max_clicks == other.max_clicks && self.exhausted_url == other.exhausted_url
+    }
+}
+
+/// When a link redirects. Either end may be open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveWindow {
+    pub active_from: Option<DateTime<Utc>>,
+    pub active_until: Option<DateTime<Utc>>,
+    pub not_yet_active_url: Option<String>,
+    pub ended_url: Option<String>,
+}
+
+impl ActiveWindow {
+    /// Whether the link redirects at `now`, or the outcome and fallback it answers with instead.
+    /// The window includes `active_from` and excludes `active_until`.
+    pub fn check(&self, now: DateTime<Utc>) -> Result<(), DomainError> {
+        if self.active_from.is_some_and(|from| now < from) {
+            return Err(DomainError::LinkNotYetActive(self.not_yet_active_url.clone()));
+        }
+        if self.active_until.is_some_and(|until| now >= until) {
+            return Err(DomainError::LinkEnded(self.ended_url.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Url {
+    pub domain: String,
     pub short_key: ShortKey,
     pub original_url: String,
+    pub workspace_id: String,
     pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Argon2 hash of the link password; `None` for public links.
+    pub password_hash: Option<String>,
+    /// `None` for links without a click limit.
+    pub click_limit: Option<ClickLimit>,
+    /// `None` for links that are always active.
+    pub active_window: Option<ActiveWindow>,
+    /// Empty for links that send every visitor to `original_url`.
+    pub platform_routes: PlatformRoutes,
+    /// Empty for links that send visitors from every country to the same place.
+    pub country_routes: CountryRoutes,
+    /// Empty for links that don't split traffic.
+    pub variants: Variants,
+    /// `None` for links that drop the visitor's query string.
+    pub forward_query: Option<QueryPolicy>,
+    /// Whether `/{short_key}/more/path` resolves, with `more/path` appended to the destination.
+    pub forward_path: bool,
+    /// `None` for links that redirect straight away, unless their domain shows a warning page.
+    pub interstitial: Option<Interstitial>,
 }
 
 impl Url {
     pub fn new(short_key: ShortKey, original_url: String) -> Self {
         Self {
+            domain: DEFAULT_DOMAIN.to_string(),
             short_key,
             original_url,
+            workspace_id: DEFAULT_WORKSPACE.to_string(),
             created_at: Utc::now(),
+            deleted_at: None,
+            password_hash: None,
+            click_limit: None,
+            active_window: None,
+            platform_routes: PlatformRoutes::default(),
+            country_routes: CountryRoutes::default(),
+            variants: Variants::default(),
+            forward_query: None,
+            forward_path: false,
+            interstitial: None,
         }
     }
+
+    pub fn with_workspace(mut self, workspace_id: impl Into<String>) -> Self {
+        self.workspace_id = workspace_id.into();
+        self
+    }
+
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = domain.into();
+        self
+    }
+
+    pub fn with_password_hash(mut self, password_hash: Option<String>) -> Self {
+        self.password_hash = password_hash;
+        self
+    }
+
+    pub fn with_click_limit(mut self, click_limit: Option<ClickLimit>) -> Self {
+        self.click_limit = click_limit;
+        self
+    }
+
+    pub fn with_active_window(mut self, active_window: Option<ActiveWindow>) -> Self {
+        self.active_window = active_window;
+        self
+    }
+
+    pub fn with_platform_routes(mut self, platform_routes: PlatformRoutes) -> Self {
+        self.platform_routes = platform_routes;
+        self
+    }
+
+    pub fn with_country_routes(mut self, country_routes: CountryRoutes) -> Self {
+        self.country_routes = country_routes;
+        self
+    }
+
+    pub fn with_variants(mut self, variants: Variants) -> Self {
+        self.variants = variants;
+        self
+    }
+
+    pub fn with_forwarding(mut self, forward_query: Option<QueryPolicy>, forward_path: bool) -> Self {
+        self.forward_query = forward_query;
+        self.forward_path = forward_path;
+        self
+    }
+
+    pub fn with_interstitial(mut self, interstitial: Option<Interstitial>) -> Self {
+        self.interstitial = interstitial;
+        self
+    }
+
+    /// The `Location` a visitor is redirected to: their destination, plus the path and query
+    /// they brought along if the link forwards them.
+    pub fn location(&self, visit: &Visit) -> String {
+        let extra_path = visit.extra_path.as_deref().filter(|_| self.forward_path);
+        forward(self.destination(visit), extra_path, visit.query.as_deref(), self.forward_query)
+    }
+
+    /// Where a visitor is sent: their platform's route, else their country's route, else
+    /// their variant, else `original_url`. Platforms win because app store links already
+    /// cover every country.
+    pub fn destination(&self, visit: &Visit) -> &str {
+        if let Some(route) = self.route(visit) {
+            return route;
+        }
+        self.variant(visit)
+            .and_then(|index| self.variants.get(index))
+            .map_or(&self.original_url, |variant| &variant.url)
+    }
+
+    /// The variant a visitor is sent to, if the link splits traffic and no platform or
+    /// country route takes them elsewhere. Visitors without an id get `original_url`.
+    pub fn variant(&self, visit: &Visit) -> Option<usize> {
+        if self.route(visit).is_some() {
+            return None;
+        }
+        let visitor_id = visit.visitor_id.as_deref()?;
+        self.variants.pick(visitor_id, &self.domain, self.short_key.as_str())
+    }
+
+    fn route(&self, visit: &Visit) -> Option<&str> {
+        let by_platform = visit.platform.and_then(|platform| self.platform_routes.destination(platform));
+        let by_country = || visit.country.as_deref().and_then(|country| self.country_routes.destination(country));
+        by_platform.or_else(by_country)
+    }
+
+    /// Whether the link has used up its click limit.
+    pub fn is_exhausted(&self) -> bool {
+        self.click_limit.as_ref().is_some_and(|limit| limit.remaining <= 0)
+    }
+
+    /// Whether a redirect may be answered permanently and cached. Links that can answer
+    /// differently per visitor or over time must be checked on every visit.
+    pub fn is_cacheable(&self) -> bool {
+        !self.is_protected()
+            && self.click_limit.is_none()
+            && self.active_window.is_none()
+            && self.platform_routes.is_empty()
+            && self.country_routes.is_empty()
+            && self.variants.is_empty()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.password_hash.is_some()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UrlResponse {
+    /// The full short URL, under the name it had before `short_url`; existing clients read it.
     pub short_key: String,
+    pub short_url: String,
     pub original_url: String,
     pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_clicks: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clicks_remaining: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "PlatformRoutes::is_empty")]
+    pub platform_urls: PlatformRoutes,
+    #[serde(default, skip_serializing_if = "CountryRoutes::is_empty")]
+    pub country_urls: CountryRoutes,
+    #[serde(default, skip_serializing_if = "Variants::is_empty")]
+    pub variants: Variants,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward_query: Option<QueryPolicy>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub forward_path: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interstitial: Option<Interstitial>,
 }
 
 impl UrlResponse {
-    pub fn new(url: Url, base_url: &str) -> Self {
+    /// The short URL is built from the link's own domain.
+    pub fn new(url: Url) -> Self {
+        let short_url = short_url(&url.domain, url.short_key.as_str());
         Self {
-            short_key: format!("{}/{}", base_url, url.short_key.as_str()),
+            short_key: short_url.clone(),
+            short_url,
+            password_protected: url.is_protected(),
+            max_clicks: url.click_limit.as_ref().map(|limit| limit.max_clicks),
+            clicks_remaining: url.click_limit.as_ref().map(|limit| limit.remaining),
+            active_from: url.active_window.as_ref().and_then(|window| window.active_from),
+            active_until: url.active_window.as_ref().and_then(|window| window.active_until),
+            platform_urls: url.platform_routes,
+            country_urls: url.country_routes,
+            variants: url.variants,
+            forward_query: url.forward_query,
+            forward_path: url.forward_path,
+            interstitial: url.interstitial,
             original_url: url.original_url,
             created_at: url.created_at,
         }
     }
-}
\ No newline at end of file
+}
+
+/// What `/{short_key}+` tells a visitor about a link before they follow it.
+#[derive(Debug, Clone)]
+pub struct LinkPreview {
+    pub short_url: String,
+    /// `None` for password-protected links: their destination is for people with the password.
+    pub destination: Option<String>,
+    /// Whether some visitors are sent elsewhere by platform, country or split.
+    pub routed: bool,
+    pub created_at: DateTime<Utc>,
+    /// The workspace the link belongs to. `None` once hidden from callers outside it.
+    pub owner: Option<String>,
+    /// `None` once hidden from callers outside the owning workspace.
+    pub clicks: Option<i64>,
+}
+
+impl LinkPreview {
+    pub fn new(url: &Url, clicks: i64) -> Self {
+        Self {
+            short_url: short_url(&url.domain, url.short_key.as_str()),
+            destination: (!url.is_protected()).then(|| url.original_url.clone()),
+            routed: !(url.platform_routes.is_empty() && url.country_routes.is_empty() && url.variants.is_empty()),
+            created_at: url.created_at,
+            owner: Some(url.workspace_id.clone()),
+            clicks: Some(clicks),
+        }
+    }
+
+    /// The preview as shown to anyone: who owns the link and how often it was followed stay private.
+    pub fn public(self) -> Self {
+        Self { owner: None, clicks: None, ..self }
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use std::fmt;
+
+/// Resources that are metered per workspace per calendar month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    Links,
+    Clicks,
+}
+
+impl fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaKind::Links => write!(f, "links"),
+            QuotaKind::Clicks => write!(f, "clicks"),
+        }
+    }
+}
+
+/// Monthly limits applied to every workspace. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuotaLimits {
+    pub max_links: Option<i64>,
+    pub max_clicks: Option<i64>,
+}
+
+impl QuotaLimits {
+    pub fn limit_for(&self, kind: QuotaKind) -> Option<i64> {
+        match kind {
+            QuotaKind::Links => self.max_links,
+            QuotaKind::Clicks => self.max_clicks,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Usage {
+    pub workspace_id: String,
+    pub period_start: NaiveDate,
+    pub links_created: i64,
+    pub clicks_tracked: i64,
+}
+
+impl Usage {
+    pub fn empty(workspace_id: &str, period_start: NaiveDate) -> Self {
+        Self {
+            workspace_id: workspace_id.to_string(),
+            period_start,
+            links_created: 0,
+            clicks_tracked: 0,
+        }
+    }
+}
+
+/// First day of the current UTC month — usage rows are keyed by it.
+pub fn current_period() -> NaiveDate {
+    let today = Utc::now().date_naive();
+    today.with_day(1).expect("day 1 exists in every month")
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaUsage {
+    pub used: i64,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageResponse {
+    pub workspace_id: String,
+    pub period_start: NaiveDate,
+    pub links: QuotaUsage,
+    pub clicks: QuotaUsage,
+}
+
+impl UsageResponse {
+    pub fn new(usage: Usage, limits: QuotaLimits) -> Self {
+        Self {
+            workspace_id: usage.workspace_id,
+            period_start: usage.period_start,
+            links: QuotaUsage {
+                used: usage.links_created,
+                limit: limits.max_links,
+            },
+            clicks: QuotaUsage {
+                used: usage.clicks_tracked,
+                limit: limits.max_clicks,
+            },
+        }
+    }
+}
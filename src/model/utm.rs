@@ -0,0 +1,122 @@
+use crate::model::errors::DomainError;
+use crate::model::url::DEFAULT_WORKSPACE;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Longest value accepted for a single UTM parameter.
+pub const MAX_UTM_VALUE_LEN: usize = 200;
+
+/// Campaign tracking parameters, appended to a destination as `utm_source`, `utm_medium`, ...
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Utm {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub medium: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+impl Utm {
+    /// Parameter names and values that are set, always in the same order.
+    fn params(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("utm_source", &self.source),
+            ("utm_medium", &self.medium),
+            ("utm_campaign", &self.campaign),
+            ("utm_term", &self.term),
+            ("utm_content", &self.content),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_deref().map(|value| (name, value)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params().next().is_none()
+    }
+
+    /// These parameters, with any left unset taken from `defaults`.
+    pub fn or(self, defaults: &Utm) -> Utm {
+        Utm {
+            source: self.source.or_else(|| defaults.source.clone()),
+            medium: self.medium.or_else(|| defaults.medium.clone()),
+            campaign: self.campaign.or_else(|| defaults.campaign.clone()),
+            term: self.term.or_else(|| defaults.term.clone()),
+            content: self.content.or_else(|| defaults.content.clone()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        for (name, value) in self.params() {
+            if value.trim().is_empty() || value.len() > MAX_UTM_VALUE_LEN {
+                return Err(DomainError::MalformedInput(format!(
+                    "{name} must be 1 to {MAX_UTM_VALUE_LEN} characters"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// `url` with these parameters set. Parameters the URL already has under the same names
+    /// are replaced, so tagging is deterministic: one base URL and one UTM set always give the
+    /// same destination.
+    pub fn apply(&self, url: &str) -> Result<String, DomainError> {
+        let mut parsed = ::url::Url::parse(url).map_err(|_| DomainError::InvalidUrl)?;
+        if self.is_empty() {
+            return Ok(url.to_string());
+        }
+        let kept: Vec<(String, String)> = parsed
+            .query_pairs()
+            .into_owned()
+            .filter(|(name, _)| self.params().all(|(set, _)| set != name))
+            .collect();
+        parsed
+            .query_pairs_mut()
+            .clear()
+            .extend_pairs(kept)
+            .extend_pairs(self.params());
+        Ok(parsed.to_string())
+    }
+}
+
+/// Named UTM defaults a workspace's links can be shortened with, e.g. `newsletter`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtmTemplate {
+    pub workspace_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub utm: Utm,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UtmTemplate {
+    pub fn new(workspace_id: Option<&str>, name: impl Into<String>, utm: Utm) -> Self {
+        Self {
+            workspace_id: workspace_id.unwrap_or(DEFAULT_WORKSPACE).to_string(),
+            name: name.into(),
+            utm,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Names are 1 to 64 letters, digits, `-` or `_`; templates also need at least one parameter.
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let name_ok = (1..=64).contains(&self.name.len())
+            && self.name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        if !name_ok {
+            return Err(DomainError::MalformedInput(format!(
+                "{:?} is not a valid template name: use 1 to 64 letters, digits, - or _",
+                self.name
+            )));
+        }
+        if self.utm.is_empty() {
+            return Err(DomainError::MalformedInput("a UTM template needs at least one parameter".to_string()));
+        }
+        self.utm.validate()
+    }
+}
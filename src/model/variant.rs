@@ -0,0 +1,89 @@
+use crate::model::errors::DomainError;
+use crate::model::url::validate_original_url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Most destinations one split link may have.
+pub const MAX_VARIANTS: usize = 10;
+
+/// Largest weight a single variant may carry; weights are relative, so this is plenty.
+pub const MAX_VARIANT_WEIGHT: u32 = 10_000;
+
+/// One destination of a split link, chosen for roughly `weight / total` of visitors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub url: String,
+    pub weight: u32,
+}
+
+/// Weighted destinations that split a link's traffic. Variants are numbered by position,
+/// so clicks can be compared per variant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Variants(Vec<Variant>);
+
+impl Variants {
+    pub fn with(mut self, url: impl Into<String>, weight: u32) -> Self {
+        self.0.push(Variant { url: url.into(), weight });
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        if !(2..=MAX_VARIANTS).contains(&self.0.len()) {
+            return Err(DomainError::MalformedInput(format!("a split link needs 2 to {MAX_VARIANTS} variants")));
+        }
+        for variant in &self.0 {
+            if !(1..=MAX_VARIANT_WEIGHT).contains(&variant.weight) {
+                return Err(DomainError::MalformedInput(format!(
+                    "variant weights must be between 1 and {MAX_VARIANT_WEIGHT}"
+                )));
+            }
+            validate_original_url(&variant.url)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Variant> {
+        self.0.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Variant> {
+        self.0.iter()
+    }
+
+    /// The variant a visitor is assigned to on the link `domain/short_key`. The same visitor
+    /// always lands on the same variant as long as the variants don't change, and visitors
+    /// spread across variants in proportion to their weights.
+    pub fn pick(&self, visitor_id: &str, domain: &str, short_key: &str) -> Option<usize> {
+        let total: u64 = self.0.iter().map(|variant| u64::from(variant.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let digest = Sha256::digest(format!("{domain}/{short_key}/{visitor_id}"));
+        let mut point = u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 is 32 bytes")) % total;
+        for (index, variant) in self.0.iter().enumerate() {
+            let weight = u64::from(variant.weight);
+            if point < weight {
+                return Some(index);
+            }
+            point -= weight;
+        }
+        None
+    }
+}
+
+/// Clicks a split link's variant has received.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantClicks {
+    pub variant: usize,
+    pub url: String,
+    pub weight: u32,
+    pub clicks: i64,
+}
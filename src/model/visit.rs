@@ -0,0 +1,59 @@
+use crate::model::platform::Platform;
+
+/// A request to follow a short link, with what the visitor brought along.
+#[derive(Debug, Clone, Default)]
+pub struct Visit {
+    /// `Host` the request was sent to; the default domain serves it when unregistered.
+    pub host: Option<String>,
+    /// Cookie from an earlier correct password for this link.
+    pub unlock_token: Option<String>,
+    /// Classified from the `User-Agent`; `None` when it is missing or unrecognised.
+    pub platform: Option<Platform>,
+    /// ISO 3166-1 alpha-2 code, uppercase; `None` when geolocation is off or found nothing.
+    pub country: Option<String>,
+    /// Sticky id from the visitor cookie; split links assign variants by it.
+    pub visitor_id: Option<String>,
+    /// Raw query string the short link was requested with.
+    pub query: Option<String>,
+    /// Path after `/{short_key}/`, for links that forward it.
+    pub extra_path: Option<String>,
+}
+
+impl Visit {
+    pub fn to_host(host: Option<&str>) -> Self {
+        Self {
+            host: host.map(str::to_string),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_unlock_token(mut self, token: impl Into<String>) -> Self {
+        self.unlock_token = Some(token.into());
+        self
+    }
+
+    pub fn on_platform(mut self, platform: Option<Platform>) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    pub fn from_country(mut self, country: Option<String>) -> Self {
+        self.country = country;
+        self
+    }
+
+    pub fn with_query(mut self, query: Option<String>) -> Self {
+        self.query = query;
+        self
+    }
+
+    pub fn with_extra_path(mut self, extra_path: impl Into<String>) -> Self {
+        self.extra_path = Some(extra_path.into());
+        self
+    }
+
+    pub fn as_visitor(mut self, visitor_id: impl Into<String>) -> Self {
+        self.visitor_id = Some(visitor_id.into());
+        self
+    }
+}
@@ -0,0 +1,108 @@
+use crate::model::api_key::ApiKey;
+use crate::model::audit::{AuditContext, AuditEvent};
+use crate::model::errors::DomainError;
+use crate::repository::audit_repository::record_event;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+
+
+type ApiKeyRow = (i64, String, String, String, NaiveDateTime, Option<NaiveDateTime>);
+
+const API_KEY_COLUMNS: &str = "id, workspace_id, name, prefix, created_at, revoked_at";
+
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn insert(&self, workspace_id: &str, name: &str, prefix: &str, secret_hash: &str) -> Result<ApiKey, DomainError>;
+    /// All keys, newest first, optionally limited to one workspace. Revoked keys are included.
+    async fn list(&self, workspace_id: Option<&str>) -> Result<Vec<ApiKey>, DomainError>;
+    /// The unrevoked key whose secret hashes to `secret_hash`, if any.
+    async fn find_active(&self, secret_hash: &str) -> Result<Option<ApiKey>, DomainError>;
+    /// Revokes an active key and records who did it. Returns `None` if no active key has this id.
+    async fn revoke(&self, id: i64, ctx: &AuditContext) -> Result<Option<ApiKey>, DomainError>;
+}
+
+pub struct PostgresApiKeyRepository {
+    pool: PgPool,
+}
+
+impl PostgresApiKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn row_into_api_key(row: ApiKeyRow) -> ApiKey {
+    ApiKey {
+        id: row.0,
+        workspace_id: row.1,
+        name: row.2,
+        prefix: row.3,
+        created_at: row.4.and_utc(),
+        revoked_at: row.5.map(|t| t.and_utc()),
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for PostgresApiKeyRepository {
+    async fn insert(&self, workspace_id: &str, name: &str, prefix: &str, secret_hash: &str) -> Result<ApiKey, DomainError> {
+        let row = sqlx::query_as::<_, ApiKeyRow>(&format!(
+            "INSERT INTO api_keys (workspace_id, name, prefix, secret_hash) VALUES ($1, $2, $3, $4) \
+             RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(workspace_id)
+        .bind(name)
+        .bind(prefix)
+        .bind(secret_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row_into_api_key(row))
+    }
+
+    async fn list(&self, workspace_id: Option<&str>) -> Result<Vec<ApiKey>, DomainError> {
+        let rows = sqlx::query_as::<_, ApiKeyRow>(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys \
+             WHERE ($1::TEXT IS NULL OR workspace_id = $1) ORDER BY id DESC"
+        ))
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_into_api_key).collect())
+    }
+
+    async fn find_active(&self, secret_hash: &str) -> Result<Option<ApiKey>, DomainError> {
+        let row = sqlx::query_as::<_, ApiKeyRow>(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys WHERE secret_hash = $1 AND revoked_at IS NULL"
+        ))
+        .bind(secret_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(row_into_api_key))
+    }
+
+    async fn revoke(&self, id: i64, ctx: &AuditContext) -> Result<Option<ApiKey>, DomainError> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query_as::<_, ApiKeyRow>(&format!(
+            "UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL \
+             RETURNING {API_KEY_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let key = row_into_api_key(row);
+
+        let event = AuditEvent::key_revoked(ctx, &key.prefix);
+        record_event(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        Ok(Some(key))
+    }
+}
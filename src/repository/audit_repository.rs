@@ -0,0 +1,125 @@
+use crate::model::audit::{AuditEvent, AuditFilter, AuditRecord};
+use crate::model::errors::DomainError;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::{PgConnection, PgPool};
+use tracing::instrument;
+
+
+type AuditRow = (i64, String, String, Option<String>, String, Option<String>, Option<String>, Option<String>, NaiveDateTime);
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    async fn list(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, DomainError>;
+}
+
+pub struct PostgresAuditRepository {
+    pool: PgPool,
+}
+
+impl PostgresAuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Appends an event on the caller's connection so it commits or rolls back
+/// together with the mutation it describes.
+pub(crate) async fn record_event(conn: &mut PgConnection, event: &AuditEvent) -> Result<(), DomainError> {
+    sqlx::query(
+        "INSERT INTO audit_events (actor, action, domain, short_key, before_url, after_url, request_id, created_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(&event.actor)
+    .bind(event.action.as_str())
+    .bind(&event.domain)
+    .bind(&event.short_key)
+    .bind(&event.before_url)
+    .bind(&event.after_url)
+    .bind(&event.request_id)
+    .bind(event.created_at.naive_utc())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Bulk variant of [`record_event`] — one statement regardless of batch size.
+pub(crate) async fn record_events(conn: &mut PgConnection, events: &[AuditEvent]) -> Result<(), DomainError> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let actors: Vec<&str> = events.iter().map(|e| e.actor.as_str()).collect();
+    let actions: Vec<&str> = events.iter().map(|e| e.action.as_str()).collect();
+    let domains: Vec<Option<&str>> = events.iter().map(|e| e.domain.as_deref()).collect();
+    let short_keys: Vec<&str> = events.iter().map(|e| e.short_key.as_str()).collect();
+    let before_urls: Vec<Option<&str>> = events.iter().map(|e| e.before_url.as_deref()).collect();
+    let after_urls: Vec<Option<&str>> = events.iter().map(|e| e.after_url.as_deref()).collect();
+    let request_ids: Vec<Option<&str>> = events.iter().map(|e| e.request_id.as_deref()).collect();
+    let created_ats: Vec<NaiveDateTime> = events.iter().map(|e| e.created_at.naive_utc()).collect();
+
+    sqlx::query(
+        "INSERT INTO audit_events (actor, action, domain, short_key, before_url, after_url, request_id, created_at) \
+         SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[], $5::TEXT[], $6::TEXT[], $7::VARCHAR[], $8::TIMESTAMP[])",
+    )
+    .bind(actors)
+    .bind(actions)
+    .bind(domains)
+    .bind(short_keys)
+    .bind(before_urls)
+    .bind(after_urls)
+    .bind(request_ids)
+    .bind(created_ats)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn row_into_record(row: AuditRow) -> AuditRecord {
+    AuditRecord {
+        id: row.0,
+        event: AuditEvent {
+            actor: row.1,
+            action: row.2.parse().expect("DB had invalid audit action"),
+            domain: row.3,
+            short_key: row.4,
+            before_url: row.5,
+            after_url: row.6,
+            request_id: row.7,
+            created_at: row.8.and_utc(),
+        },
+    }
+}
+
+#[async_trait]
+impl AuditRepository for PostgresAuditRepository {
+    #[instrument(skip_all)]
+    async fn list(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, DomainError> {
+        let rows = sqlx::query_as::<_, AuditRow>(
+            "SELECT id, actor, action, domain, short_key, before_url, after_url, request_id, created_at \
+             FROM audit_events \
+             WHERE ($1::TEXT IS NULL OR actor = $1) \
+               AND ($2::TEXT IS NULL OR action = $2) \
+               AND ($3::TEXT IS NULL OR short_key = $3) \
+               AND ($4::TIMESTAMP IS NULL OR created_at >= $4) \
+               AND ($5::TIMESTAMP IS NULL OR created_at < $5) \
+               AND ($6::BIGINT IS NULL OR id < $6) \
+               AND ($8::TEXT IS NULL OR domain = $8) \
+             ORDER BY id DESC \
+             LIMIT $7",)
+        .bind(&filter.actor)
+        .bind(filter.action.map(|action| action.as_str()))
+        .bind(&filter.short_key)
+        .bind(filter.since.map(|t| t.naive_utc()))
+        .bind(filter.until.map(|t| t.naive_utc()))
+        .bind(filter.before_id)
+        .bind(filter.limit)
+        .bind(&filter.domain)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_into_record).collect())
+    }
+}
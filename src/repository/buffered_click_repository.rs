@@ -0,0 +1,118 @@
+use crate::model::click::ClickEvent;
+use crate::model::errors::DomainError;
+use crate::repository::click_repository::ClickRepository;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Clicks written per statement.
+pub const CLICK_BATCH_SIZE: usize = 500;
+
+/// Longest a click waits in the buffer before it is written.
+pub const CLICK_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Clicks held in memory before new ones are dropped rather than slowing redirects down.
+pub const CLICK_QUEUE_CAPACITY: usize = 10_000;
+
+/// Takes click recording off the redirect path: `record` only enqueues, and a background
+/// writer inserts the queue in batches. The writer drains the queue when shut down.
+pub struct BufferedClickRepository<R> {
+    sender: mpsc::Sender<ClickEvent>,
+    inner: Arc<R>,
+}
+
+impl<R: ClickRepository + 'static> BufferedClickRepository<R> {
+    /// Starts the writer. It stops once `shutdown` is cancelled, after writing everything queued.
+    pub fn spawn(inner: R, shutdown: CancellationToken) -> (Self, JoinHandle<()>) {
+        let inner = Arc::new(inner);
+        let (sender, receiver) = mpsc::channel(CLICK_QUEUE_CAPACITY);
+        let writer = tokio::spawn(run_writer(inner.clone(), receiver, shutdown));
+        (Self { sender, inner }, writer)
+    }
+}
+
+async fn run_writer<R: ClickRepository>(
+    repo: Arc<R>,
+    mut receiver: mpsc::Receiver<ClickEvent>,
+    shutdown: CancellationToken,
+) {
+    let mut buffer = Vec::with_capacity(CLICK_BATCH_SIZE);
+    let mut ticker = tokio::time::interval(CLICK_FLUSH_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.cancelled() => break,
+            received = receiver.recv() => match received {
+                Some(click) => {
+                    buffer.push(click);
+                    if buffer.len() >= CLICK_BATCH_SIZE {
+                        flush(&*repo, &mut buffer).await;
+                    }
+                }
+                None => break,
+            },
+            _ = ticker.tick() => flush(&*repo, &mut buffer).await,
+        }
+    }
+
+    // Refuse new clicks, then write out everything already queued
+    receiver.close();
+    while let Some(click) = receiver.recv().await {
+        buffer.push(click);
+        if buffer.len() >= CLICK_BATCH_SIZE {
+            flush(&*repo, &mut buffer).await;
+        }
+    }
+    flush(&*repo, &mut buffer).await;
+    tracing::info!("click writer stopped");
+}
+
+async fn flush<R: ClickRepository>(repo: &R, buffer: &mut Vec<ClickEvent>) {
+    if buffer.is_empty() {
+        return;
+    }
+    if let Err(e) = repo.record_many(buffer).await {
+        tracing::error!(error = %e, dropped = buffer.len(), "failed to write click events");
+    }
+    buffer.clear();
+}
+
+#[async_trait]
+impl<R: ClickRepository + 'static> ClickRepository for BufferedClickRepository<R> {
+    // A lost click must never fail the redirect it belongs to, so overflow is logged, not returned
+    async fn record(&self, click: &ClickEvent) -> Result<(), DomainError> {
+        match self.sender.try_send(click.clone()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => tracing::warn!("click queue full, dropping click event"),
+            Err(TrySendError::Closed(_)) => tracing::warn!("click writer stopped, dropping click event"),
+        }
+        Ok(())
+    }
+
+    async fn record_many(&self, clicks: &[ClickEvent]) -> Result<(), DomainError> {
+        for click in clicks {
+            self.record(click).await?;
+        }
+        Ok(())
+    }
+
+    fn stream(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<ClickEvent, DomainError>> {
+        self.inner.stream(workspace_id)
+    }
+
+    /// Clicks still waiting in the buffer are not counted yet.
+    async fn count_by_variant(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<Vec<(i32, i64)>, DomainError> {
+        self.inner.count_by_variant(domain, workspace_id, short_key).await
+    }
+
+    /// Clicks still waiting in the buffer are not counted yet.
+    async fn count(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<i64, DomainError> {
+        self.inner.count(domain, workspace_id, short_key).await
+    }
+}
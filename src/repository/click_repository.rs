@@ -0,0 +1,140 @@
+use crate::model::click::ClickEvent;
+use crate::model::errors::DomainError;
+use crate::repository::cursor::cursor_stream;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::PgPool;
+use tracing::instrument;
+
+
+type ClickRow = (String, String, String, NaiveDateTime, Option<String>, Option<i32>);
+
+#[async_trait]
+pub trait ClickRepository: Send + Sync {
+    async fn record(&self, click: &ClickEvent) -> Result<(), DomainError>;
+
+    /// Inserts many clicks in one statement.
+    async fn record_many(&self, clicks: &[ClickEvent]) -> Result<(), DomainError>;
+
+    /// Every recorded click, oldest first, optionally limited to one workspace.
+    fn stream(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<ClickEvent, DomainError>>;
+
+    /// Clicks per variant position for a split link, lowest position first. Variants
+    /// nobody has clicked yet are missing.
+    async fn count_by_variant(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<Vec<(i32, i64)>, DomainError>;
+
+    /// All clicks a link has received.
+    async fn count(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<i64, DomainError>;
+}
+
+pub struct PostgresClickRepository {
+    pool: PgPool,
+}
+
+impl PostgresClickRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn row_into_click(row: ClickRow) -> ClickEvent {
+    ClickEvent {
+        domain: row.0,
+        short_key: row.1,
+        workspace_id: row.2,
+        clicked_at: row.3.and_utc(),
+        country: row.4,
+        variant: row.5,
+    }
+}
+
+#[async_trait]
+impl ClickRepository for PostgresClickRepository {
+    #[instrument(skip_all, fields(short_key = %click.short_key))]
+    async fn record(&self, click: &ClickEvent) -> Result<(), DomainError> {
+        sqlx::query(
+            "INSERT INTO click_events (domain, short_key, workspace_id, clicked_at, country, variant) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(&click.domain)
+        .bind(&click.short_key)
+        .bind(&click.workspace_id)
+        .bind(click.clicked_at.naive_utc())
+        .bind(&click.country)
+        .bind(click.variant)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(count = clicks.len()))]
+    async fn record_many(&self, clicks: &[ClickEvent]) -> Result<(), DomainError> {
+        if clicks.is_empty() {
+            return Ok(());
+        }
+
+        let domains: Vec<&str> = clicks.iter().map(|c| c.domain.as_str()).collect();
+        let short_keys: Vec<&str> = clicks.iter().map(|c| c.short_key.as_str()).collect();
+        let workspace_ids: Vec<&str> = clicks.iter().map(|c| c.workspace_id.as_str()).collect();
+        let clicked_ats: Vec<NaiveDateTime> = clicks.iter().map(|c| c.clicked_at.naive_utc()).collect();
+        let countries: Vec<Option<&str>> = clicks.iter().map(|c| c.country.as_deref()).collect();
+        let variants: Vec<Option<i32>> = clicks.iter().map(|c| c.variant).collect();
+
+        sqlx::query(
+            "INSERT INTO click_events (domain, short_key, workspace_id, clicked_at, country, variant) \
+             SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::TIMESTAMP[], $5::CHAR(2)[], $6::INTEGER[])",
+        )
+        .bind(domains)
+        .bind(short_keys)
+        .bind(workspace_ids)
+        .bind(clicked_ats)
+        .bind(countries)
+        .bind(variants)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    fn stream(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<ClickEvent, DomainError>> {
+        cursor_stream::<ClickRow>(
+            self.pool.clone(),
+            "SELECT domain, short_key, workspace_id, clicked_at, country, variant FROM click_events \
+             WHERE ($1::TEXT IS NULL OR workspace_id = $1) ORDER BY id".to_string(),
+            workspace_id.map(str::to_string),
+        )
+        .map(|row| row.map(row_into_click))
+        .boxed()
+    }
+
+    #[instrument(skip_all, fields(short_key))]
+    async fn count_by_variant(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<Vec<(i32, i64)>, DomainError> {
+        let rows = sqlx::query_as::<_, (i32, i64)>(
+            "SELECT variant, COUNT(*) FROM click_events \
+             WHERE domain = $1 AND workspace_id = $2 AND short_key = $3 AND variant IS NOT NULL \
+             GROUP BY variant ORDER BY variant",
+        )
+        .bind(domain)
+        .bind(workspace_id)
+        .bind(short_key)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    #[instrument(skip_all, fields(short_key))]
+    async fn count(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<i64, DomainError> {
+        let (count,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM click_events WHERE domain = $1 AND workspace_id = $2 AND short_key = $3",
+        )
+        .bind(domain)
+        .bind(workspace_id)
+        .bind(short_key)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+}
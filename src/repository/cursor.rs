@@ -0,0 +1,42 @@
+use crate::model::errors::DomainError;
+use async_stream::try_stream;
+use futures::stream::BoxStream;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool};
+
+/// Rows pulled from the server per round-trip while streaming an export.
+const CURSOR_BATCH: usize = 1000;
+
+/// Streams the rows of `select` through a server-side cursor so only one batch is
+/// held in memory at a time. `select` may reference a single text parameter `$1`
+/// (bound to `param`). The cursor lives in a read-only transaction that ends with the stream.
+pub(crate) fn cursor_stream<R>(
+    pool: PgPool,
+    select: String,
+    param: Option<String>,
+) -> BoxStream<'static, Result<R, DomainError>>
+where
+    R: for<'r> FromRow<'r, PgRow> + Send + Unpin + 'static,
+{
+    Box::pin(try_stream! {
+        let mut tx = pool.begin().await?;
+        sqlx::query("SET TRANSACTION READ ONLY").execute(&mut *tx).await?;
+        sqlx::query(&format!("DECLARE export_cursor NO SCROLL CURSOR FOR {select}"))
+            .bind(param)
+            .execute(&mut *tx)
+            .await?;
+
+        let fetch = format!("FETCH {CURSOR_BATCH} FROM export_cursor");
+        loop {
+            let rows = sqlx::query_as::<_, R>(&fetch).fetch_all(&mut *tx).await?;
+            if rows.is_empty() {
+                break;
+            }
+            for row in rows {
+                yield row;
+            }
+        }
+
+        tx.commit().await?;
+    })
+}
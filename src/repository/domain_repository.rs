@@ -0,0 +1,113 @@
+use crate::model::domain::{Domain, DomainFallbacks};
+use crate::model::errors::DomainError;
+use crate::model::interstitial::Interstitial;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use tracing::instrument;
+
+
+type DomainRow = (String, Option<String>, NaiveDateTime, Option<String>, Option<String>, Option<String>, Option<i32>);
+
+const DOMAIN_COLUMNS: &str = "host, workspace_id, created_at, root_url, not_found_url, not_found_page, interstitial_countdown";
+
+#[async_trait]
+pub trait DomainRepository: Send + Sync {
+    async fn find(&self, host: &str) -> Result<Option<Domain>, DomainError>;
+    /// Registers a domain. Returns `false` if the host is already registered.
+    async fn insert(&self, domain: &Domain) -> Result<bool, DomainError>;
+    /// All registered domains, alphabetically.
+    async fn list(&self) -> Result<Vec<Domain>, DomainError>;
+    /// Replaces the domain's fallbacks. `None` if the host is not registered.
+    async fn set_fallbacks(&self, host: &str, fallbacks: &DomainFallbacks) -> Result<Option<Domain>, DomainError>;
+    /// Sets or clears the domain's warning page. `None` if the host is not registered.
+    async fn set_interstitial(&self, host: &str, interstitial: Option<Interstitial>) -> Result<Option<Domain>, DomainError>;
+}
+
+pub struct PostgresDomainRepository {
+    pool: PgPool,
+}
+
+impl PostgresDomainRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn row_into_domain(row: DomainRow) -> Domain {
+    Domain {
+        host: row.0,
+        workspace_id: row.1,
+        created_at: row.2.and_utc(),
+        fallbacks: DomainFallbacks {
+            root_url: row.3,
+            not_found_url: row.4,
+            not_found_page: row.5,
+        },
+        interstitial: row.6.map(|countdown| Interstitial::new(countdown.max(0) as u32)),
+    }
+}
+
+#[async_trait]
+impl DomainRepository for PostgresDomainRepository {
+    #[instrument(skip_all, fields(host))]
+    async fn find(&self, host: &str) -> Result<Option<Domain>, DomainError> {
+        let row = sqlx::query_as::<_, DomainRow>(&format!(
+            "SELECT {DOMAIN_COLUMNS} FROM domains WHERE host = $1"))
+        .bind(host)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(row_into_domain))
+    }
+
+    #[instrument(skip_all, fields(host = domain.host.as_str()))]
+    async fn insert(&self, domain: &Domain) -> Result<bool, DomainError> {
+        let result = sqlx::query(
+            "INSERT INTO domains (host, workspace_id, created_at) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",)
+        .bind(&domain.host)
+        .bind(&domain.workspace_id)
+        .bind(domain.created_at.naive_utc())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(skip_all)]
+    async fn list(&self) -> Result<Vec<Domain>, DomainError> {
+        let rows = sqlx::query_as::<_, DomainRow>(&format!(
+            "SELECT {DOMAIN_COLUMNS} FROM domains ORDER BY host"))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_into_domain).collect())
+    }
+
+    #[instrument(skip_all, fields(host))]
+    async fn set_fallbacks(&self, host: &str, fallbacks: &DomainFallbacks) -> Result<Option<Domain>, DomainError> {
+        let row = sqlx::query_as::<_, DomainRow>(&format!(
+            "UPDATE domains SET root_url = $2, not_found_url = $3, not_found_page = $4
+             WHERE host = $1 RETURNING {DOMAIN_COLUMNS}"))
+        .bind(host)
+        .bind(&fallbacks.root_url)
+        .bind(&fallbacks.not_found_url)
+        .bind(&fallbacks.not_found_page)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(row_into_domain))
+    }
+
+    #[instrument(skip_all, fields(host))]
+    async fn set_interstitial(&self, host: &str, interstitial: Option<Interstitial>) -> Result<Option<Domain>, DomainError> {
+        let row = sqlx::query_as::<_, DomainRow>(&format!(
+            "UPDATE domains SET interstitial_countdown = $2 WHERE host = $1 RETURNING {DOMAIN_COLUMNS}"))
+        .bind(host)
+        .bind(interstitial.map(|interstitial| interstitial.countdown as i32))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(row_into_domain))
+    }
+}
@@ -0,0 +1,87 @@
+use crate::metrics::Metrics;
+use crate::model::audit::AuditContext;
+use crate::model::errors::DomainError;
+use crate::model::short_key::ShortKey;
+use crate::model::Url;
+use crate::repository::url_repository::UrlRepository;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Wraps any `UrlRepository` and records how long each call takes, labelled by method.
+pub struct MeteredUrlRepository<R> {
+    inner: R,
+    metrics: Arc<Metrics>,
+}
+
+impl<R: UrlRepository> MeteredUrlRepository<R> {
+    pub fn new(inner: R, metrics: Arc<Metrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    async fn timed<T>(&self, method: &str, call: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        let result = call.await;
+        self.metrics.observe_query(method, started.elapsed().as_secs_f64());
+        result
+    }
+}
+
+#[async_trait]
+impl<R: UrlRepository> UrlRepository for MeteredUrlRepository<R> {
+    async fn find_by_original_url(&self, domain: &str, workspace_id: &str, original_url: &str) -> Result<Option<Url>, DomainError> {
+        self.timed("find_by_original_url", self.inner.find_by_original_url(domain, workspace_id, original_url)).await
+    }
+
+    async fn find_by_short_key(&self, domain: &str, short_key: &ShortKey) -> Result<Option<Url>, DomainError> {
+        self.timed("find_by_short_key", self.inner.find_by_short_key(domain, short_key)).await
+    }
+
+    async fn find_by_original_urls(&self, domain: &str, workspace_id: &str, original_urls: &[String]) -> Result<Vec<Url>, DomainError> {
+        self.timed("find_by_original_urls", self.inner.find_by_original_urls(domain, workspace_id, original_urls)).await
+    }
+
+    async fn existing_short_keys(&self, domain: &str, short_keys: &[String]) -> Result<Vec<String>, DomainError> {
+        self.timed("existing_short_keys", self.inner.existing_short_keys(domain, short_keys)).await
+    }
+
+    async fn insert(&self, url: &Url, ctx: &AuditContext) -> Result<(), DomainError> {
+        self.timed("insert", self.inner.insert(url, ctx)).await
+    }
+
+    async fn insert_many(&self, urls: &[Url], ctx: &AuditContext) -> Result<Vec<Url>, DomainError> {
+        self.timed("insert_many", self.inner.insert_many(urls, ctx)).await
+    }
+
+    async fn import_many(&self, urls: &[Url], ctx: &AuditContext) -> Result<Vec<(String, String)>, DomainError> {
+        self.timed("import_many", self.inner.import_many(urls, ctx)).await
+    }
+
+    async fn retarget(&self, domain: &str, short_key: &ShortKey, original_url: &str, ctx: &AuditContext) -> Result<Option<Url>, DomainError> {
+        self.timed("retarget", self.inner.retarget(domain, short_key, original_url, ctx)).await
+    }
+
+    async fn delete(&self, domain: &str, short_key: &ShortKey, deleted_at: DateTime<Utc>, ctx: &AuditContext) -> Result<bool, DomainError> {
+        self.timed("delete", self.inner.delete(domain, short_key, deleted_at, ctx)).await
+    }
+
+    async fn restore(&self, domain: &str, short_key: &ShortKey, ctx: &AuditContext) -> Result<Option<Url>, DomainError> {
+        self.timed("restore", self.inner.restore(domain, short_key, ctx)).await
+    }
+
+    async fn take_click(&self, domain: &str, short_key: &ShortKey) -> Result<bool, DomainError> {
+        self.timed("take_click", self.inner.take_click(domain, short_key)).await
+    }
+
+    async fn return_click(&self, domain: &str, short_key: &ShortKey) -> Result<(), DomainError> {
+        self.timed("return_click", self.inner.return_click(domain, short_key)).await
+    }
+
+    // Streams are consumed at the caller's pace, so wall time here would measure the client, not the DB
+    fn stream_live(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<Url, DomainError>> {
+        self.inner.stream_live(workspace_id)
+    }
+}
@@ -1 +1,24 @@
 pub mod url_repository;
+pub mod usage_repository;
+pub mod audit_repository;
+pub mod click_repository;
+pub mod api_key_repository;
+pub mod domain_repository;
+pub mod utm_template_repository;
+pub mod metered_url_repository;
+pub mod buffered_click_repository;
+mod cursor;
+
+/// Every migration under `./migrations`, embedded at compile time.
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+/// Runs [`MIGRATOR`] on a single connection with `redirect.default_domain` set, so migrations
+/// that place existing rows on a domain use the configured one instead of assuming it.
+pub async fn migrate(pool: &sqlx::PgPool, default_domain: &str) -> Result<(), sqlx::migrate::MigrateError> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT set_config('redirect.default_domain', $1, false)")
+        .bind(default_domain)
+        .execute(&mut *conn)
+        .await?;
+    MIGRATOR.run(&mut *conn).await
+}
@@ -1,18 +1,85 @@
+use crate::model::audit::{AuditAction, AuditContext, AuditEvent};
 use crate::model::errors::DomainError;
 use crate::model::short_key::ShortKey;
-use crate::model::Url;
+use crate::model::{ActiveWindow, ClickLimit, CountryRoutes, Interstitial, PlatformRoutes, Url, Variants};
+use crate::repository::audit_repository::{record_event, record_events};
+use crate::repository::cursor::cursor_stream;
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
+use futures::stream::{BoxStream, StreamExt};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::types::Json;
 use sqlx::PgPool;
+use tracing::instrument;
 
 
-type UrlRow = (String, String, NaiveDateTime);
+#[derive(sqlx::FromRow)]
+struct UrlRow {
+    domain: String,
+    short_key: String,
+    original_url: String,
+    workspace_id: String,
+    created_at: NaiveDateTime,
+    deleted_at: Option<NaiveDateTime>,
+    password_hash: Option<String>,
+    max_clicks: Option<i32>,
+    clicks_remaining: Option<i32>,
+    exhausted_url: Option<String>,
+    active_from: Option<NaiveDateTime>,
+    active_until: Option<NaiveDateTime>,
+    not_yet_active_url: Option<String>,
+    ended_url: Option<String>,
+    ios_url: Option<String>,
+    android_url: Option<String>,
+    desktop_url: Option<String>,
+    country_urls: Json<CountryRoutes>,
+    variants: Json<Variants>,
+    forward_query: Option<String>,
+    forward_path: bool,
+    interstitial_countdown: Option<i32>,
+}
+
+const URL_COLUMNS: &str = "domain, short_key, original_url, workspace_id, created_at, deleted_at, password_hash, \
+                           max_clicks, clicks_remaining, exhausted_url, \
+                           active_from, active_until, not_yet_active_url, ended_url, \
+                           ios_url, android_url, desktop_url, country_urls, variants, \
+                           forward_query, forward_path, interstitial_countdown";
 
+/// Links are identified by domain and short key together; the same key may exist on
+/// several domains. Every mutation writes its audit event in the same transaction as the change.
 #[async_trait]
 pub trait UrlRepository: Send + Sync {
-    async fn find_by_original_url(&self, original_url: &str) -> Result<Option<Url>, DomainError>;
-    async fn find_by_short_key(&self, short_key: &ShortKey) -> Result<Option<Url>, DomainError>;
-    async fn insert(&self, url: &Url) -> Result<(), DomainError>;
+    /// Only live (non-deleted) links of `workspace_id` are matched.
+    async fn find_by_original_url(&self, domain: &str, workspace_id: &str, original_url: &str) -> Result<Option<Url>, DomainError>;
+    /// Deleted links are returned too so their keys are never reissued.
+    async fn find_by_short_key(&self, domain: &str, short_key: &ShortKey) -> Result<Option<Url>, DomainError>;
+    /// Live links of `workspace_id` on `domain` for any of the given destinations, in one round-trip.
+    async fn find_by_original_urls(&self, domain: &str, workspace_id: &str, original_urls: &[String]) -> Result<Vec<Url>, DomainError>;
+    /// The subset of `short_keys` that are already taken on `domain` (live or deleted).
+    async fn existing_short_keys(&self, domain: &str, short_keys: &[String]) -> Result<Vec<String>, DomainError>;
+    /// Fails with `DestinationTaken` if the key or the live destination was taken since it was
+    /// looked up.
+    async fn insert(&self, url: &Url, ctx: &AuditContext) -> Result<(), DomainError>;
+    /// Inserts many links in one statement. Links whose destination was concurrently
+    /// shortened by someone else are skipped; only the inserted links are returned.
+    async fn insert_many(&self, urls: &[Url], ctx: &AuditContext) -> Result<Vec<Url>, DomainError>;
+    /// Inserts links with caller-chosen keys and timestamps in one transaction.
+    /// Rows that collide on key or live destination are skipped; returns the
+    /// `(domain, short_key)` pairs that went in.
+    async fn import_many(&self, urls: &[Url], ctx: &AuditContext) -> Result<Vec<(String, String)>, DomainError>;
+    /// Points a live link at a new destination. Returns `None` if there is no live link.
+    async fn retarget(&self, domain: &str, short_key: &ShortKey, original_url: &str, ctx: &AuditContext) -> Result<Option<Url>, DomainError>;
+    /// Soft-deletes a live link as of `deleted_at`. Returns `false` if there is no live link.
+    async fn delete(&self, domain: &str, short_key: &ShortKey, deleted_at: DateTime<Utc>, ctx: &AuditContext) -> Result<bool, DomainError>;
+    /// Brings back a deleted link. Returns `None` if there is no deleted link.
+    async fn restore(&self, domain: &str, short_key: &ShortKey, ctx: &AuditContext) -> Result<Option<Url>, DomainError>;
+    /// Spends one click of a live, limited link in a single conditional update, so concurrent
+    /// visits can never exceed the limit. Returns `false` if no clicks were left.
+    async fn take_click(&self, domain: &str, short_key: &ShortKey) -> Result<bool, DomainError>;
+    /// Gives back a click taken by `take_click` for a visit that was refused afterwards. Never
+    /// raises the count above the link's limit.
+    async fn return_click(&self, domain: &str, short_key: &ShortKey) -> Result<(), DomainError>;
+    /// Every live link, oldest first, optionally limited to one workspace.
+    fn stream_live(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<Url, DomainError>>;
 }
 
 pub struct PostgresUrlRepository {
@@ -25,19 +92,57 @@ impl PostgresUrlRepository {
     }
 }
 
+// Live destinations are unique per domain and workspace, so moving a link onto one another
+// live link already uses is a conflict rather than a database failure
+fn destination_taken(error: sqlx::Error) -> DomainError {
+    match error {
+        sqlx::Error::Database(db) if db.is_unique_violation() => DomainError::DestinationTaken,
+        other => other.into(),
+    }
+}
+
 fn row_into_url(row: UrlRow) -> Url {
     Url {
-        short_key: ShortKey::new(row.0).expect("DB had invalid short_key"),
-        original_url: row.1,
-        created_at: row.2.and_utc(),
+        domain: row.domain,
+        short_key: ShortKey::new(row.short_key).expect("DB had invalid short_key"),
+        original_url: row.original_url,
+        workspace_id: row.workspace_id,
+        created_at: row.created_at.and_utc(),
+        deleted_at: row.deleted_at.map(|t| t.and_utc()),
+        password_hash: row.password_hash,
+        click_limit: row.max_clicks.map(|max_clicks| ClickLimit {
+            max_clicks,
+            remaining: row.clicks_remaining.unwrap_or(0),
+            exhausted_url: row.exhausted_url,
+        }),
+        active_window: (row.active_from.is_some() || row.active_until.is_some()).then(|| ActiveWindow {
+            active_from: row.active_from.map(|t| t.and_utc()),
+            active_until: row.active_until.map(|t| t.and_utc()),
+            not_yet_active_url: row.not_yet_active_url,
+            ended_url: row.ended_url,
+        }),
+        platform_routes: PlatformRoutes {
+            ios: row.ios_url,
+            android: row.android_url,
+            desktop: row.desktop_url,
+        },
+        country_routes: row.country_urls.0,
+        variants: row.variants.0,
+        forward_query: row.forward_query.map(|policy| policy.parse().expect("DB had invalid forward_query")),
+        forward_path: row.forward_path,
+        interstitial: row.interstitial_countdown.map(|countdown| Interstitial::new(countdown.max(0) as u32)),
     }
 }
 
 #[async_trait]
 impl UrlRepository for PostgresUrlRepository {
-    async fn find_by_original_url(&self, original_url: &str) -> Result<Option<Url>, DomainError> {
-        let row = sqlx::query_as::<_, UrlRow>(
-            "SELECT short_key, original_url, created_at FROM urls WHERE original_url = $1",)
+    #[instrument(skip_all, fields(domain, workspace_id))]
+    async fn find_by_original_url(&self, domain: &str, workspace_id: &str, original_url: &str) -> Result<Option<Url>, DomainError> {
+        let row = sqlx::query_as::<_, UrlRow>(&format!(
+            "SELECT {URL_COLUMNS} FROM urls \
+             WHERE domain = $1 AND workspace_id = $2 AND original_url = $3 AND deleted_at IS NULL"))
+        .bind(domain)
+        .bind(workspace_id)
         .bind(original_url)
         .fetch_optional(&self.pool)
         .await?;
@@ -45,9 +150,11 @@ impl UrlRepository for PostgresUrlRepository {
         Ok(row.map(row_into_url))
     }
 
-    async fn find_by_short_key(&self, short_key: &ShortKey) -> Result<Option<Url>, DomainError> {
-        let row = sqlx::query_as::<_, UrlRow>(
-            "SELECT short_key, original_url, created_at FROM urls WHERE short_key = $1",)
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    async fn find_by_short_key(&self, domain: &str, short_key: &ShortKey) -> Result<Option<Url>, DomainError> {
+        let row = sqlx::query_as::<_, UrlRow>(&format!(
+            "SELECT {URL_COLUMNS} FROM urls WHERE domain = $1 AND short_key = $2"))
+        .bind(domain)
         .bind(short_key.as_str())
         .fetch_optional(&self.pool)
         .await?;
@@ -55,16 +162,329 @@ impl UrlRepository for PostgresUrlRepository {
         Ok(row.map(row_into_url))
     }
 
-    async fn insert(&self, url: &Url) -> Result<(), DomainError> {
+    #[instrument(skip_all, fields(domain, workspace_id, count = original_urls.len()))]
+    async fn find_by_original_urls(&self, domain: &str, workspace_id: &str, original_urls: &[String]) -> Result<Vec<Url>, DomainError> {
+        let rows = sqlx::query_as::<_, UrlRow>(&format!(
+            "SELECT {URL_COLUMNS} FROM urls \
+             WHERE domain = $1 AND workspace_id = $2 AND original_url = ANY($3) AND deleted_at IS NULL"))
+        .bind(domain)
+        .bind(workspace_id)
+        .bind(original_urls)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_into_url).collect())
+    }
+
+    #[instrument(skip_all, fields(domain, count = short_keys.len()))]
+    async fn existing_short_keys(&self, domain: &str, short_keys: &[String]) -> Result<Vec<String>, DomainError> {
+        let rows = sqlx::query_as::<_, (String,)>(
+            "SELECT short_key FROM urls WHERE domain = $1 AND short_key = ANY($2)",)
+        .bind(domain)
+        .bind(short_keys)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(key,)| key).collect())
+    }
+
+    #[instrument(skip_all, fields(count = urls.len()))]
+    async fn insert_many(&self, urls: &[Url], ctx: &AuditContext) -> Result<Vec<Url>, DomainError> {
+        if urls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let domains: Vec<&str> = urls.iter().map(|u| u.domain.as_str()).collect();
+        let short_keys: Vec<&str> = urls.iter().map(|u| u.short_key.as_str()).collect();
+        let original_urls: Vec<&str> = urls.iter().map(|u| u.original_url.as_str()).collect();
+        let workspace_ids: Vec<&str> = urls.iter().map(|u| u.workspace_id.as_str()).collect();
+        let created_ats: Vec<NaiveDateTime> = urls.iter().map(|u| u.created_at.naive_utc()).collect();
+        let password_hashes: Vec<Option<&str>> = urls.iter().map(|u| u.password_hash.as_deref()).collect();
+        let max_clicks: Vec<Option<i32>> = urls.iter().map(|u| u.click_limit.as_ref().map(|l| l.max_clicks)).collect();
+        let exhausted_urls: Vec<Option<&str>> = urls
+            .iter()
+            .map(|u| u.click_limit.as_ref().and_then(|l| l.exhausted_url.as_deref()))
+            .collect();
+        let windows: Vec<Option<&ActiveWindow>> = urls.iter().map(|u| u.active_window.as_ref()).collect();
+        let active_froms: Vec<Option<NaiveDateTime>> = windows
+            .iter()
+            .map(|w| w.and_then(|w| w.active_from).map(|t| t.naive_utc()))
+            .collect();
+        let active_untils: Vec<Option<NaiveDateTime>> = windows
+            .iter()
+            .map(|w| w.and_then(|w| w.active_until).map(|t| t.naive_utc()))
+            .collect();
+        let not_yet_active_urls: Vec<Option<&str>> = windows
+            .iter()
+            .map(|w| w.and_then(|w| w.not_yet_active_url.as_deref()))
+            .collect();
+        let ended_urls: Vec<Option<&str>> = windows.iter().map(|w| w.and_then(|w| w.ended_url.as_deref())).collect();
+        let ios_urls: Vec<Option<&str>> = urls.iter().map(|u| u.platform_routes.ios.as_deref()).collect();
+        let android_urls: Vec<Option<&str>> = urls.iter().map(|u| u.platform_routes.android.as_deref()).collect();
+        let desktop_urls: Vec<Option<&str>> = urls.iter().map(|u| u.platform_routes.desktop.as_deref()).collect();
+        let country_urls: Vec<Json<&CountryRoutes>> = urls.iter().map(|u| Json(&u.country_routes)).collect();
+        let variants: Vec<Json<&Variants>> = urls.iter().map(|u| Json(&u.variants)).collect();
+        let forward_queries: Vec<Option<&str>> = urls.iter().map(|u| u.forward_query.map(|policy| policy.as_str())).collect();
+        let forward_paths: Vec<bool> = urls.iter().map(|u| u.forward_path).collect();
+        let interstitial_countdowns: Vec<Option<i32>> =
+            urls.iter().map(|u| u.interstitial.map(|interstitial| interstitial.countdown as i32)).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        let rows = sqlx::query_as::<_, UrlRow>(&format!(
+            "INSERT INTO urls (domain, short_key, original_url, workspace_id, created_at, password_hash, \
+                               max_clicks, clicks_remaining, exhausted_url, \
+                               active_from, active_until, not_yet_active_url, ended_url, \
+                               ios_url, android_url, desktop_url, country_urls, variants, \
+                               forward_query, forward_path, interstitial_countdown) \
+             SELECT domain, short_key, original_url, workspace_id, created_at, password_hash, \
+                    max_clicks, max_clicks, exhausted_url, \
+                    active_from, active_until, not_yet_active_url, ended_url, \
+                    ios_url, android_url, desktop_url, country_urls, variants, \
+                    forward_query, forward_path, interstitial_countdown \
+             FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::TEXT[], $4::VARCHAR[], $5::TIMESTAMP[], $6::TEXT[], \
+                         $7::INTEGER[], $8::TEXT[], $9::TIMESTAMP[], $10::TIMESTAMP[], $11::TEXT[], $12::TEXT[], \
+                         $13::TEXT[], $14::TEXT[], $15::TEXT[], $16::JSONB[], $17::JSONB[], \
+                         $18::TEXT[], $19::BOOLEAN[], $20::INTEGER[]) \
+                  AS t (domain, short_key, original_url, workspace_id, created_at, password_hash, max_clicks, exhausted_url, \
+                        active_from, active_until, not_yet_active_url, ended_url, ios_url, android_url, desktop_url, \
+                        country_urls, variants, forward_query, forward_path, interstitial_countdown) \
+             ON CONFLICT (domain, workspace_id, original_url) WHERE deleted_at IS NULL DO NOTHING \
+             RETURNING {URL_COLUMNS}"))
+        .bind(domains)
+        .bind(short_keys)
+        .bind(original_urls)
+        .bind(workspace_ids)
+        .bind(created_ats)
+        .bind(password_hashes)
+        .bind(max_clicks)
+        .bind(exhausted_urls)
+        .bind(active_froms)
+        .bind(active_untils)
+        .bind(not_yet_active_urls)
+        .bind(ended_urls)
+        .bind(ios_urls)
+        .bind(android_urls)
+        .bind(desktop_urls)
+        .bind(country_urls)
+        .bind(variants)
+        .bind(forward_queries)
+        .bind(forward_paths)
+        .bind(interstitial_countdowns)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let inserted: Vec<Url> = rows.into_iter().map(row_into_url).collect();
+        let events: Vec<AuditEvent> = inserted
+            .iter()
+            .map(|url| AuditEvent::new(ctx, AuditAction::Create, &url.domain, url.short_key.as_str(), None, Some(url.original_url.clone())))
+            .collect();
+        record_events(&mut tx, &events).await?;
+
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    #[instrument(skip_all, fields(count = urls.len()))]
+    async fn import_many(&self, urls: &[Url], ctx: &AuditContext) -> Result<Vec<(String, String)>, DomainError> {
+        if urls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let domains: Vec<&str> = urls.iter().map(|u| u.domain.as_str()).collect();
+        let short_keys: Vec<&str> = urls.iter().map(|u| u.short_key.as_str()).collect();
+        let original_urls: Vec<&str> = urls.iter().map(|u| u.original_url.as_str()).collect();
+        let workspace_ids: Vec<&str> = urls.iter().map(|u| u.workspace_id.as_str()).collect();
+        let created_ats: Vec<NaiveDateTime> = urls.iter().map(|u| u.created_at.naive_utc()).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        // No conflict target: both the key and the live-destination index count as conflicts
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            "INSERT INTO urls (domain, short_key, original_url, workspace_id, created_at) \
+             SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::TEXT[], $4::VARCHAR[], $5::TIMESTAMP[]) \
+             ON CONFLICT DO NOTHING \
+             RETURNING domain, short_key, original_url",)
+        .bind(domains)
+        .bind(short_keys)
+        .bind(original_urls)
+        .bind(workspace_ids)
+        .bind(created_ats)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let events: Vec<AuditEvent> = rows
+            .iter()
+            .map(|(domain, key, url)| AuditEvent::new(ctx, AuditAction::Import, domain, key, None, Some(url.clone())))
+            .collect();
+        record_events(&mut tx, &events).await?;
+
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|(domain, key, _)| (domain, key)).collect())
+    }
+
+    #[instrument(skip_all, fields(domain = url.domain.as_str(), short_key = url.short_key.as_str()))]
+    async fn insert(&self, url: &Url, ctx: &AuditContext) -> Result<(), DomainError> {
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query(
-            "INSERT INTO urls (short_key, original_url, created_at) VALUES ($1, $2, $3)",
+            "INSERT INTO urls (domain, short_key, original_url, workspace_id, created_at, password_hash, \
+                               max_clicks, clicks_remaining, exhausted_url, \
+                               active_from, active_until, not_yet_active_url, ended_url, \
+                               ios_url, android_url, desktop_url, country_urls, variants, \
+                               forward_query, forward_path, interstitial_countdown) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)",
         )
+        .bind(&url.domain)
         .bind(url.short_key.as_str())
         .bind(&url.original_url)
+        .bind(&url.workspace_id)
         .bind(url.created_at.naive_utc())
+        .bind(&url.password_hash)
+        .bind(url.click_limit.as_ref().map(|limit| limit.max_clicks))
+        .bind(url.click_limit.as_ref().map(|limit| limit.remaining))
+        .bind(url.click_limit.as_ref().and_then(|limit| limit.exhausted_url.as_deref()))
+        .bind(url.active_window.as_ref().and_then(|window| window.active_from).map(|t| t.naive_utc()))
+        .bind(url.active_window.as_ref().and_then(|window| window.active_until).map(|t| t.naive_utc()))
+        .bind(url.active_window.as_ref().and_then(|window| window.not_yet_active_url.as_deref()))
+        .bind(url.active_window.as_ref().and_then(|window| window.ended_url.as_deref()))
+        .bind(&url.platform_routes.ios)
+        .bind(&url.platform_routes.android)
+        .bind(&url.platform_routes.desktop)
+        .bind(Json(&url.country_routes))
+        .bind(Json(&url.variants))
+        .bind(url.forward_query.map(|policy| policy.as_str()))
+        .bind(url.forward_path)
+        .bind(url.interstitial.map(|interstitial| interstitial.countdown as i32))
+        .execute(&mut *tx)
+        .await
+        .map_err(destination_taken)?;
+
+        let event = AuditEvent::new(ctx, AuditAction::Create, &url.domain, url.short_key.as_str(), None, Some(url.original_url.clone()));
+        record_event(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    async fn retarget(&self, domain: &str, short_key: &ShortKey, original_url: &str, ctx: &AuditContext) -> Result<Option<Url>, DomainError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the row so the recorded "before" is the value we actually replaced
+        let before = sqlx::query_as::<_, (String,)>(
+            "SELECT original_url FROM urls WHERE domain = $1 AND short_key = $2 AND deleted_at IS NULL FOR UPDATE",)
+        .bind(domain)
+        .bind(short_key.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((before,)) = before else {
+            return Ok(None);
+        };
+
+        let row = sqlx::query_as::<_, UrlRow>(&format!(
+            "UPDATE urls SET original_url = $3 WHERE domain = $1 AND short_key = $2 RETURNING {URL_COLUMNS}"))
+        .bind(domain)
+        .bind(short_key.as_str())
+        .bind(original_url)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(destination_taken)?;
+
+        let event = AuditEvent::new(ctx, AuditAction::Retarget, domain, short_key.as_str(), Some(before), Some(original_url.to_string()));
+        record_event(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        Ok(Some(row_into_url(row)))
+    }
+
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    async fn delete(&self, domain: &str, short_key: &ShortKey, deleted_at: DateTime<Utc>, ctx: &AuditContext) -> Result<bool, DomainError> {
+        let mut tx = self.pool.begin().await?;
+
+        let before = sqlx::query_as::<_, (String,)>(
+            "UPDATE urls SET deleted_at = $3 WHERE domain = $1 AND short_key = $2 AND deleted_at IS NULL RETURNING original_url",)
+        .bind(domain)
+        .bind(short_key.as_str())
+        .bind(deleted_at.naive_utc())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((before,)) = before else {
+            return Ok(false);
+        };
+
+        let event = AuditEvent::new(ctx, AuditAction::Delete, domain, short_key.as_str(), Some(before), None);
+        record_event(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    async fn restore(&self, domain: &str, short_key: &ShortKey, ctx: &AuditContext) -> Result<Option<Url>, DomainError> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query_as::<_, UrlRow>(&format!(
+            "UPDATE urls SET deleted_at = NULL WHERE domain = $1 AND short_key = $2 AND deleted_at IS NOT NULL RETURNING {URL_COLUMNS}"))
+        .bind(domain)
+        .bind(short_key.as_str())
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(destination_taken)?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let url = row_into_url(row);
+        let event = AuditEvent::new(ctx, AuditAction::Restore, domain, short_key.as_str(), None, Some(url.original_url.clone()));
+        record_event(&mut tx, &event).await?;
+
+        tx.commit().await?;
+        Ok(Some(url))
+    }
+
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    async fn take_click(&self, domain: &str, short_key: &ShortKey) -> Result<bool, DomainError> {
+        let result = sqlx::query(
+            "UPDATE urls SET clicks_remaining = clicks_remaining - 1 \
+             WHERE domain = $1 AND short_key = $2 AND deleted_at IS NULL AND clicks_remaining > 0",
+        )
+        .bind(domain)
+        .bind(short_key.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    async fn return_click(&self, domain: &str, short_key: &ShortKey) -> Result<(), DomainError> {
+        sqlx::query(
+            "UPDATE urls SET clicks_remaining = clicks_remaining + 1 \
+             WHERE domain = $1 AND short_key = $2 AND clicks_remaining < max_clicks",
+        )
+        .bind(domain)
+        .bind(short_key.as_str())
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn stream_live(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<Url, DomainError>> {
+        cursor_stream::<UrlRow>(
+            self.pool.clone(),
+            format!(
+                "SELECT {URL_COLUMNS} FROM urls \
+                 WHERE deleted_at IS NULL AND ($1::TEXT IS NULL OR workspace_id = $1) \
+                 ORDER BY created_at, domain, short_key"
+            ),
+            workspace_id.map(str::to_string),
+        )
+        .map(|row| row.map(row_into_url))
+        .boxed()
+    }
+}
@@ -0,0 +1,160 @@
+use crate::model::errors::DomainError;
+use crate::model::usage::{QuotaKind, Usage};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use tracing::instrument;
+
+
+type UsageRow = (String, NaiveDate, i64, i64);
+
+#[async_trait]
+pub trait UsageRepository: Send + Sync {
+    /// Returns the usage for the period, or an all-zero record if nothing was counted yet.
+    async fn find(&self, workspace_id: &str, period_start: NaiveDate) -> Result<Usage, DomainError>;
+
+    /// Atomically bumps the counter for `kind` unless it has already reached `limit`.
+    /// Returns `false` when the increment was refused.
+    async fn try_increment(
+        &self,
+        workspace_id: &str,
+        period_start: NaiveDate,
+        kind: QuotaKind,
+        limit: Option<i64>,
+    ) -> Result<bool, DomainError>;
+
+    /// Bumps the counter for `kind` by up to `amount` without passing `limit`.
+    /// Returns how many units were actually granted.
+    async fn increment_up_to(
+        &self,
+        workspace_id: &str,
+        period_start: NaiveDate,
+        kind: QuotaKind,
+        amount: i64,
+        limit: Option<i64>,
+    ) -> Result<i64, DomainError>;
+}
+
+pub struct PostgresUsageRepository {
+    pool: PgPool,
+}
+
+impl PostgresUsageRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn counter_column(kind: QuotaKind) -> &'static str {
+    match kind {
+        QuotaKind::Links => "links_created",
+        QuotaKind::Clicks => "clicks_tracked",
+    }
+}
+
+#[async_trait]
+impl UsageRepository for PostgresUsageRepository {
+    #[instrument(skip(self))]
+    async fn find(&self, workspace_id: &str, period_start: NaiveDate) -> Result<Usage, DomainError> {
+        let row = sqlx::query_as::<_, UsageRow>(
+            "SELECT workspace_id, period_start, links_created, clicks_tracked FROM usage \
+             WHERE workspace_id = $1 AND period_start = $2",)
+        .bind(workspace_id)
+        .bind(period_start)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match row {
+            Some(row) => Usage {
+                workspace_id: row.0,
+                period_start: row.1,
+                links_created: row.2,
+                clicks_tracked: row.3,
+            },
+            None => Usage::empty(workspace_id, period_start),
+        })
+    }
+
+    async fn try_increment(
+        &self,
+        workspace_id: &str,
+        period_start: NaiveDate,
+        kind: QuotaKind,
+        limit: Option<i64>,
+    ) -> Result<bool, DomainError> {
+        // A first-of-month insert would otherwise slip past a zero limit
+        if limit.is_some_and(|limit| limit <= 0) {
+            return Ok(false);
+        }
+
+        let sql = match kind {
+            QuotaKind::Links => {
+                "INSERT INTO usage (workspace_id, period_start, links_created) VALUES ($1, $2, 1) \
+                 ON CONFLICT (workspace_id, period_start) \
+                 DO UPDATE SET links_created = usage.links_created + 1 \
+                 WHERE $3::BIGINT IS NULL OR usage.links_created < $3 \
+                 RETURNING links_created"
+            }
+            QuotaKind::Clicks => {
+                "INSERT INTO usage (workspace_id, period_start, clicks_tracked) VALUES ($1, $2, 1) \
+                 ON CONFLICT (workspace_id, period_start) \
+                 DO UPDATE SET clicks_tracked = usage.clicks_tracked + 1 \
+                 WHERE $3::BIGINT IS NULL OR usage.clicks_tracked < $3 \
+                 RETURNING clicks_tracked"
+            }
+        };
+
+        let row = sqlx::query_as::<_, (i64,)>(sql)
+            .bind(workspace_id)
+            .bind(period_start)
+            .bind(limit)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn increment_up_to(
+        &self,
+        workspace_id: &str,
+        period_start: NaiveDate,
+        kind: QuotaKind,
+        amount: i64,
+        limit: Option<i64>,
+    ) -> Result<i64, DomainError> {
+        let column = counter_column(kind);
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO usage (workspace_id, period_start) VALUES ($1, $2) \
+             ON CONFLICT (workspace_id, period_start) DO NOTHING",)
+        .bind(workspace_id)
+        .bind(period_start)
+        .execute(&mut *tx)
+        .await?;
+
+        // Row lock serialises concurrent batches for the same workspace and month
+        let (current,) = sqlx::query_as::<_, (i64,)>(&format!(
+            "SELECT {column} FROM usage WHERE workspace_id = $1 AND period_start = $2 FOR UPDATE"))
+        .bind(workspace_id)
+        .bind(period_start)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let granted = match limit {
+            Some(limit) => amount.min(limit - current).max(0),
+            None => amount,
+        };
+
+        sqlx::query(&format!(
+            "UPDATE usage SET {column} = {column} + $3 WHERE workspace_id = $1 AND period_start = $2"))
+        .bind(workspace_id)
+        .bind(period_start)
+        .bind(granted)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(granted)
+    }
+}
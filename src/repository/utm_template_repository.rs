@@ -0,0 +1,106 @@
+use crate::model::errors::DomainError;
+use crate::model::utm::{Utm, UtmTemplate};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use tracing::instrument;
+
+
+#[derive(sqlx::FromRow)]
+struct UtmTemplateRow {
+    workspace_id: String,
+    name: String,
+    utm_source: Option<String>,
+    utm_medium: Option<String>,
+    utm_campaign: Option<String>,
+    utm_term: Option<String>,
+    utm_content: Option<String>,
+    updated_at: NaiveDateTime,
+}
+
+const TEMPLATE_COLUMNS: &str = "workspace_id, name, utm_source, utm_medium, utm_campaign, utm_term, utm_content, updated_at";
+
+/// Templates are named per workspace; two workspaces may both have a `newsletter` template.
+#[async_trait]
+pub trait UtmTemplateRepository: Send + Sync {
+    async fn find(&self, workspace_id: &str, name: &str) -> Result<Option<UtmTemplate>, DomainError>;
+    /// Creates the template, or replaces the one with the same workspace and name.
+    async fn save(&self, template: &UtmTemplate) -> Result<(), DomainError>;
+    /// The workspace's templates, by name.
+    async fn list(&self, workspace_id: &str) -> Result<Vec<UtmTemplate>, DomainError>;
+}
+
+pub struct PostgresUtmTemplateRepository {
+    pool: PgPool,
+}
+
+impl PostgresUtmTemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn row_into_template(row: UtmTemplateRow) -> UtmTemplate {
+    UtmTemplate {
+        workspace_id: row.workspace_id,
+        name: row.name,
+        utm: Utm {
+            source: row.utm_source,
+            medium: row.utm_medium,
+            campaign: row.utm_campaign,
+            term: row.utm_term,
+            content: row.utm_content,
+        },
+        updated_at: row.updated_at.and_utc(),
+    }
+}
+
+#[async_trait]
+impl UtmTemplateRepository for PostgresUtmTemplateRepository {
+    #[instrument(skip_all, fields(workspace_id, name))]
+    async fn find(&self, workspace_id: &str, name: &str) -> Result<Option<UtmTemplate>, DomainError> {
+        let row = sqlx::query_as::<_, UtmTemplateRow>(&format!(
+            "SELECT {TEMPLATE_COLUMNS} FROM utm_templates WHERE workspace_id = $1 AND name = $2"))
+        .bind(workspace_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(row_into_template))
+    }
+
+    #[instrument(skip_all, fields(workspace_id = template.workspace_id.as_str(), name = template.name.as_str()))]
+    async fn save(&self, template: &UtmTemplate) -> Result<(), DomainError> {
+        sqlx::query(
+            "INSERT INTO utm_templates (workspace_id, name, utm_source, utm_medium, utm_campaign, utm_term, utm_content, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (workspace_id, name) DO UPDATE SET \
+                 utm_source = EXCLUDED.utm_source, utm_medium = EXCLUDED.utm_medium, \
+                 utm_campaign = EXCLUDED.utm_campaign, utm_term = EXCLUDED.utm_term, \
+                 utm_content = EXCLUDED.utm_content, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&template.workspace_id)
+        .bind(&template.name)
+        .bind(&template.utm.source)
+        .bind(&template.utm.medium)
+        .bind(&template.utm.campaign)
+        .bind(&template.utm.term)
+        .bind(&template.utm.content)
+        .bind(template.updated_at.naive_utc())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(workspace_id))]
+    async fn list(&self, workspace_id: &str) -> Result<Vec<UtmTemplate>, DomainError> {
+        let rows = sqlx::query_as::<_, UtmTemplateRow>(&format!(
+            "SELECT {TEMPLATE_COLUMNS} FROM utm_templates WHERE workspace_id = $1 ORDER BY name"))
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_into_template).collect())
+    }
+}
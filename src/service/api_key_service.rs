@@ -0,0 +1,53 @@
+use crate::model::api_key::{hash_secret, visible_prefix, API_KEY_PREFIX};
+use crate::model::{ApiKey, AuditContext, DomainError, MintedApiKey};
+use crate::repository::api_key_repository::ApiKeyRepository;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+/// Random characters after `API_KEY_PREFIX`; 40 alphanumerics is ~238 bits.
+const SECRET_LEN: usize = 40;
+
+pub struct ApiKeyService {
+    repo: Box<dyn ApiKeyRepository>,
+}
+
+impl ApiKeyService {
+    pub fn new(repo: impl ApiKeyRepository + 'static) -> Self {
+        Self { repo: Box::new(repo) }
+    }
+
+    /// Creates a key for `workspace_id`. The returned secret is not stored and cannot be recovered.
+    pub async fn mint(&self, workspace_id: &str, name: &str) -> Result<MintedApiKey, DomainError> {
+        let secret = random_secret();
+        let key = self
+            .repo
+            .insert(workspace_id, name, &visible_prefix(&secret), &hash_secret(&secret))
+            .await?;
+        Ok(MintedApiKey { key, secret })
+    }
+
+    pub async fn list(&self, workspace_id: Option<&str>) -> Result<Vec<ApiKey>, DomainError> {
+        self.repo.list(workspace_id).await
+    }
+
+    /// The unrevoked key a presented secret belongs to, if any.
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<ApiKey>, DomainError> {
+        if !secret.starts_with(API_KEY_PREFIX) {
+            return Ok(None);
+        }
+        self.repo.find_active(&hash_secret(secret)).await
+    }
+
+    pub async fn revoke(&self, ctx: &AuditContext, id: i64) -> Result<ApiKey, DomainError> {
+        self.repo.revoke(id, ctx).await?.ok_or(DomainError::ApiKeyNotFound)
+    }
+}
+
+fn random_secret() -> String {
+    let random: String = thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(SECRET_LEN)
+        .map(char::from)
+        .collect();
+    format!("{API_KEY_PREFIX}{random}")
+}
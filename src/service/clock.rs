@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+
+/// Where the service gets the current time, so time-dependent behaviour can be tested.
+/// Any `Fn() -> DateTime<Utc>` closure is a clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> DateTime<Utc> + Send + Sync,
+{
+    fn now(&self) -> DateTime<Utc> {
+        self()
+    }
+}
@@ -0,0 +1,59 @@
+use crate::model::domain::normalize_host;
+use crate::model::{Domain, DomainError, DomainFallbacks, Interstitial};
+use crate::repository::domain_repository::DomainRepository;
+
+pub struct DomainService {
+    repo: Box<dyn DomainRepository>,
+}
+
+impl DomainService {
+    pub fn new(repo: impl DomainRepository + 'static) -> Self {
+        Self { repo: Box::new(repo) }
+    }
+
+    /// Registers a short domain. Without a `workspace_id` every workspace may create links on it.
+    pub async fn register(&self, host: &str, workspace_id: Option<&str>) -> Result<Domain, DomainError> {
+        let host = normalize_host(host).ok_or(DomainError::InvalidDomain)?;
+        let domain = Domain::new(host, workspace_id.map(str::to_string));
+        if self.repo.insert(&domain).await? {
+            Ok(domain)
+        } else {
+            Err(DomainError::DomainTaken(domain.host))
+        }
+    }
+
+    /// Registers `host` as a shared domain unless it already exists, e.g. the configured default.
+    pub async fn ensure(&self, host: &str) -> Result<(), DomainError> {
+        match self.register(host, None).await {
+            Ok(_) | Err(DomainError::DomainTaken(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets where the domain's root and unknown keys send visitors, replacing earlier settings.
+    pub async fn set_fallbacks(&self, host: &str, fallbacks: &DomainFallbacks) -> Result<Domain, DomainError> {
+        let host = normalize_host(host).ok_or(DomainError::InvalidDomain)?;
+        fallbacks.validate()?;
+        self.repo
+            .set_fallbacks(&host, fallbacks)
+            .await?
+            .ok_or(DomainError::UnknownDomain(host))
+    }
+
+    /// Shows a warning page before every link on the domain that doesn't set its own;
+    /// `None` goes back to redirecting straight away.
+    pub async fn set_interstitial(&self, host: &str, interstitial: Option<Interstitial>) -> Result<Domain, DomainError> {
+        let host = normalize_host(host).ok_or(DomainError::InvalidDomain)?;
+        if let Some(interstitial) = &interstitial {
+            interstitial.validate()?;
+        }
+        self.repo
+            .set_interstitial(&host, interstitial)
+            .await?
+            .ok_or(DomainError::UnknownDomain(host))
+    }
+
+    pub async fn list(&self) -> Result<Vec<Domain>, DomainError> {
+        self.repo.list().await
+    }
+}
@@ -1,3 +1,8 @@
 pub mod url_service;
 
 
+pub mod api_key_service;
+pub mod clock;
+pub mod domain_service;
+pub mod unlock;
+pub mod utm_template_service;
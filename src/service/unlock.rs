@@ -0,0 +1,105 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a correct password keeps a link unlocked for the visitor.
+pub const UNLOCK_TTL: ChronoDuration = ChronoDuration::hours(1);
+
+/// Password guesses allowed per link within `PASSWORD_ATTEMPT_WINDOW`.
+pub const MAX_PASSWORD_ATTEMPTS: u32 = 5;
+
+pub const PASSWORD_ATTEMPT_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// Issues and checks the tokens kept in a visitor's unlock cookie. A token is
+/// `{expiry}.{hmac}` over the link's domain, key and expiry, so it is only good for one link.
+pub struct UnlockTokens {
+    secret: Vec<u8>,
+}
+
+impl UnlockTokens {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self { secret: secret.into() }
+    }
+
+    /// A per-process secret: tokens stop working on restart and on other instances.
+    pub fn random() -> Self {
+        let mut secret = vec![0u8; 32];
+        thread_rng().fill_bytes(&mut secret);
+        Self::new(secret)
+    }
+
+    pub fn issue(&self, domain: &str, short_key: &str, now: DateTime<Utc>) -> String {
+        let expires = (now + UNLOCK_TTL).timestamp();
+        let signature = self.mac(domain, short_key, expires).finalize().into_bytes();
+        format!("{expires}.{}", hex::encode(signature))
+    }
+
+    pub fn verify(&self, token: &str, domain: &str, short_key: &str, now: DateTime<Utc>) -> bool {
+        let Some((expires, signature)) = token.split_once('.') else {
+            return false;
+        };
+        let (Ok(expires), Ok(signature)) = (expires.parse::<i64>(), hex::decode(signature)) else {
+            return false;
+        };
+        // verify_slice compares in constant time
+        expires > now.timestamp() && self.mac(domain, short_key, expires).verify_slice(&signature).is_ok()
+    }
+
+    fn mac(&self, domain: &str, short_key: &str, expires: i64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("{domain}\n{short_key}\n{expires}").as_bytes());
+        mac
+    }
+}
+
+/// Caps password guesses per link. Every guess counts, right or wrong, until a correct
+/// one clears the link, so parallel guessing gets no further than sequential guessing.
+/// Counts live in memory and are per instance.
+pub struct PasswordAttempts {
+    max_attempts: u32,
+    window: Duration,
+    attempts: Mutex<HashMap<(String, String), (u32, Instant)>>,
+}
+
+impl PasswordAttempts {
+    pub fn new(max_attempts: u32, window: Duration) -> Self {
+        Self {
+            max_attempts,
+            window,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a guess at the link. `Err` carries how long until guesses are accepted again.
+    pub fn try_attempt(&self, domain: &str, short_key: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap();
+        attempts.retain(|_, (_, started)| now.duration_since(*started) < self.window);
+
+        let (count, started) = attempts
+            .entry((domain.to_string(), short_key.to_string()))
+            .or_insert((0, now));
+        if *count >= self.max_attempts {
+            return Err(self.window.saturating_sub(now.duration_since(*started)));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    pub fn clear(&self, domain: &str, short_key: &str) {
+        self.attempts
+            .lock()
+            .unwrap()
+            .remove(&(domain.to_string(), short_key.to_string()));
+    }
+}
+
+impl Default for PasswordAttempts {
+    fn default() -> Self {
+        Self::new(MAX_PASSWORD_ATTEMPTS, PASSWORD_ATTEMPT_WINDOW)
+    }
+}
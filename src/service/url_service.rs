@@ -1,11 +1,56 @@
-use crate::model::{DomainError, ShortKey, Url};
+use crate::model::domain::{normalize_host, short_url, DEFAULT_DOMAIN};
+use crate::model::passthrough::validate_extra_path;
+use crate::model::password::{hash_password, validate_password, verify_password};
+use crate::model::url::{validate_original_url, DEFAULT_WORKSPACE};
+use crate::model::usage::current_period;
+use crate::model::import::{is_csv_header, parse_import_line, ImportIssue, MAX_IMPORT_CHUNK_SIZE};
+use crate::model::{
+    AuditContext, AuditFilter, AuditRecord, ClickEvent, DataFormat, Domain, DomainError, DomainFallbacks, ImportRecord,
+    ImportReport, LinkPreview, NewLink, QuotaKind, QuotaLimits, ShortKey, Url, Usage, Utm, VariantClicks, Visit,
+    Warning, WarningList,
+};
+use crate::repository::audit_repository::AuditRepository;
+use crate::repository::click_repository::ClickRepository;
+use crate::repository::domain_repository::DomainRepository;
 use crate::repository::url_repository::UrlRepository;
+use crate::repository::usage_repository::UsageRepository;
+use crate::repository::utm_template_repository::UtmTemplateRepository;
+use crate::service::clock::{Clock, SystemClock};
+use crate::service::unlock::{PasswordAttempts, UnlockTokens};
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt};
+use prometheus::IntCounter;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
+use std::collections::{HashMap, HashSet};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tracing::instrument;
 
+/// Most links accepted by a single `shorten_batch` call unless configured otherwise.
+pub const DEFAULT_BATCH_LIMIT: usize = 500;
+
+/// Ceiling for a configured batch limit; the whole batch goes into one insert statement.
+pub const MAX_BATCH_LIMIT: usize = 5000;
+
+struct Quota {
+    repo: Box<dyn UsageRepository>,
+    limits: QuotaLimits,
+}
 
 pub struct UrlService {
     repo: Box<dyn UrlRepository>,
+    quota: Option<Quota>,
+    audit: Option<Box<dyn AuditRepository>>,
+    clicks: Option<Box<dyn ClickRepository>>,
+    domains: Option<Box<dyn DomainRepository>>,
+    default_domain: String,
+    utm_templates: Option<Box<dyn UtmTemplateRepository>>,
+    warnings: WarningList,
+    unlock: UnlockTokens,
+    password_attempts: PasswordAttempts,
+    batch_limit: usize,
+    key_retries: Option<IntCounter>,
+    clock: Box<dyn Clock>,
 }
 
 impl UrlService {
@@ -13,49 +58,973 @@ impl UrlService {
     pub fn new(repo: impl UrlRepository + 'static) -> Self {
         Self {
             repo: Box::new(repo),
+            quota: None,
+            audit: None,
+            clicks: None,
+            domains: None,
+            default_domain: DEFAULT_DOMAIN.to_string(),
+            utm_templates: None,
+            warnings: WarningList::default(),
+            unlock: UnlockTokens::random(),
+            password_attempts: PasswordAttempts::default(),
+            batch_limit: DEFAULT_BATCH_LIMIT,
+            key_retries: None,
+            clock: Box::new(SystemClock),
         }
     }
 
+    /// Enables usage accounting and enforces `limits` per workspace per month.
+    pub fn with_quota(mut self, repo: impl UsageRepository + 'static, limits: QuotaLimits) -> Self {
+        self.quota = Some(Quota {
+            repo: Box::new(repo),
+            limits,
+        });
+        self
+    }
+
+    /// Enables reading back the audit trail that the repository writes on every mutation.
+    pub fn with_audit_log(mut self, repo: impl AuditRepository + 'static) -> Self {
+        self.audit = Some(Box::new(repo));
+        self
+    }
+
+    /// Records a click event for every successful resolve.
+    pub fn with_click_log(mut self, repo: impl ClickRepository + 'static) -> Self {
+        self.clicks = Some(Box::new(repo));
+        self
+    }
+
+    /// Serves links on every domain registered in `repo`. Links created without a domain, and
+    /// requests for hosts that are not registered, use `default_domain`.
+    pub fn with_domains(mut self, repo: impl DomainRepository + 'static, default_domain: impl Into<String>) -> Self {
+        self.domains = Some(Box::new(repo));
+        self.default_domain = default_domain.into();
+        self
+    }
+
+    /// Lets links be shortened with their workspace's UTM templates.
+    pub fn with_utm_templates(mut self, repo: impl UtmTemplateRepository + 'static) -> Self {
+        self.utm_templates = Some(Box::new(repo));
+        self
+    }
+
+    /// Shows a warning page, without a countdown, before any destination on `warnings`.
+    pub fn with_warning_list(mut self, warnings: WarningList) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Signs unlock cookies with a shared secret, so they survive restarts and work on every instance.
+    pub fn with_unlock_secret(mut self, secret: impl Into<Vec<u8>>) -> Self {
+        self.unlock = UnlockTokens::new(secret);
+        self
+    }
+
+    pub fn with_password_attempts(mut self, attempts: PasswordAttempts) -> Self {
+        self.password_attempts = attempts;
+        self
+    }
+
+    /// Capped at [`MAX_BATCH_LIMIT`].
+    pub fn with_batch_limit(mut self, limit: usize) -> Self {
+        self.batch_limit = limit.min(MAX_BATCH_LIMIT);
+        self
+    }
+
+    /// Counts generated keys that had to be thrown away because they were already taken.
+    pub fn with_key_retry_counter(mut self, counter: IntCounter) -> Self {
+        self.key_retries = Some(counter);
+        self
+    }
+
+    /// Replaces the wall clock that activation windows and unlock tokens are checked against, and
+    /// that deletions are stamped with.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
     
     pub async fn shorten(&self, original_url: &str) -> Result<Url, DomainError> {
-        // Check if this URL already has a short key 
-        if let Some(existing) = self.repo.find_by_original_url(original_url).await? {
+        self.shorten_for(&AuditContext::system(), DEFAULT_WORKSPACE, original_url).await
+    }
+
+    pub async fn shorten_for(&self, ctx: &AuditContext, workspace_id: &str, original_url: &str) -> Result<Url, DomainError> {
+        self.shorten_link(ctx, &NewLink::new(original_url).in_workspace(workspace_id)).await
+    }
+
+    /// Shortens one link on its requested domain, or the default domain.
+    #[instrument(skip_all, fields(workspace_id = link.workspace_id.as_str(), domain = link.domain.as_deref()))]
+    pub async fn shorten_link(&self, ctx: &AuditContext, link: &NewLink) -> Result<Url, DomainError> {
+        let link = &self.tag_utm(link, &mut HashMap::new()).await?;
+        validate_original_url(&link.original_url)?;
+        if let Some(password) = &link.password {
+            validate_password(password)?;
+        }
+        let click_limit = link.click_limit()?;
+        let active_window = link.active_window()?;
+        link.platform_routes.validate()?;
+        link.country_routes.validate()?;
+        link.variants.validate()?;
+        if let Some(interstitial) = &link.interstitial {
+            interstitial.validate()?;
+        }
+        let registered = self.registered_domains(link.domain.as_deref()).await?;
+        let domain = self.place_link(&link.workspace_id, link.domain.as_deref(), &registered)?;
+
+        // Check if this workspace already has a short key for this URL on this domain
+        if let Some(existing) = self.repo.find_by_original_url(&domain, &link.workspace_id, &link.original_url).await? {
+            self.check_same_settings(&existing, link).await?;
             return Ok(existing); 
         }
 
-        // It's a new URL — generate a unique key and save it
-        let short_key = self.generate_unique_key().await?;
-        let url = Url::new(short_key, original_url.to_string());
-        self.repo.insert(&url).await?;
+        // It's a new URL — count it against the workspace before generating a key
+        self.consume(&link.workspace_id, QuotaKind::Links).await?;
+
+        let password_hash = hash_off_thread(link.password.clone()).await?;
+        loop {
+            let short_key = self.generate_unique_key(&domain).await?;
+            let url = Url::new(short_key, link.original_url.clone())
+                .with_workspace(&link.workspace_id)
+                .with_domain(&domain)
+                .with_password_hash(password_hash.clone())
+                .with_click_limit(click_limit.clone())
+                .with_active_window(active_window.clone())
+                .with_platform_routes(link.platform_routes.clone())
+                .with_country_routes(link.country_routes.clone())
+                .with_variants(link.variants.clone())
+                .with_forwarding(link.forward_query, link.forward_path)
+                .with_interstitial(link.interstitial);
+            match self.repo.insert(&url, ctx).await {
+                Ok(()) => return Ok(url),
+                // Lost a race since the lookup: a concurrent request shortened the same destination,
+                // whose link we hand out like any existing one, or took the key, so draw another
+                Err(DomainError::DestinationTaken) => {
+                    let winner = self.repo.find_by_original_url(&domain, &link.workspace_id, &link.original_url).await?;
+                    if let Some(existing) = winner {
+                        self.check_same_settings(&existing, link).await?;
+                        return Ok(existing);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Shortens many links with one bulk lookup and one bulk insert.
+    /// The outer error rejects the whole batch; inner results are per link, in input order.
+    #[instrument(skip_all, fields(items = links.len()))]
+    pub async fn shorten_batch(
+        &self,
+        ctx: &AuditContext,
+        links: &[NewLink],
+    ) -> Result<Vec<Result<Url, DomainError>>, DomainError> {
+        if links.len() > self.batch_limit {
+            return Err(DomainError::BatchTooLarge(self.batch_limit));
+        }
+
+        // An unknown template only fails its own link; the rest of the batch goes ahead
+        let mut templates = HashMap::new();
+        let mut tagged: Vec<NewLink> = Vec::with_capacity(links.len());
+        let mut tagging: Vec<Option<DomainError>> = Vec::with_capacity(links.len());
+        for link in links {
+            match self.tag_utm(link, &mut templates).await {
+                Ok(link) => {
+                    tagged.push(link);
+                    tagging.push(None);
+                }
+                Err(e @ DomainError::DatabaseError(_)) => return Err(e),
+                Err(e) => {
+                    tagged.push(link.clone());
+                    tagging.push(Some(e));
+                }
+            }
+        }
+        let links = &tagged[..];
+
+        let registered = self
+            .registered_domains(links.iter().filter_map(|link| link.domain.as_deref()))
+            .await?;
+        // Where each link goes, or why it can't be created
+        let placements: Vec<Result<String, DomainError>> = links
+            .iter()
+            .zip(tagging)
+            .map(|(link, tagging)| {
+                if let Some(e) = tagging {
+                    return Err(e);
+                }
+                validate_original_url(&link.original_url)?;
+                if let Some(password) = &link.password {
+                    validate_password(password)?;
+                }
+                link.click_limit()?;
+                link.active_window()?;
+                link.platform_routes.validate()?;
+                link.country_routes.validate()?;
+                link.variants.validate()?;
+                if let Some(interstitial) = &link.interstitial {
+                    interstitial.validate()?;
+                }
+                self.place_link(&link.workspace_id, link.domain.as_deref(), &registered)
+            })
+            .collect();
+        let placed = || {
+            links
+                .iter()
+                .zip(&placements)
+                .filter_map(|(link, placement)| placement.as_ref().ok().map(|domain| (link, domain.as_str())))
+        };
+
+        let mut wanted: HashMap<(&str, &str), Vec<String>> = HashMap::new();
+        let mut seen: HashSet<(&str, &str, &str)> = HashSet::new();
+        for (link, domain) in placed() {
+            if seen.insert((domain, &link.workspace_id, &link.original_url)) {
+                wanted.entry((domain, &link.workspace_id)).or_default().push(link.original_url.clone());
+            }
+        }
+
+        // Same idempotency as single shorten — existing destinations keep their key in their
+        // workspace on that domain
+        let mut resolved: HashMap<Destination, Url> = HashMap::new();
+        for ((domain, workspace_id), original_urls) in &wanted {
+            for url in self.repo.find_by_original_urls(domain, workspace_id, original_urls).await? {
+                resolved.insert(destination(&url.domain, &url.workspace_id, &url.original_url), url);
+            }
+        }
+
+        // First occurrence of each new destination creates it; repeats share the result
+        let mut creators: Vec<(&NewLink, &str)> = Vec::new();
+        let mut claimed: HashSet<(&str, &str, &str)> = HashSet::new();
+        for (link, domain) in placed() {
+            let key = destination(domain, &link.workspace_id, &link.original_url);
+            if !resolved.contains_key(&key) && claimed.insert((domain, &link.workspace_id, &link.original_url)) {
+                creators.push((link, domain));
+            }
+        }
+
+        let mut refused: HashSet<Destination> = HashSet::new();
+        let mut per_workspace: HashMap<&str, Vec<(&NewLink, &str)>> = HashMap::new();
+        for creator in &creators {
+            per_workspace.entry(creator.0.workspace_id.as_str()).or_default().push(*creator);
+        }
+        let mut admitted: Vec<(&NewLink, &str)> = Vec::new();
+        for (link, _) in &creators {
+            if let Some(group) = per_workspace.remove(link.workspace_id.as_str()) {
+                let granted = self
+                    .consume_up_to(&link.workspace_id, QuotaKind::Links, group.len() as i64)
+                    .await? as usize;
+                let (allowed, denied) = group.split_at(granted.min(group.len()));
+                admitted.extend(allowed);
+                refused.extend(denied.iter().map(|(link, domain)| destination(domain, &link.workspace_id, &link.original_url)));
+            }
+        }
+
+        // Keys only need to be unique per domain
+        let mut per_domain: HashMap<&str, Vec<&NewLink>> = HashMap::new();
+        for (link, domain) in &admitted {
+            per_domain.entry(domain).or_default().push(link);
+        }
+        let mut urls: Vec<Url> = Vec::with_capacity(admitted.len());
+        for (domain, group) in per_domain {
+            let keys = self.generate_unique_keys(domain, group.len()).await?;
+            // Hashing is slow on purpose; run the group's hashes side by side on the blocking pool
+            let hashes = future::try_join_all(group.iter().map(|link| hash_off_thread(link.password.clone()))).await?;
+            for ((link, key), password_hash) in group.iter().zip(keys).zip(hashes) {
+                urls.push(
+                    Url::new(key, link.original_url.clone())
+                        .with_workspace(&link.workspace_id)
+                        .with_domain(domain)
+                        .with_password_hash(password_hash)
+                        .with_click_limit(link.click_limit()?)
+                        .with_active_window(link.active_window()?)
+                        .with_platform_routes(link.platform_routes.clone())
+                        .with_country_routes(link.country_routes.clone())
+                        .with_variants(link.variants.clone())
+                        .with_forwarding(link.forward_query, link.forward_path)
+                        .with_interstitial(link.interstitial),
+                );
+            }
+        }
+
+        let inserted = self.repo.insert_many(&urls, ctx).await?;
+        let mut lost_race: HashMap<(String, String), Vec<String>> = HashMap::new();
+        if inserted.len() < urls.len() {
+            let inserted_keys: HashSet<(&str, &str)> = inserted
+                .iter()
+                .map(|url| (url.domain.as_str(), url.short_key.as_str()))
+                .collect();
+            for url in urls.iter().filter(|url| !inserted_keys.contains(&(url.domain.as_str(), url.short_key.as_str()))) {
+                lost_race
+                    .entry((url.domain.clone(), url.workspace_id.clone()))
+                    .or_default()
+                    .push(url.original_url.clone());
+            }
+        }
+        // The links this batch just created were built from their own request, so only later
+        // repeats of the destination need comparing with them
+        let mut fresh: HashSet<Destination> = inserted
+            .iter()
+            .map(|url| destination(&url.domain, &url.workspace_id, &url.original_url))
+            .collect();
+        resolved.extend(
+            inserted
+                .into_iter()
+                .map(|url| (destination(&url.domain, &url.workspace_id, &url.original_url), url)),
+        );
+
+        // Someone else shortened these between our lookup and insert — use their link
+        for ((domain, workspace_id), original_urls) in &lost_race {
+            for url in self.repo.find_by_original_urls(domain, workspace_id, original_urls).await? {
+                resolved.insert(destination(&url.domain, &url.workspace_id, &url.original_url), url);
+            }
+        }
+
+        let mut results = Vec::with_capacity(links.len());
+        for (link, placement) in links.iter().zip(placements) {
+            let result = match placement {
+                Ok(domain) => {
+                    let key = destination(&domain, &link.workspace_id, &link.original_url);
+                    match resolved.get(&key) {
+                        _ if refused.contains(&key) => Err(DomainError::QuotaExceeded(QuotaKind::Links)),
+                        Some(url) if fresh.remove(&key) => Ok(url.clone()),
+                        // Repeats of a destination share one link, so each must ask for the same settings
+                        Some(url) => self.check_same_settings(url, link).await.map(|()| url.clone()),
+                        None => Err(DomainError::UrlNotFound),
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    /// Streams CSV or JSON Lines rows into the database, preserving their keys and
+    /// creation times. Each chunk commits in its own transaction, so a failure part-way
+    /// leaves earlier chunks in place. Imports are not metered against quotas.
+    #[instrument(skip_all, fields(%format, chunk_size))]
+    pub async fn import_links<R>(
+        &self,
+        ctx: &AuditContext,
+        reader: R,
+        format: DataFormat,
+        chunk_size: usize,
+    ) -> Result<ImportReport, DomainError>
+    where
+        R: AsyncBufRead + Unpin + Send,
+    {
+        let chunk_size = chunk_size.clamp(1, MAX_IMPORT_CHUNK_SIZE);
+        let mut report = ImportReport::default();
+        let mut chunk: Vec<(usize, ImportRecord)> = Vec::with_capacity(chunk_size);
+        let mut lines = reader.lines();
+        let mut line_no = 0;
+
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| DomainError::MalformedInput(format!("line {}: {e}", line_no + 1)))?
+        {
+            line_no += 1;
+            if line_no == 1 && format == DataFormat::Csv && is_csv_header(&line) {
+                continue;
+            }
+
+            match parse_import_line(format, &line) {
+                Ok(Some(record)) => chunk.push((line_no, record)),
+                Ok(None) => {}
+                Err(reason) => report.add_invalid(ImportIssue { line: line_no, short_key: None, reason }),
+            }
+
+            if chunk.len() >= chunk_size {
+                self.import_chunk(ctx, std::mem::take(&mut chunk), &mut report).await?;
+            }
+        }
+
+        self.import_chunk(ctx, chunk, &mut report).await?;
+        Ok(report)
+    }
+
+    async fn import_chunk(
+        &self,
+        ctx: &AuditContext,
+        chunk: Vec<(usize, ImportRecord)>,
+        report: &mut ImportReport,
+    ) -> Result<(), DomainError> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+
+        let registered = self
+            .registered_domains(chunk.iter().filter_map(|(_, record)| record.domain.as_deref()))
+            .await?;
+
+        // A key repeated inside one chunk would look "inserted" for every copy
+        let mut keys_in_chunk: HashSet<(String, String)> = HashSet::new();
+        let mut line_numbers: Vec<usize> = Vec::with_capacity(chunk.len());
+        let mut urls: Vec<Url> = Vec::with_capacity(chunk.len());
+        for (line, record) in chunk {
+            let short_key = record.short_key.as_str().to_string();
+            let domain = match self.place_link(&record.workspace_id, record.domain.as_deref(), &registered) {
+                Ok(domain) => domain,
+                Err(e) => {
+                    report.add_invalid(ImportIssue { line, short_key: Some(short_key), reason: e.to_string() });
+                    continue;
+                }
+            };
+            if keys_in_chunk.insert((domain.clone(), short_key.clone())) {
+                line_numbers.push(line);
+                urls.push(record.into_url(&domain));
+            } else {
+                report.add_conflict(ImportIssue {
+                    line,
+                    short_key: Some(short_key),
+                    reason: "short key repeated in input".to_string(),
+                });
+            }
+        }
+
+        let inserted: HashSet<(String, String)> = self.repo.import_many(&urls, ctx).await?.into_iter().collect();
+        report.imported += inserted.len();
+        report.chunks_committed += 1;
+
+        let skipped: Vec<(usize, &Url)> = line_numbers
+            .into_iter()
+            .zip(&urls)
+            .filter(|(_, url)| !inserted.contains(&(url.domain.clone(), url.short_key.as_str().to_string())))
+            .collect();
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        // Tell key collisions apart from destinations that already have a live link
+        let mut skipped_keys: HashMap<&str, Vec<String>> = HashMap::new();
+        for (_, url) in &skipped {
+            skipped_keys.entry(url.domain.as_str()).or_default().push(url.short_key.as_str().to_string());
+        }
+        let mut taken: HashSet<(String, String)> = HashSet::new();
+        for (domain, keys) in skipped_keys {
+            let existing = self.repo.existing_short_keys(domain, &keys).await?;
+            taken.extend(existing.into_iter().map(|key| (domain.to_string(), key)));
+        }
+
+        for (line, url) in skipped {
+            let reason = if taken.contains(&(url.domain.clone(), url.short_key.as_str().to_string())) {
+                "short key already exists".to_string()
+            } else {
+                format!("{} is already shortened under another key", url.original_url)
+            };
+            report.add_conflict(ImportIssue {
+                line,
+                short_key: Some(url.short_key.as_str().to_string()),
+                reason,
+            });
+        }
+        Ok(())
+    }
+
+    /// Given the requested host and a short key, return the original URL so we can redirect to it.
+    /// Protected links answer `PasswordRequired`; see `visit` for visitors with an unlock cookie.
+    pub async fn resolve(&self, host: Option<&str>, short_key: &ShortKey) -> Result<Url, DomainError> {
+        self.visit(&Visit::to_host(host), short_key).await
+    }
+
+    /// Follows a short link for a visitor. Hosts that are not registered domains resolve
+    /// against the default domain. Extra path after the key only resolves on links that
+    /// forward it. Scheduled links only redirect inside their activation window, and
+    /// protected links need a valid unlock token.
+    /// Every successful visit is a tracked click for the link's workspace. Links without an
+    /// interstitial of their own come back with their domain's.
+    #[instrument(skip_all, fields(host = visit.host.as_deref(), short_key = short_key.as_str()))]
+    pub async fn visit(&self, visit: &Visit, short_key: &ShortKey) -> Result<Url, DomainError> {
+        let (domain, settings) = self.serving(visit.host.as_deref()).await?;
+        let mut url = self.repo
+            .find_by_short_key(&domain, short_key)
+            .await?                  
+            .filter(|url| !url.is_deleted())
+            .ok_or(DomainError::UrlNotFound)?;
+        if url.interstitial.is_none() {
+            url.interstitial = settings.and_then(|settings| settings.interstitial);
+        }
+        if let Some(extra_path) = &visit.extra_path {
+            if !url.forward_path {
+                return Err(DomainError::UrlNotFound);
+            }
+            validate_extra_path(extra_path)?;
+        }
+
+        // Outside its window a link is closed to everyone, so there is no point asking for a password
+        if let Some(window) = &url.active_window {
+            window.check(self.clock.now())?;
+        }
+        let unlocked = || {
+            visit
+                .unlock_token
+                .as_deref()
+                .is_some_and(|token| self.unlock.verify(token, &domain, short_key.as_str(), self.clock.now()))
+        };
+        if url.is_protected() && !unlocked() {
+            return Err(DomainError::PasswordRequired);
+        }
+        let exhausted = || DomainError::LinkExhausted(url.click_limit.as_ref().and_then(|limit| limit.exhausted_url.clone()));
+        if url.is_exhausted() {
+            return Err(exhausted());
+        }
+
+        // The atomic take settles races for the last clicks; only a visit that got one costs quota,
+        // and a visit the quota refuses hands its click back
+        if url.click_limit.is_some() && !self.repo.take_click(&domain, short_key).await? {
+            return Err(exhausted());
+        }
+        if let Err(e) = self.consume(&url.workspace_id, QuotaKind::Clicks).await {
+            if url.click_limit.is_some() {
+                self.repo.return_click(&domain, short_key).await?;
+            }
+            return Err(e);
+        }
+        if let Some(clicks) = &self.clicks {
+            let click = ClickEvent::new(&url.domain, url.short_key.as_str(), &url.workspace_id)
+                .with_country(visit.country.clone())
+                .with_variant(url.variant(visit));
+            clicks.record(&click).await?;
+        }
         Ok(url)
     }
 
-    /// Given a short key, return the original URL so we can redirect to it.
-    pub async fn resolve(&self, short_key: &ShortKey) -> Result<Url, DomainError> {
+    /// Describes a link without following it: no click is recorded and no quota is spent.
+    /// Protected links keep their destination hidden.
+    #[instrument(skip_all, fields(host, short_key = short_key.as_str()))]
+    pub async fn preview(&self, host: Option<&str>, short_key: &ShortKey) -> Result<LinkPreview, DomainError> {
+        let domain = self.serving_domain(host).await?;
+        let url = self.repo
+            .find_by_short_key(&domain, short_key)
+            .await?
+            .filter(|url| !url.is_deleted())
+            .ok_or(DomainError::UrlNotFound)?;
+        let clicks = match &self.clicks {
+            Some(clicks) => clicks.count(&url.domain, &url.workspace_id, short_key.as_str()).await?,
+            None => 0,
+        };
+        Ok(LinkPreview::new(&url, clicks))
+    }
+
+    /// The public URL of a live link on the domain serving `host`, e.g. to draw its QR code.
+    /// Not a click.
+    #[instrument(skip_all, fields(host, short_key = short_key.as_str()))]
+    pub async fn short_url(&self, host: Option<&str>, short_key: &ShortKey) -> Result<String, DomainError> {
+        let domain = self.serving_domain(host).await?;
+        let url = self.repo
+            .find_by_short_key(&domain, short_key)
+            .await?
+            .filter(|url| !url.is_deleted())
+            .ok_or(DomainError::UrlNotFound)?;
+        Ok(short_url(&url.domain, url.short_key.as_str()))
+    }
+
+    /// The warning page to show instead of redirecting a visitor to `location`, if any.
+    /// Destinations on the warning list always get one, whatever the link says.
+    pub fn warning(&self, url: &Url, location: &str) -> Option<Warning> {
+        if self.warnings.matches(location) {
+            return Some(Warning::Flagged);
+        }
+        url.interstitial.map(Warning::Leaving)
+    }
+
+    /// Checks a password for a protected link and returns a token for the visitor's unlock cookie.
+    /// Guesses are capped per link; see `PasswordAttempts`.
+    #[instrument(skip_all, fields(host, short_key = short_key.as_str()))]
+    pub async fn unlock(&self, host: Option<&str>, short_key: &ShortKey, password: &str) -> Result<String, DomainError> {
+        let domain = self.serving_domain(host).await?;
+        let url = self.repo
+            .find_by_short_key(&domain, short_key)
+            .await?
+            .filter(|url| !url.is_deleted())
+            .ok_or(DomainError::UrlNotFound)?;
+
+        if let Some(hash) = url.password_hash {
+            self.password_attempts
+                .try_attempt(&domain, short_key.as_str())
+                .map_err(|wait| DomainError::TooManyAttempts(wait.as_secs().max(1)))?;
+            if !verify_off_thread(hash, password.to_string()).await {
+                return Err(DomainError::WrongPassword);
+            }
+            self.password_attempts.clear(&domain, short_key.as_str());
+        }
+        Ok(self.unlock.issue(&domain, short_key.as_str(), self.clock.now()))
+    }
+
+    /// What the domain serving `host` answers instead of a plain 404. Unregistered hosts use the
+    /// default domain's settings.
+    #[instrument(skip_all, fields(host))]
+    pub async fn fallbacks(&self, host: Option<&str>) -> Result<DomainFallbacks, DomainError> {
+        let Some(domains) = &self.domains else {
+            return Ok(DomainFallbacks::default());
+        };
+        if let Some(host) = host.and_then(normalize_host)
+            && let Some(domain) = domains.find(&host).await?
+        {
+            return Ok(domain.fallbacks);
+        }
+        Ok(domains
+            .find(&self.default_domain)
+            .await?
+            .map(|domain| domain.fallbacks)
+            .unwrap_or_default())
+    }
+
+    /// Fetches a link for inspection, deleted or not. Unlike `resolve`, this is not a click.
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    pub async fn lookup(&self, domain: Option<&str>, short_key: &ShortKey) -> Result<Url, DomainError> {
         self.repo
-            .find_by_short_key(short_key)
-            .await?                  
+            .find_by_short_key(&self.named_domain(domain)?, short_key)
+            .await?
             .ok_or(DomainError::UrlNotFound)
     }
 
-    /// Generates a 6-char alphanumeric key that doesn't already exist in the DB.
+    /// Clicks per variant of a split link, in variant order. Counts are zero when click
+    /// logging is not configured.
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    pub async fn variant_clicks(&self, domain: Option<&str>, short_key: &ShortKey) -> Result<Vec<VariantClicks>, DomainError> {
+        let url = self.lookup(domain, short_key).await?;
+        let counts: HashMap<i32, i64> = match &self.clicks {
+            Some(clicks) => clicks
+                .count_by_variant(&url.domain, &url.workspace_id, short_key.as_str())
+                .await?
+                .into_iter()
+                .collect(),
+            None => HashMap::new(),
+        };
+        Ok(url
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(index, variant)| VariantClicks {
+                variant: index,
+                url: variant.url.clone(),
+                weight: variant.weight,
+                clicks: i32::try_from(index).ok().and_then(|index| counts.get(&index)).copied().unwrap_or(0),
+            })
+            .collect())
+    }
+
+    /// Streams every live link (optionally for one workspace) without buffering them.
+    pub fn export_links(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<Url, DomainError>> {
+        self.repo.stream_live(workspace_id)
+    }
+
+    /// Streams recorded click events; empty when click logging is not configured.
+    pub fn export_clicks(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<ClickEvent, DomainError>> {
+        match &self.clicks {
+            Some(clicks) => clicks.stream(workspace_id),
+            None => stream::empty().boxed(),
+        }
+    }
+
+    /// Points an existing link at a new destination.
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    pub async fn retarget(
+        &self,
+        ctx: &AuditContext,
+        domain: Option<&str>,
+        short_key: &ShortKey,
+        original_url: &str,
+    ) -> Result<Url, DomainError> {
+        validate_original_url(original_url)?;
+        self.repo
+            .retarget(&self.named_domain(domain)?, short_key, original_url, ctx)
+            .await?
+            .ok_or(DomainError::UrlNotFound)
+    }
+
+    /// Soft-deletes a link; it stops resolving but can be restored.
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    pub async fn delete(&self, ctx: &AuditContext, domain: Option<&str>, short_key: &ShortKey) -> Result<(), DomainError> {
+        if self.repo.delete(&self.named_domain(domain)?, short_key, self.clock.now(), ctx).await? {
+            Ok(())
+        } else {
+            Err(DomainError::UrlNotFound)
+        }
+    }
+
+    #[instrument(skip_all, fields(domain, short_key = short_key.as_str()))]
+    pub async fn restore(&self, ctx: &AuditContext, domain: Option<&str>, short_key: &ShortKey) -> Result<Url, DomainError> {
+        self.repo
+            .restore(&self.named_domain(domain)?, short_key, ctx)
+            .await?
+            .ok_or(DomainError::UrlNotFound)
+    }
+
+    /// Audit events matching `filter`, newest first. Empty when no audit log is configured.
+    #[instrument(skip_all)]
+    pub async fn audit_log(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>, DomainError> {
+        match &self.audit {
+            Some(audit) => audit.list(filter).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Current month's consumption for a workspace, alongside the configured limits.
+    #[instrument(skip_all, fields(workspace_id))]
+    pub async fn usage(&self, workspace_id: &str) -> Result<(Usage, QuotaLimits), DomainError> {
+        let period = current_period();
+        match &self.quota {
+            Some(quota) => Ok((quota.repo.find(workspace_id, period).await?, quota.limits)),
+            None => Ok((Usage::empty(workspace_id, period), QuotaLimits::default())),
+        }
+    }
+
+    /// The link with its UTM parameters added to `original_url`: the request's own, then the
+    /// named template's for any left unset. Tagging comes before the idempotency lookup, so the
+    /// same base URL with the same UTM set finds the same link, and a different set makes a new
+    /// one. Templates already looked up are reused from `templates`.
+    async fn tag_utm(
+        &self,
+        link: &NewLink,
+        templates: &mut HashMap<(String, String), Option<Utm>>,
+    ) -> Result<NewLink, DomainError> {
+        let mut utm = link.utm.clone();
+        if let Some(name) = &link.utm_template {
+            let key = (link.workspace_id.clone(), name.clone());
+            if !templates.contains_key(&key) {
+                let template = match &self.utm_templates {
+                    Some(repo) => repo.find(&link.workspace_id, name).await?.map(|template| template.utm),
+                    None => None,
+                };
+                templates.insert(key.clone(), template);
+            }
+            let template = templates[&key].as_ref().ok_or_else(|| DomainError::UnknownUtmTemplate(name.clone()))?;
+            utm = utm.or(template);
+        }
+        if utm.is_empty() {
+            return Ok(link.clone());
+        }
+        utm.validate()?;
+        let mut tagged = link.clone();
+        tagged.original_url = utm.apply(&link.original_url)?;
+        Ok(tagged)
+    }
+
+    /// The domain an admin operation addresses: the named one, or the default.
+    fn named_domain(&self, domain: Option<&str>) -> Result<String, DomainError> {
+        match domain {
+            Some(domain) => normalize_host(domain).ok_or(DomainError::InvalidDomain),
+            None => Ok(self.default_domain.clone()),
+        }
+    }
+
+    /// The domain serving a redirect for `host`. Missing or unregistered hosts get the default domain.
+    async fn serving_domain(&self, host: Option<&str>) -> Result<String, DomainError> {
+        let (Some(host), Some(domains)) = (host.and_then(normalize_host), &self.domains) else {
+            return Ok(self.default_domain.clone());
+        };
+        if host != self.default_domain && domains.find(&host).await?.is_some() {
+            Ok(host)
+        } else {
+            Ok(self.default_domain.clone())
+        }
+    }
+
+    /// Like `serving_domain`, along with the serving domain's settings when domains are configured.
+    async fn serving(&self, host: Option<&str>) -> Result<(String, Option<Domain>), DomainError> {
+        let Some(domains) = &self.domains else {
+            return Ok((self.default_domain.clone(), None));
+        };
+        if let Some(host) = host.and_then(normalize_host)
+            && let Some(domain) = domains.find(&host).await?
+        {
+            return Ok((host, Some(domain)));
+        }
+        Ok((self.default_domain.clone(), domains.find(&self.default_domain).await?))
+    }
+
+    /// Looks up the registered domains among `requested`, once each.
+    async fn registered_domains<'a>(
+        &self,
+        requested: impl IntoIterator<Item = &'a str>,
+    ) -> Result<HashMap<String, Domain>, DomainError> {
+        let mut registered = HashMap::new();
+        let Some(domains) = &self.domains else {
+            return Ok(registered);
+        };
+        let hosts: HashSet<String> = requested.into_iter().filter_map(normalize_host).collect();
+        for host in hosts {
+            if let Some(domain) = domains.find(&host).await? {
+                registered.insert(host, domain);
+            }
+        }
+        Ok(registered)
+    }
+
+    /// The domain a new link of `workspace_id` goes on: the requested one if the workspace
+    /// may use it, otherwise the default.
+    fn place_link(
+        &self,
+        workspace_id: &str,
+        requested: Option<&str>,
+        registered: &HashMap<String, Domain>,
+    ) -> Result<String, DomainError> {
+        let Some(requested) = requested else {
+            return Ok(self.default_domain.clone());
+        };
+        let host = normalize_host(requested).ok_or(DomainError::InvalidDomain)?;
+        if host == self.default_domain {
+            return Ok(host);
+        }
+        match registered.get(&host) {
+            Some(domain) if domain.serves(workspace_id) => Ok(host),
+            _ => Err(DomainError::UnknownDomain(host)),
+        }
+    }
+
+    async fn consume(&self, workspace_id: &str, kind: QuotaKind) -> Result<(), DomainError> {
+        let Some(quota) = &self.quota else {
+            return Ok(());
+        };
+
+        let allowed = quota
+            .repo
+            .try_increment(workspace_id, current_period(), kind, quota.limits.limit_for(kind))
+            .await?;
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(DomainError::QuotaExceeded(kind))
+        }
+    }
+
+    /// Bulk flavour of `consume`: grants up to `amount` units and returns how many were granted.
+    async fn consume_up_to(&self, workspace_id: &str, kind: QuotaKind, amount: i64) -> Result<i64, DomainError> {
+        let Some(quota) = &self.quota else {
+            return Ok(amount);
+        };
+
+        quota
+            .repo
+            .increment_up_to(workspace_id, current_period(), kind, amount, quota.limits.limit_for(kind))
+            .await
+    }
+
+    /// Generates `count` distinct keys that don't exist on `domain`, checking each round in bulk.
+    async fn generate_unique_keys(&self, domain: &str, count: usize) -> Result<Vec<ShortKey>, DomainError> {
+        let mut keys: Vec<ShortKey> = Vec::with_capacity(count);
+        let mut taken: HashSet<String> = HashSet::new();
+
+        while keys.len() < count {
+            let mut candidates: Vec<String> = Vec::new();
+            while candidates.len() < count - keys.len() {
+                let key = random_key();
+                if taken.insert(key.clone()) {
+                    candidates.push(key);
+                }
+            }
+
+            let existing: HashSet<String> = self.repo.existing_short_keys(domain, &candidates).await?.into_iter().collect();
+            self.count_key_retries(existing.len());
+            for key in candidates.into_iter().filter(|key| !existing.contains(key)) {
+                keys.push(ShortKey::new(key)?);
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// Generates a 6-char alphanumeric key that doesn't already exist on `domain`.
     /// Loops until it finds one (collisions are extremely rare with 6 chars).
-    async fn generate_unique_key(&self) -> Result<ShortKey, DomainError> {
+    async fn generate_unique_key(&self, domain: &str) -> Result<ShortKey, DomainError> {
         loop {
             
-            let key: String = thread_rng()
-                .sample_iter(Alphanumeric)
-                .take(6)
-                .map(char::from)
-                .collect();
-
-            let short_key = ShortKey::new(key)?;
+            let short_key = ShortKey::new(random_key())?;
 
             // Only return it if it doesn't already exist — avoids key collisions
-            if self.repo.find_by_short_key(&short_key).await?.is_none() {
+            if self.repo.find_by_short_key(domain, &short_key).await?.is_none() {
                 return Ok(short_key);
             }
             // If it exists, loop around and try a new random key
+            self.count_key_retries(1);
+        }
+    }
+
+    fn count_key_retries(&self, retries: usize) {
+        if let Some(counter) = &self.key_retries {
+            counter.inc_by(retries as u64);
+        }
+    }
+
+    /// An existing link is only handed out to a request asking for the same click limit, schedule,
+    /// routes, variants, passthrough options and password, so shortening again can't lift a limit, move a schedule,
+    /// reroute visitors, or strip or discover a password.
+    async fn check_same_settings(&self, url: &Url, link: &NewLink) -> Result<(), DomainError> {
+        let same_limit = match (&url.click_limit, link.click_limit()?) {
+            (None, None) => true,
+            (Some(existing), Some(requested)) => existing.same_settings(&requested),
+            _ => false,
+        };
+        if !same_limit {
+            return Err(DomainError::ClickLimitConflict);
+        }
+        if url.active_window != link.active_window()? {
+            return Err(DomainError::ScheduleConflict);
         }
+        if url.platform_routes != link.platform_routes
+            || url.country_routes != link.country_routes
+            || url.variants != link.variants
+            || url.forward_query != link.forward_query
+            || url.forward_path != link.forward_path
+            || url.interstitial != link.interstitial
+        {
+            return Err(DomainError::RoutingConflict);
+        }
+
+        let same = match (&url.password_hash, link.password.as_deref()) {
+            (None, None) => true,
+            // Comparing passwords is a guess at the link's password, so it counts against the
+            // same per-link limit as the unlock form
+            (Some(hash), Some(password)) => {
+                let key = url.short_key.as_str();
+                self.password_attempts
+                    .try_attempt(&url.domain, key)
+                    .map_err(|wait| DomainError::TooManyAttempts(wait.as_secs().max(1)))?;
+                let same = verify_off_thread(hash.clone(), password.to_string()).await;
+                if same {
+                    self.password_attempts.clear(&url.domain, key);
+                }
+                same
+            }
+            _ => false,
+        };
+        if same { Ok(()) } else { Err(DomainError::ProtectionConflict) }
     }
-}
\ No newline at end of file
+}
+
+/// A live destination is unique per domain and workspace: `(domain, workspace_id, original_url)`.
+type Destination = (String, String, String);
+
+fn destination(domain: &str, workspace_id: &str, original_url: &str) -> Destination {
+    (domain.to_string(), workspace_id.to_string(), original_url.to_string())
+}
+
+// Argon2 is deliberately slow, so it runs on the blocking pool instead of an async worker
+async fn hash_off_thread(password: Option<String>) -> Result<Option<String>, DomainError> {
+    let Some(password) = password else {
+        return Ok(None);
+    };
+    tokio::task::spawn_blocking(move || hash_password(&password))
+        .await
+        .expect("password hashing panicked")
+        .map(Some)
+}
+
+async fn verify_off_thread(hash: String, password: String) -> bool {
+    tokio::task::spawn_blocking(move || verify_password(&hash, &password))
+        .await
+        .expect("password verification panicked")
+}
+
+fn random_key() -> String {
+    thread_rng()
+        .sample_iter(Alphanumeric)
+        .take(6)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,27 @@
+use crate::model::url::DEFAULT_WORKSPACE;
+use crate::model::utm::{Utm, UtmTemplate};
+use crate::model::DomainError;
+use crate::repository::utm_template_repository::UtmTemplateRepository;
+
+pub struct UtmTemplateService {
+    repo: Box<dyn UtmTemplateRepository>,
+}
+
+impl UtmTemplateService {
+    pub fn new(repo: impl UtmTemplateRepository + 'static) -> Self {
+        Self { repo: Box::new(repo) }
+    }
+
+    /// Creates or replaces a workspace's template. Links already shortened with it keep
+    /// the parameters they were tagged with.
+    pub async fn save(&self, workspace_id: Option<&str>, name: &str, utm: Utm) -> Result<UtmTemplate, DomainError> {
+        let template = UtmTemplate::new(workspace_id, name, utm);
+        template.validate()?;
+        self.repo.save(&template).await?;
+        Ok(template)
+    }
+
+    pub async fn list(&self, workspace_id: Option<&str>) -> Result<Vec<UtmTemplate>, DomainError> {
+        self.repo.list(workspace_id.unwrap_or(DEFAULT_WORKSPACE)).await
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Owns the background workers (e.g. the click writer) and stops them in one place.
+///
+/// Workers watch `token()` and are expected to flush their state and return once it
+/// is cancelled. `shutdown` waits for them up to a deadline.
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    token: CancellationToken,
+    workers: Vec<(&'static str, JoinHandle<()>)>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancelled when shutdown begins; hand a clone to every background worker.
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn register(&mut self, name: &'static str, worker: JoinHandle<()>) {
+        self.workers.push((name, worker));
+    }
+
+    /// Signals every worker to stop and waits for them to finish flushing.
+    /// Workers still running at `timeout` are aborted. Returns `false` if any had to be.
+    pub async fn shutdown(self, timeout: Duration) -> bool {
+        self.token.cancel();
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut clean = true;
+
+        for (name, mut worker) in self.workers {
+            match tokio::time::timeout_at(deadline, &mut worker).await {
+                Ok(Ok(())) => tracing::info!(worker = name, "worker stopped"),
+                Ok(Err(e)) => {
+                    tracing::error!(worker = name, error = %e, "worker failed");
+                    clean = false;
+                }
+                Err(_) => {
+                    tracing::warn!(worker = name, "worker did not stop in time, aborting");
+                    worker.abort();
+                    clean = false;
+                }
+            }
+        }
+        clean
+    }
+}
+
+/// Resolves on SIGTERM or Ctrl-C.
+pub async fn termination_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl-C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
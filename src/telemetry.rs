@@ -0,0 +1,160 @@
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// How log lines are rendered, chosen with `LOG_FORMAT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per event, with the enclosing span fields, for log shippers.
+    Json,
+    /// Human-readable multi-line output for local development.
+    Pretty,
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(LogFormat::Json),
+            "pretty" | "text" => Some(LogFormat::Pretty),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps exporters alive; dropping it flushes and shuts them down.
+#[must_use = "dropping the guard immediately stops trace and metric export"]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otlp")]
+    otlp: Option<otlp::Providers>,
+}
+
+/// Installs the global subscriber. `RUST_LOG` overrides the default `info` filter.
+///
+/// With the `otlp` feature and an `otlp_endpoint` (e.g. `http://collector:4318`), spans and
+/// request/query latencies are also exported over OTLP/HTTP. Call this before building
+/// `Metrics` so its OTLP instruments bind to the exporting meter provider.
+pub fn init_tracing(format: LogFormat, otlp_endpoint: Option<&str>) -> TelemetryGuard {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let fmt_layer = match format {
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer().pretty().boxed(),
+    };
+
+    #[cfg(feature = "otlp")]
+    {
+        let (otel_layer, providers) = match otlp_endpoint.map(otlp::install) {
+            Some(Ok((layer, providers))) => (Some(layer), Some(providers)),
+            Some(Err(e)) => {
+                eprintln!("OTLP export disabled: {e}");
+                (None, None)
+            }
+            None => (None, None),
+        };
+        tracing_subscriber::registry().with(filter).with(fmt_layer).with(otel_layer).init();
+        TelemetryGuard { otlp: providers }
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        tracing_subscriber::registry().with(filter).with(fmt_layer).init();
+        if otlp_endpoint.is_some() {
+            tracing::warn!("OTLP endpoint configured, but this binary was built without the `otlp` feature");
+        }
+        TelemetryGuard {}
+    }
+}
+
+impl TelemetryGuard {
+    /// Flushes pending spans and metrics. Blocks until the exporters finish or time out.
+    pub fn shutdown(self) {
+        drop(self);
+    }
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(providers) = self.otlp.take() {
+            providers.shutdown();
+        }
+    }
+}
+
+/// Makes `span` a child of the caller's trace, so exported spans join it.
+#[cfg(feature = "otlp")]
+pub fn set_remote_parent(span: &tracing::Span, traceparent: &str) {
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use std::collections::HashMap;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let carrier = HashMap::from([("traceparent".to_string(), traceparent.to_string())]);
+    let _ = span.set_parent(TraceContextPropagator::new().extract(&carrier));
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+    use opentelemetry_sdk::Resource;
+    use tracing::Subscriber;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    const DEFAULT_SERVICE_NAME: &str = "redirect-service";
+
+    pub struct Providers {
+        tracer: SdkTracerProvider,
+        meter: SdkMeterProvider,
+    }
+
+    impl Providers {
+        pub fn shutdown(self) {
+            if let Err(e) = self.tracer.shutdown() {
+                eprintln!("Failed to flush spans: {e}");
+            }
+            if let Err(e) = self.meter.shutdown() {
+                eprintln!("Failed to flush metrics: {e}");
+            }
+        }
+    }
+
+    /// Builds both exporters and registers the meter provider globally.
+    pub fn install<S>(endpoint: &str) -> Result<(OpenTelemetryLayer<S, Tracer>, Providers), Box<dyn std::error::Error>>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let endpoint = endpoint.trim_end_matches('/');
+        let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+        let resource = Resource::builder().with_service_name(service_name).build();
+
+        let spans = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{endpoint}/v1/traces"))
+            .build()?;
+        let tracer = SdkTracerProvider::builder()
+            .with_batch_exporter(spans)
+            .with_resource(resource.clone())
+            .build();
+
+        let metrics = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{endpoint}/v1/metrics"))
+            .build()?;
+        let meter = SdkMeterProvider::builder()
+            .with_periodic_exporter(metrics)
+            .with_resource(resource)
+            .build();
+        opentelemetry::global::set_meter_provider(meter.clone());
+
+        let layer = tracing_opentelemetry::layer().with_tracer(tracer.tracer(DEFAULT_SERVICE_NAME));
+        Ok((layer, Providers { tracer, meter }))
+    }
+}
@@ -1,10 +1,37 @@
-use axum::{body::Body, http::{Request, StatusCode}, Router, ServiceExt};
-use redirect_service::api::handler::{redirect_to_url, shorten_url, AppState};
+use axum::{body::Body, http::{Request, StatusCode}, Router};
+use redirect_service::api::handler::{
+    delete_url, export, get_audit, get_usage, import_links, qr_code, redirect_root, redirect_to_url, redirect_with_path,
+    restore_url, retarget_url, shorten_batch, shorten_url, unlock_url, variant_stats,
+};
+use redirect_service::api::admin::{identify_caller, require_admin, require_credentials, AdminToken, Credentials};
+use redirect_service::api::domains::{
+    clear_domain_interstitial, list_domains, register_domain, set_domain_fallbacks, set_domain_interstitial,
+};
+use redirect_service::api::utm_templates::{list_utm_templates, save_utm_template};
+use axum::response::IntoResponse;
+use redirect_service::api::health::{healthz, readyz, version, Health};
+use redirect_service::api::metrics::{render_metrics, track_metrics};
+use redirect_service::api::request_context::{request_id, TraceParent};
+use redirect_service::geo::GeoLocator;
+use redirect_service::metrics::Metrics;
+use redirect_service::model::domain::DEFAULT_DOMAIN;
+use redirect_service::api::pages::preview_page;
+use redirect_service::model::{DomainError, LinkPreview, WarningList};
+use redirect_service::repository::api_key_repository::PostgresApiKeyRepository;
+use redirect_service::repository::audit_repository::PostgresAuditRepository;
+use redirect_service::repository::click_repository::PostgresClickRepository;
+use redirect_service::repository::domain_repository::PostgresDomainRepository;
+use redirect_service::repository::utm_template_repository::PostgresUtmTemplateRepository;
+use redirect_service::repository::metered_url_repository::MeteredUrlRepository;
 use redirect_service::repository::url_repository::PostgresUrlRepository;
+use redirect_service::service::api_key_service::ApiKeyService;
+use redirect_service::service::domain_service::DomainService;
+use redirect_service::service::utm_template_service::UtmTemplateService;
 use redirect_service::service::url_service::UrlService;
 use serde_json::{json, Value};
 use sqlx::PgPool;
 use std::sync::Arc;
+use tower::ServiceExt;
 
 
 // builds a real router wired to a real database
@@ -23,12 +50,108 @@ async fn setup_app() -> Router {
         .await
         .expect("Failed to clean urls table");
 
-    let repo = PostgresUrlRepository::new(pool);
-    let service = Arc::new(UrlService::new(repo));
+    sqlx::query("TRUNCATE audit_events")
+        .execute(&pool)
+        .await
+        .expect("Failed to clean audit_events table");
+
+    sqlx::query("DELETE FROM click_events")
+        .execute(&pool)
+        .await
+        .expect("Failed to clean click_events table");
+
+    sqlx::query("DELETE FROM utm_templates")
+        .execute(&pool)
+        .await
+        .expect("Failed to clean utm_templates table");
+
+    sqlx::query("DELETE FROM domains WHERE host <> $1")
+        .bind(DEFAULT_DOMAIN)
+        .execute(&pool)
+        .await
+        .expect("Failed to clean domains table");
+
+    let metrics = Arc::new(Metrics::new().with_pool(pool.clone()));
+    let repo = MeteredUrlRepository::new(PostgresUrlRepository::new(pool.clone()), metrics.clone());
+    let audit_repo = PostgresAuditRepository::new(pool.clone());
+    let click_repo = PostgresClickRepository::new(pool.clone());
+    let domains = Arc::new(DomainService::new(PostgresDomainRepository::new(pool.clone())));
+    let service = Arc::new(
+        UrlService::new(repo)
+            .with_audit_log(audit_repo)
+            .with_click_log(click_repo)
+            .with_domains(PostgresDomainRepository::new(pool.clone()), DEFAULT_DOMAIN)
+            .with_utm_templates(PostgresUtmTemplateRepository::new(pool.clone()))
+            .with_warning_list(WarningList::parse("flagged.example"))
+            .with_key_retry_counter(metrics.key_generation_retries.clone()),
+    );
+    let admin_token = AdminToken::new(Some("s3cret".to_string()));
+    let credentials = Credentials::new(admin_token.clone(), ApiKeyService::new(PostgresApiKeyRepository::new(pool.clone())));
+
+    let admin = Router::new()
+        .route("/import", axum::routing::post(import_links))
+        .merge(
+            Router::new()
+                .route("/domains", axum::routing::get(list_domains).post(register_domain))
+                .route("/domains/{host}/fallbacks", axum::routing::put(set_domain_fallbacks))
+                .route(
+                    "/domains/{host}/interstitial",
+                    axum::routing::put(set_domain_interstitial).delete(clear_domain_interstitial),
+                )
+                .with_state(domains),
+        )
+        .merge(
+            Router::new()
+                .route("/utm-templates", axum::routing::get(list_utm_templates))
+                .route("/utm-templates/{name}", axum::routing::put(save_utm_template))
+                .with_state(Arc::new(UtmTemplateService::new(PostgresUtmTemplateRepository::new(pool)))),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(admin_token.clone(), require_admin));
 
     Router::new()
-        .route("/shorten", axum::routing::post(shorten_url))
-        .route("/{short_key}", axum::routing::get(redirect_to_url))
+        .route(
+            "/shorten",
+            axum::routing::post(shorten_url)
+                .route_layer(axum::middleware::from_fn_with_state(credentials.clone(), identify_caller)),
+        )
+        .merge(
+            Router::new()
+                .route("/shorten/batch", axum::routing::post(shorten_batch))
+                .route("/usage", axum::routing::get(get_usage))
+                .route("/audit", axum::routing::get(get_audit))
+                .route("/links/{short_key}", axum::routing::patch(retarget_url).delete(delete_url))
+                .route("/links/{short_key}/restore", axum::routing::post(restore_url))
+                .route("/links/{short_key}/variants", axum::routing::get(variant_stats))
+                .route_layer(axum::middleware::from_fn_with_state(credentials.clone(), require_credentials))
+                .route(
+                    "/export",
+                    axum::routing::get(export)
+                        .route_layer(axum::middleware::from_fn_with_state(admin_token, require_admin)),
+                ),
+        )
+        .nest("/admin", admin)
+        .route("/", axum::routing::get(redirect_root))
+        .route(
+            "/{short_key}",
+            axum::routing::get(redirect_to_url)
+                .post(unlock_url)
+                .route_layer(axum::middleware::from_fn_with_state(credentials, identify_caller)),
+        )
+        .route("/{short_key}/qr", axum::routing::get(qr_code))
+        .route("/{short_key}/{*extra_path}", axum::routing::get(redirect_with_path))
+        .merge(
+            Router::new()
+                .route("/metrics", axum::routing::get(render_metrics))
+                .with_state(metrics.clone()),
+        )
+        .layer(axum::Extension(Arc::new(
+            GeoLocator::default()
+                .with_database(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/geoip-country-test.mmdb"))
+                .unwrap()
+                .with_country_header(axum::http::HeaderName::from_static("cf-ipcountry")),
+        )))
+        .layer(axum::middleware::from_fn_with_state(metrics, track_metrics))
+        .layer(axum::middleware::from_fn(request_id))
         .with_state(service)
 }
 
@@ -76,6 +199,7 @@ async fn post_shorten_returns_201_with_short_url() {
         .as_str()
         .unwrap()
         .starts_with("http://localhost:8080/"));
+    assert_eq!(body["shortKey"], body["shortUrl"]);
     assert_eq!(
         body["originalUrl"],
         "https://leetcode.com/problems/add-two-numbers/description/"
@@ -128,7 +252,7 @@ async fn get_short_key_returns_301_redirect() {
     let short_url = body["shortUrl"].as_str().unwrap();
 
     // extract just the key from "http://localhost:8080/aB3kR9"
-    let key = short_url.split('/').last().unwrap();
+    let key = short_url.rsplit('/').next().unwrap();
 
     // now GET the short key
     let response = app
@@ -168,6 +292,44 @@ async fn get_unknown_short_key_returns_404() {
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
+// GET / without a root fallback answers a problem body like any other miss
+#[tokio::test]
+async fn get_root_without_fallback_returns_problem() {
+    let app = setup_app().await;
+
+    let response = app.oneshot(visit("localhost:8080", "/")).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["content-type"], "application/problem+json");
+    assert_eq!(json_body(response).await["code"], "url_not_found");
+}
+
+// bodies and query strings axum cannot parse are rejected with problem+json, not plain text
+#[tokio::test]
+async fn malformed_requests_return_problem_json() {
+    let app = setup_app().await;
+
+    let body = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/shorten")
+                .header("Content-Type", "application/json")
+                .body(Body::from("{\"url\": "))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let query = send(app, "GET", "/audit?limit=many").await;
+
+    for response in [body, query] {
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["content-type"], "application/problem+json");
+        assert_eq!(json_body(response).await["code"], "malformed_input");
+    }
+}
+
 // GET /{short_key} with key under 6 chars returns 400
 #[tokio::test]
 async fn get_short_key_under_6_chars_returns_400() {
@@ -185,4 +347,1395 @@ async fn get_short_key_under_6_chars_returns_400() {
         .unwrap();
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-}
\ No newline at end of file
+}
+// GET /usage returns zero consumption and no limits when quotas are off
+#[tokio::test]
+async fn get_usage_returns_consumption_for_workspace() {
+    let app = setup_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/usage?workspace=acme")
+                .header("Authorization", "Bearer s3cret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["workspaceId"], "acme");
+    assert_eq!(body["links"]["used"], 0);
+    assert!(body["clicks"]["limit"].is_null());
+}
+
+// sends a bodiless request with the admin token and returns the response
+async fn send(app: Router, method: &str, uri: &str) -> axum::response::Response {
+    app.oneshot(
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", "Bearer s3cret")
+            .header("X-Request-Id", "req-42")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+// DELETE /links/{key} stops the redirect and POST .../restore brings it back
+#[tokio::test]
+async fn delete_then_restore_link() {
+    let app = setup_app().await;
+    let (_, body) = post_shorten(app.clone(), "https://leetcode.com/problems/two-sum/").await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let deleted = send(app.clone(), "DELETE", &format!("/links/{key}")).await;
+    let while_deleted = send(app.clone(), "GET", &format!("/{key}")).await;
+    let restored = send(app.clone(), "POST", &format!("/links/{key}/restore")).await;
+    let after_restore = send(app.clone(), "GET", &format!("/{key}")).await;
+
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    assert_eq!(deleted.headers().get("x-request-id").unwrap(), "req-42");
+    assert_eq!(while_deleted.status(), StatusCode::NOT_FOUND);
+    assert_eq!(restored.status(), StatusCode::OK);
+    assert_eq!(after_restore.status(), StatusCode::MOVED_PERMANENTLY);
+}
+
+// GET /audit answers who changed a link
+#[tokio::test]
+async fn get_audit_lists_mutations_for_short_key() {
+    let app = setup_app().await;
+    let (_, body) = post_shorten(app.clone(), "https://leetcode.com/problems/3sum/").await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    send(app.clone(), "DELETE", &format!("/links/{key}")).await;
+
+    let response = send(app, "GET", &format!("/audit?shortKey={key}&action=delete")).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    let events = body["events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["actor"], "admin");
+    assert_eq!(events[0]["domain"], DEFAULT_DOMAIN);
+    assert_eq!(events[0]["requestId"], "req-42");
+    assert_eq!(events[0]["beforeUrl"], "https://leetcode.com/problems/3sum/");
+    assert!(body["nextCursor"].is_null());
+}
+
+// managing links needs the admin token, and a claimed X-Actor neither grants access nor names the actor
+#[tokio::test]
+async fn link_management_requires_credentials() {
+    let app = setup_app().await;
+    let (_, body) = post_shorten(app.clone(), "https://leetcode.com/problems/4sum/").await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    for (method, uri) in [
+        ("DELETE", format!("/links/{key}")),
+        ("POST", format!("/links/{key}/restore")),
+        ("GET", format!("/links/{key}/variants")),
+        ("GET", format!("/audit?shortKey={key}")),
+        ("GET", "/usage?workspace=acme".to_string()),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().method(method).uri(&uri).header("X-Actor", "mallory").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{method} {uri}");
+    }
+
+    let mut deleted = Request::builder().method("DELETE").uri(format!("/links/{key}"));
+    deleted = deleted.header("Authorization", "Bearer s3cret").header("X-Actor", "mallory");
+    app.clone().oneshot(deleted.body(Body::empty()).unwrap()).await.unwrap();
+    let events = json_body(send(app, "GET", &format!("/audit?shortKey={key}&action=delete")).await).await;
+    assert_eq!(events["events"][0]["actor"], "admin");
+}
+
+// an API key manages its own workspace's links and nothing else, until it is revoked
+#[tokio::test]
+async fn api_keys_are_confined_to_their_workspace() {
+    let app = setup_app().await;
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let keys = ApiKeyService::new(PostgresApiKeyRepository::new(pool));
+    let minted = keys.mint("acme", "ci").await.unwrap();
+    let bearer = format!("Bearer {}", minted.secret);
+    let as_key = |method: &str, uri: &str| {
+        Request::builder().method(method).uri(uri).header("Authorization", &bearer).body(Body::empty()).unwrap()
+    };
+
+    let ours = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/shorten")
+                .header("Authorization", &bearer)
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "url": "https://acme.io/launch" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let ours = json_body(ours).await["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let (_, theirs) = post_shorten(app.clone(), "https://globex.io/launch").await;
+    let theirs = theirs["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let deleted = app.clone().oneshot(as_key("DELETE", &format!("/links/{ours}"))).await.unwrap();
+    let foreign = app.clone().oneshot(as_key("DELETE", &format!("/links/{theirs}"))).await.unwrap();
+    let usage = app.clone().oneshot(as_key("GET", "/usage")).await.unwrap();
+    let other_usage = app.clone().oneshot(as_key("GET", "/usage?workspace=default")).await.unwrap();
+    let audit = app.clone().oneshot(as_key("GET", "/audit")).await.unwrap();
+
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    assert_eq!(foreign.status(), StatusCode::NOT_FOUND);
+    assert_eq!(json_body(usage).await["workspaceId"], "acme");
+    assert_eq!(other_usage.status(), StatusCode::FORBIDDEN);
+    let events = json_body(audit).await;
+    let events = events["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| event["actor"] == format!("api-key:{}", minted.key.id)));
+
+    keys.revoke(&redirect_service::model::AuditContext::system(), minted.key.id).await.unwrap();
+    let revoked = app.oneshot(as_key("POST", &format!("/links/{ours}/restore"))).await.unwrap();
+    assert_eq!(revoked.status(), StatusCode::UNAUTHORIZED);
+}
+
+// without credentials /shorten only fills the default workspace; keys stay in their own
+#[tokio::test]
+async fn shorten_confines_workspaces_to_their_callers() {
+    let app = setup_app().await;
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let keys = ApiKeyService::new(PostgresApiKeyRepository::new(pool));
+    let minted = keys.mint("acme", "ci").await.unwrap();
+    let shorten = |bearer: Option<&str>, body: Value| {
+        let mut request = Request::builder().method("POST").uri("/shorten").header("Content-Type", "application/json");
+        if let Some(bearer) = bearer {
+            request = request.header("Authorization", format!("Bearer {bearer}"));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let (status, anonymous) = send_json(app.clone(), shorten(None, json!({ "url": "https://example.com/a" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) =
+        send_json(app.clone(), shorten(None, json!({ "url": "https://example.com/b", "workspace": "acme" }))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, keyed) = send_json(app.clone(), shorten(Some(&minted.secret), json!({ "url": "https://example.com/c" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, foreign) = send_json(
+        app.clone(),
+        shorten(Some(&minted.secret), json!({ "url": "https://example.com/d", "workspace": "globex" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(foreign["code"], "forbidden");
+
+    let (status, admin) =
+        send_json(app.clone(), shorten(Some("s3cret"), json!({ "url": "https://example.com/e", "workspace": "globex" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The admin's preview names each link's workspace
+    for (link, workspace) in [(anonymous, "default"), (keyed, "acme"), (admin, "globex")] {
+        let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+        let page = body_text(send(app.clone(), "GET", &format!("/{key}+")).await).await;
+        assert!(page.contains(&format!("<dt>Owner</dt><dd>{workspace}</dd>")), "{workspace}");
+    }
+    let events = json_body(send(app, "GET", "/audit?action=create").await).await;
+    let actors: Vec<&str> = events["events"].as_array().unwrap().iter().map(|e| e["actor"].as_str().unwrap()).collect();
+    assert!(actors.contains(&"anonymous"));
+    assert!(actors.contains(&format!("api-key:{}", minted.key.id).as_str()));
+    assert!(actors.contains(&"admin"));
+}
+
+// PATCH /links/{key} only accepts destinations a new link could have
+#[tokio::test]
+async fn retarget_rejects_script_urls() {
+    let app = setup_app().await;
+    let (_, body) = post_shorten(app.clone(), "https://leetcode.com/problems/jump-game/").await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PATCH")
+                .uri(format!("/links/{key}"))
+                .header("Authorization", "Bearer s3cret")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "url": "javascript:alert(1)" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_body(response).await["code"], "invalid_url");
+    let location = app.oneshot(visit("localhost:8080", &format!("/{key}"))).await.unwrap();
+    assert_eq!(location.headers()["location"], "https://leetcode.com/problems/jump-game/");
+}
+
+// reads a response body as json
+async fn json_body(response: axum::response::Response) -> Value {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+// GET /{short_key} with unknown key returns a problem+json body with the request id
+#[tokio::test]
+async fn unknown_short_key_returns_problem_json() {
+    let app = setup_app().await;
+
+    let response = send(app, "GET", "/xxxxxx").await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "url_not_found");
+    assert_eq!(body["type"], "urn:redirect-service:problem:url_not_found");
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["requestId"], "req-42");
+}
+
+// invalid keys get a stable machine-readable code
+#[tokio::test]
+async fn invalid_short_key_returns_problem_code() {
+    let app = setup_app().await;
+
+    let response = send(app, "GET", "/abc").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_body(response).await["code"], "invalid_short_key");
+}
+
+// database errors never leak driver text to clients
+#[tokio::test]
+async fn database_error_body_hides_internal_details() {
+    let error = DomainError::DatabaseError(sqlx::Error::Protocol("relation \"urls\" is broken".to_string()));
+
+    let response = error.into_response();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "internal_error");
+    assert!(!body.to_string().contains("urls"));
+}
+
+// POST /shorten/batch returns a result per item, including individual errors
+#[tokio::test]
+async fn post_shorten_batch_returns_per_item_results() {
+    let app = setup_app().await;
+    let body = json!([
+        { "url": "https://leetcode.com/problems/add-two-numbers/description/" },
+        { "url": "ftp://example.com/file" },
+        { "url": "https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html" }
+    ])
+    .to_string();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/shorten/batch")
+                .header("Authorization", "Bearer s3cret")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["status"], 201);
+    assert!(results[0]["link"]["shortUrl"].is_string());
+    assert_eq!(results[1]["status"], 400);
+    assert_eq!(results[1]["error"]["code"], "invalid_url");
+    assert_eq!(results[2]["link"]["originalUrl"], "https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html");
+}
+
+// sends an import body to the admin endpoint with the given bearer token
+async fn post_import(app: Router, token: &str, content_type: &str, body: &str) -> axum::response::Response {
+    app.oneshot(
+        Request::builder()
+            .method("POST")
+            .uri("/admin/import")
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+// POST /admin/import streams CSV rows in and the imported keys redirect
+#[tokio::test]
+async fn admin_import_csv_creates_redirects() {
+    let app = setup_app().await;
+    let csv = "short_key,original_url,created_at\nmigr01,https://example.com/migrated,2020-01-01T00:00:00Z\nbad,https://example.com/x,\n";
+
+    let response = post_import(app.clone(), "s3cret", "text/csv", csv).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let report = json_body(response).await;
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["invalidCount"], 1);
+    assert_eq!(report["invalid"][0]["line"], 3);
+
+    let redirect = send(app, "GET", "/migr01").await;
+    assert_eq!(redirect.status(), StatusCode::MOVED_PERMANENTLY);
+}
+
+// admin endpoints reject callers without the token
+#[tokio::test]
+async fn admin_import_without_token_is_unauthorized() {
+    let app = setup_app().await;
+
+    let response = post_import(app, "wrong", "text/csv", "").await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(json_body(response).await["code"], "unauthorized");
+}
+
+// unknown formats are refused before anything is read
+#[tokio::test]
+async fn admin_import_with_unknown_format_is_unsupported() {
+    let app = setup_app().await;
+
+    let response = post_import(app, "s3cret", "application/xml", "<links/>").await;
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+// sends an authorized admin GET and returns status and body text
+async fn admin_get_text(app: Router, uri: &str) -> (StatusCode, String) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri(uri)
+                .header("Authorization", "Bearer s3cret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+// GET /export streams links as CSV that import can read back
+#[tokio::test]
+async fn export_links_as_csv() {
+    let app = setup_app().await;
+    post_shorten(app.clone(), "https://example.com/export,with,commas").await;
+
+    let (status, body) = admin_get_text(app, "/export?format=csv").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(lines[0], "short_key,original_url,created_at,workspace_id,domain");
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].contains("\"https://example.com/export,with,commas\""));
+}
+
+// GET /export?kind=clicks streams click events as JSON Lines
+#[tokio::test]
+async fn export_clicks_as_jsonl() {
+    let app = setup_app().await;
+    let (_, body) = post_shorten(app.clone(), "https://example.com/clicked").await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    send(app.clone(), "GET", &format!("/{key}")).await;
+
+    let (status, body) = admin_get_text(app, "/export?format=jsonl&kind=clicks").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let click: Value = serde_json::from_str(body.lines().next().unwrap()).unwrap();
+    assert_eq!(click["shortKey"], key);
+    assert_eq!(click["workspaceId"], "default");
+}
+
+// GET /metrics reports requests per route template, redirect outcomes and DB timings
+#[tokio::test]
+async fn metrics_track_routes_redirects_and_queries() {
+    let app = setup_app().await;
+    let (_, body) = post_shorten(app.clone(), "https://example.com/metered").await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    send(app.clone(), "GET", &format!("/{key}")).await;
+    send(app.clone(), "GET", "/zzzzzz").await;
+
+    let response = send(app, "GET", "/metrics").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+
+    assert!(text.contains(r#"http_requests_total{method="GET",route="/{short_key}",status="301"} 1"#));
+    assert!(text.contains(r#"http_requests_total{method="POST",route="/shorten",status="201"} 1"#));
+    assert!(text.contains(r#"redirects_total{outcome="hit"} 1"#));
+    assert!(text.contains(r#"redirects_total{outcome="miss"} 1"#));
+    assert!(text.contains(r#"db_query_duration_seconds_count{method="insert"} 1"#));
+    assert!(text.contains("db_pool_max_connections"));
+}
+
+// a valid incoming traceparent is echoed back unchanged
+#[tokio::test]
+async fn traceparent_is_propagated() {
+    let app = setup_app().await;
+    let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/zzzzzz")
+                .header("traceparent", traceparent)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers().get("traceparent").unwrap(), traceparent);
+}
+
+// a malformed traceparent is replaced by a freshly started trace
+#[tokio::test]
+async fn invalid_traceparent_starts_new_trace() {
+    let app = setup_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/zzzzzz")
+                .header("traceparent", "00-00000000000000000000000000000000-00f067aa0ba902b7-01")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let echoed = response.headers().get("traceparent").unwrap().to_str().unwrap();
+    let parsed = TraceParent::parse(echoed).expect("echoed traceparent should be valid");
+    assert_ne!(parsed.trace_id, "00000000000000000000000000000000");
+}
+
+// captures formatted log output for the duration of a test
+#[derive(Clone, Default)]
+struct LogCapture(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// each request logs one JSON line with request id, route, short key, status and latency
+#[tokio::test]
+async fn request_span_is_logged_as_json() {
+    let app = setup_app().await;
+    let capture = LogCapture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    send(app, "GET", "/zzzzzz").await;
+
+    let logs = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    let line = logs
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .find(|line| line["message"] == "request completed")
+        .expect("request completed event");
+    assert_eq!(line["span"]["request_id"], "req-42");
+    assert_eq!(line["span"]["route"], "/{short_key}");
+    assert_eq!(line["span"]["short_key"], "zzzzzz");
+    assert_eq!(line["span"]["status"], 404);
+    assert!(line["span"]["latency_ms"].is_number());
+}
+
+// builds the probe routes around a shared Health handle
+async fn setup_health() -> (Router, Health) {
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let health = Health::new(pool);
+    let app = Router::new()
+        .route("/healthz", axum::routing::get(healthz))
+        .route("/readyz", axum::routing::get(readyz))
+        .route("/version", axum::routing::get(version))
+        .with_state(health.clone());
+    (app, health)
+}
+
+// GET /healthz and GET /version answer without touching the database
+#[tokio::test]
+async fn liveness_and_version() {
+    let (app, _) = setup_health().await;
+
+    let live = send(app.clone(), "GET", "/healthz").await;
+    let version = send(app, "GET", "/version").await;
+
+    assert_eq!(live.status(), StatusCode::OK);
+    assert_eq!(version.status(), StatusCode::OK);
+    let body = json_body(version).await;
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["gitSha"].is_string());
+}
+
+// GET /readyz passes with a reachable, migrated database and fails once draining starts
+#[tokio::test]
+async fn readiness_flips_when_draining() {
+    let (app, health) = setup_health().await;
+
+    let ready = send(app.clone(), "GET", "/readyz").await;
+    assert_eq!(ready.status(), StatusCode::OK);
+    let body = json_body(ready).await;
+    assert_eq!(body["database"]["ok"], true);
+    assert_eq!(body["migrations"]["ok"], true);
+
+    health.begin_draining();
+    let draining = send(app, "GET", "/readyz").await;
+
+    assert_eq!(draining.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = json_body(draining).await;
+    assert_eq!(body["ready"], false);
+    assert_eq!(body["draining"], true);
+}
+
+async fn send_json(app: Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+// links on a registered domain get that domain's short URL and only resolve under its Host
+#[tokio::test]
+async fn branded_domain_routes_by_host() {
+    let app = setup_app().await;
+    let register = Request::builder()
+        .method("POST")
+        .uri("/admin/domains")
+        .header("Authorization", "Bearer s3cret")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "host": "Go.Acme.io" }).to_string()))
+        .unwrap();
+    let (status, domain) = send_json(app.clone(), register).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(domain["host"], "go.acme.io");
+
+    let shorten = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "url": "https://example.com/branded", "domain": "go.acme.io" }).to_string()))
+        .unwrap();
+    let (status, link) = send_json(app.clone(), shorten).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let short_url = link["shortUrl"].as_str().unwrap();
+    let key = short_url.strip_prefix("https://go.acme.io/").unwrap();
+
+    let visit = |host: &str| {
+        Request::builder()
+            .uri(format!("/{key}"))
+            .header("Host", host)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let on_brand = app.clone().oneshot(visit("go.acme.io")).await.unwrap();
+    let on_default = app.oneshot(visit("localhost:8080")).await.unwrap();
+
+    assert_eq!(on_brand.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(on_brand.headers()["location"], "https://example.com/branded");
+    assert_eq!(on_default.status(), StatusCode::NOT_FOUND);
+}
+
+// shortening on a domain nobody registered is a client error
+#[tokio::test]
+async fn shorten_on_unknown_domain_returns_422() {
+    let app = setup_app().await;
+    let shorten = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "url": "https://example.com/", "domain": "nope.io" }).to_string()))
+        .unwrap();
+
+    let (status, problem) = send_json(app, shorten).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(problem["code"], "unknown_domain");
+}
+
+async fn configure_domain(app: Router, host: &str, fallbacks: Value) -> (StatusCode, Value) {
+    let admin = |method: &str, uri: String, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Authorization", "Bearer s3cret")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    send_json(app.clone(), admin("POST", "/admin/domains".to_string(), json!({ "host": host }))).await;
+    send_json(app, admin("PUT", format!("/admin/domains/{host}/fallbacks"), fallbacks)).await
+}
+
+fn visit(host: &str, path: &str) -> Request<Body> {
+    Request::builder().uri(path).header("Host", host).body(Body::empty()).unwrap()
+}
+
+// a workspace's domain takes links from that workspace's callers only, whatever the body names
+#[tokio::test]
+async fn owned_domain_needs_its_workspace_credentials() {
+    let app = setup_app().await;
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let keys = ApiKeyService::new(PostgresApiKeyRepository::new(pool));
+    let register = Request::builder()
+        .method("POST")
+        .uri("/admin/domains")
+        .header("Authorization", "Bearer s3cret")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "host": "go.acme.io", "workspace": "acme" }).to_string()))
+        .unwrap();
+    let (status, _) = send_json(app.clone(), register).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let shorten = |bearer: Option<&str>, body: Value| {
+        let mut request = Request::builder().method("POST").uri("/shorten").header("Content-Type", "application/json");
+        if let Some(bearer) = bearer {
+            request = request.header("Authorization", format!("Bearer {bearer}"));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+    let acme = keys.mint("acme", "ci").await.unwrap();
+    let globex = keys.mint("globex", "ci").await.unwrap();
+
+    let (status, _) = send_json(
+        app.clone(),
+        shorten(None, json!({ "url": "https://example.com/x", "domain": "go.acme.io", "workspace": "acme" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, anonymous) =
+        send_json(app.clone(), shorten(None, json!({ "url": "https://example.com/x", "domain": "go.acme.io" }))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(anonymous["code"], "unknown_domain");
+    let (status, _) = send_json(
+        app.clone(),
+        shorten(Some(&globex.secret), json!({ "url": "https://example.com/x", "domain": "go.acme.io", "workspace": "acme" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send_json(
+        app.clone(),
+        shorten(Some(&globex.secret), json!({ "url": "https://example.com/x", "domain": "go.acme.io" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, owned) = send_json(
+        app.clone(),
+        shorten(Some(&acme.secret), json!({ "url": "https://example.com/x", "domain": "go.acme.io" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(owned["shortUrl"].as_str().unwrap().contains("go.acme.io/"));
+}
+
+// a domain's root and unknown keys redirect where configured; other domains keep the plain 404
+#[tokio::test]
+async fn domain_fallbacks_redirect_root_and_unknown_keys() {
+    let app = setup_app().await;
+    let (status, domain) = configure_domain(
+        app.clone(),
+        "acme.link",
+        json!({ "rootUrl": "https://acme.io/", "notFoundUrl": "https://acme.io/missing?src=short" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(domain["rootUrl"], "https://acme.io/");
+
+    let root = app.clone().oneshot(visit("acme.link", "/")).await.unwrap();
+    let unknown = app.clone().oneshot(visit("acme.link", "/nope12")).await.unwrap();
+    let malformed = app.clone().oneshot(visit("acme.link", "/ab")).await.unwrap();
+    let elsewhere = app.oneshot(visit("localhost:8080", "/")).await.unwrap();
+
+    assert_eq!(root.status(), StatusCode::FOUND);
+    assert_eq!(root.headers()["location"], "https://acme.io/");
+    assert_eq!(unknown.status(), StatusCode::FOUND);
+    assert_eq!(unknown.headers()["location"], "https://acme.io/missing?src=short&key=nope12");
+    assert_eq!(malformed.headers()["location"], "https://acme.io/missing?src=short&key=ab");
+    assert_eq!(elsewhere.status(), StatusCode::NOT_FOUND);
+}
+
+// a branded 404 page is served as HTML; it cannot be combined with a not-found redirect
+#[tokio::test]
+async fn domain_fallback_page_is_html() {
+    let app = setup_app().await;
+    let page = "<h1>This link took a wrong turn</h1>";
+    let (status, _) = configure_domain(app.clone(), "acme.link", json!({ "notFoundPage": page })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let response = app.clone().oneshot(visit("acme.link", "/xxxxxx")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, page.as_bytes());
+
+    let (status, problem) = configure_domain(
+        app,
+        "acme.link",
+        json!({ "notFoundUrl": "https://acme.io/", "notFoundPage": page }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(problem["code"], "malformed_input");
+}
+
+async fn body_text(response: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+// a protected link prompts for its password, then redirects visitors holding the unlock cookie
+#[tokio::test]
+async fn protected_link_prompts_then_unlocks_with_cookie() {
+    let app = setup_app().await;
+    let shorten = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "url": "https://example.com/plans", "password": "hunter22" }).to_string()))
+        .unwrap();
+    let (_, link) = send_json(app.clone(), shorten).await;
+    assert_eq!(link["passwordProtected"], true);
+    let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let prompt = app.clone().oneshot(visit("localhost:8080", &format!("/{key}"))).await.unwrap();
+    assert_eq!(prompt.status(), StatusCode::OK);
+    assert_eq!(prompt.headers()["cache-control"], "private, no-store");
+    assert!(body_text(prompt).await.contains(&format!(r#"action="/{key}""#)));
+
+    let submit = |password: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/{key}"))
+            .header("Host", "localhost:8080")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(Body::from(format!("password={password}")))
+            .unwrap()
+    };
+    let wrong = app.clone().oneshot(submit("nope")).await.unwrap();
+    assert_eq!(wrong.status(), StatusCode::FORBIDDEN);
+    assert!(body_text(wrong).await.contains("Incorrect password"));
+
+    let right = app.clone().oneshot(submit("hunter22")).await.unwrap();
+    assert_eq!(right.status(), StatusCode::SEE_OTHER);
+    assert_eq!(right.headers()["location"], format!("/{key}"));
+    let cookie = right.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.contains(&format!("Path=/{key}")) && cookie.contains("HttpOnly"));
+    assert!(!cookie.contains("Secure"));
+
+    let unlocked = Request::builder()
+        .uri(format!("/{key}"))
+        .header("Host", "localhost:8080")
+        .header("Cookie", cookie.split(';').next().unwrap())
+        .body(Body::empty())
+        .unwrap();
+    let redirect = app.oneshot(unlocked).await.unwrap();
+    assert_eq!(redirect.status(), StatusCode::FOUND);
+    assert_eq!(redirect.headers()["location"], "https://example.com/plans");
+    assert_eq!(redirect.headers()["cache-control"], "private, no-store");
+}
+
+// a limited link redirects uncached until spent, then answers 410 or its exhausted URL
+#[tokio::test]
+async fn click_limited_links_expire() {
+    let app = setup_app().await;
+    let shorten = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let (_, once) = send_json(app.clone(), shorten(json!({ "url": "https://example.com/once", "maxClicks": 1 }))).await;
+    let (_, moved) = send_json(
+        app.clone(),
+        shorten(json!({ "url": "https://example.com/sale", "maxClicks": 1, "exhaustedUrl": "https://example.com/sold-out" })),
+    )
+    .await;
+    assert_eq!(once["maxClicks"], 1);
+    assert_eq!(once["clicksRemaining"], 1);
+    let key = |link: &Value| link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let first = app.clone().oneshot(visit("localhost:8080", &format!("/{}", key(&once)))).await.unwrap();
+    assert_eq!(first.status(), StatusCode::FOUND);
+    assert_eq!(first.headers()["cache-control"], "private, no-store");
+    let gone = app.clone().oneshot(visit("localhost:8080", &format!("/{}", key(&once)))).await.unwrap();
+    assert_eq!(gone.status(), StatusCode::GONE);
+    assert_eq!(json_body(gone).await["code"], "link_exhausted");
+
+    app.clone().oneshot(visit("localhost:8080", &format!("/{}", key(&moved)))).await.unwrap();
+    let redirected = app.oneshot(visit("localhost:8080", &format!("/{}", key(&moved)))).await.unwrap();
+    assert_eq!(redirected.status(), StatusCode::FOUND);
+    assert_eq!(redirected.headers()["location"], "https://example.com/sold-out");
+}
+
+// outside its window a link answers 404 or 410 with distinct codes, or its fallback
+#[tokio::test]
+async fn scheduled_links_answer_outside_their_window() {
+    let app = setup_app().await;
+    let shorten = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let (status, upcoming) = send_json(
+        app.clone(),
+        shorten(json!({ "url": "https://example.com/launch", "activeFrom": "2999-01-01T00:00:00Z" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(upcoming["activeFrom"], "2999-01-01T00:00:00Z");
+    let (_, ended) = send_json(
+        app.clone(),
+        shorten(json!({
+            "url": "https://example.com/old-sale",
+            "activeUntil": "2000-01-01T00:00:00Z",
+            "endedUrl": "https://example.com/sales"
+        })),
+    )
+    .await;
+    let key = |link: &Value| link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let early = app.clone().oneshot(visit("localhost:8080", &format!("/{}", key(&upcoming)))).await.unwrap();
+    assert_eq!(early.status(), StatusCode::NOT_FOUND);
+    assert_eq!(json_body(early).await["code"], "link_not_yet_active");
+
+    let late = app.oneshot(visit("localhost:8080", &format!("/{}", key(&ended)))).await.unwrap();
+    assert_eq!(late.status(), StatusCode::FOUND);
+    assert_eq!(late.headers()["location"], "https://example.com/sales");
+    assert_eq!(late.headers()["cache-control"], "private, no-store");
+}
+
+// the User-Agent picks the platform route; unknown clients get the default destination
+#[tokio::test]
+async fn platform_routes_follow_user_agent() {
+    let app = setup_app().await;
+    let shorten = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({
+                "url": "https://example.com/app",
+                "platformUrls": { "ios": "https://apps.apple.com/app/id1", "android": "https://play.google.com/store/apps/details?id=com.example" }
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let (_, link) = send_json(app.clone(), shorten).await;
+    assert_eq!(link["platformUrls"]["ios"], "https://apps.apple.com/app/id1");
+    let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let follow = |user_agent: &str| {
+        Request::builder()
+            .uri(format!("/{key}"))
+            .header("Host", "localhost:8080")
+            .header("User-Agent", user_agent)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let iphone = app
+        .clone()
+        .oneshot(follow("Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15"))
+        .await
+        .unwrap();
+    let android = app
+        .clone()
+        .oneshot(follow("Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 Mobile Safari/537.36"))
+        .await
+        .unwrap();
+    let curl = app.oneshot(follow("curl/8.5.0")).await.unwrap();
+
+    assert_eq!(iphone.status(), StatusCode::FOUND);
+    assert_eq!(iphone.headers()["location"], "https://apps.apple.com/app/id1");
+    assert_eq!(android.headers()["location"], "https://play.google.com/store/apps/details?id=com.example");
+    assert_eq!(curl.headers()["location"], "https://example.com/app");
+    assert_eq!(curl.headers()["cache-control"], "private, no-store");
+}
+
+// the visitor's country, from the trusted header or the client address, picks the route
+#[tokio::test]
+async fn country_routes_follow_geolocation() {
+    let app = setup_app().await;
+    let shorten = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({
+                "url": "https://example.com/store",
+                "countryUrls": { "gb": "https://example.co.uk/store", "DE": "https://example.de/store" }
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let (status, link) = send_json(app.clone(), shorten).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(link["countryUrls"]["GB"], "https://example.co.uk/store");
+    let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let from = |client: &str, country: Option<&str>| {
+        let mut request = visit("localhost:8080", &format!("/{key}"));
+        request
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo(std::net::SocketAddr::new(client.parse().unwrap(), 50000)));
+        if let Some(country) = country {
+            request.headers_mut().insert("CF-IPCountry", country.parse().unwrap());
+        }
+        request
+    };
+
+    let london = app.clone().oneshot(from("81.2.69.142", None)).await.unwrap();
+    let proxied = app.clone().oneshot(from("81.2.69.142", Some("DE"))).await.unwrap();
+    let unknown = app.clone().oneshot(from("10.0.0.1", None)).await.unwrap();
+
+    assert_eq!(london.status(), StatusCode::FOUND);
+    assert_eq!(london.headers()["location"], "https://example.co.uk/store");
+    assert_eq!(proxied.headers()["location"], "https://example.de/store");
+    assert_eq!(unknown.headers()["location"], "https://example.com/store");
+
+    let (_, body) = admin_get_text(app, "/export?format=jsonl&kind=clicks").await;
+    let countries: Vec<Value> = body
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["country"].clone())
+        .collect();
+    assert_eq!(countries, vec![json!("GB"), json!("DE"), Value::Null]);
+}
+
+// a split link assigns a variant on the first visit, keeps it through the visitor cookie
+// and reports clicks per variant
+#[tokio::test]
+async fn split_links_keep_visitors_on_their_variant() {
+    let app = setup_app().await;
+    let shorten = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({
+                "url": "https://example.com/pricing",
+                "variants": [
+                    { "url": "https://example.com/pricing-a", "weight": 50 },
+                    { "url": "https://example.com/pricing-b", "weight": 50 }
+                ]
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let (status, link) = send_json(app.clone(), shorten).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(link["variants"][1]["weight"], 50);
+    let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+
+    let first = app.clone().oneshot(visit("localhost:8080", &format!("/{key}"))).await.unwrap();
+    assert_eq!(first.status(), StatusCode::FOUND);
+    assert_eq!(first.headers()["cache-control"], "private, no-store");
+    let cookie = first.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.starts_with("rs_vid=") && cookie.contains("Path=/;") && !cookie.contains("Secure"), "{cookie}");
+    let location = first.headers()["location"].to_str().unwrap().to_string();
+    assert!(location.starts_with("https://example.com/pricing-"), "{location}");
+
+    let visitor = cookie.split(';').next().unwrap().to_string();
+    let mut again = visit("localhost:8080", &format!("/{key}"));
+    again.headers_mut().insert("Cookie", visitor.parse().unwrap());
+    let again = app.clone().oneshot(again).await.unwrap();
+    assert_eq!(again.headers()["location"], location.as_str());
+    assert!(again.headers().get("set-cookie").is_none());
+
+    let mut stats = visit("localhost:8080", &format!("/links/{key}/variants"));
+    stats.headers_mut().insert("Authorization", "Bearer s3cret".parse().unwrap());
+    let stats = app.clone().oneshot(stats).await.unwrap();
+    assert_eq!(stats.status(), StatusCode::OK);
+    let stats = json_body(stats).await;
+    let chosen = stats.as_array().unwrap().iter().find(|stat| stat["url"] == location.as_str()).unwrap();
+    assert_eq!(chosen["clicks"], 2);
+    let total: i64 = stats.as_array().unwrap().iter().map(|stat| stat["clicks"].as_i64().unwrap()).sum();
+    assert_eq!(total, 2);
+}
+
+// redirects that forward a path are hits; previews, found or not, are neither hits nor misses
+#[tokio::test]
+async fn metrics_count_path_redirects_and_leave_previews_out() {
+    let app = setup_app().await;
+    let request = Request::builder()
+        .method("POST")
+        .uri("/shorten")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "url": "https://example.com/docs", "forwardPath": true }).to_string()))
+        .unwrap();
+    let (_, body) = send_json(app.clone(), request).await;
+    let key = body["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    send(app.clone(), "GET", &format!("/{key}/guides/setup")).await;
+    send(app.clone(), "GET", &format!("/{key}+")).await;
+    send(app.clone(), "GET", "/zzzzzz+").await;
+
+    let response = send(app, "GET", "/metrics").await;
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+
+    assert!(text.contains(r#"redirects_total{outcome="hit"} 1"#));
+    assert!(text.contains(r#"redirects_total{outcome="preview"} 2"#));
+    assert!(!text.contains(r#"redirects_total{outcome="miss"}"#));
+}
+
+// incoming query strings and extra path are forwarded only by links that ask for it
+#[tokio::test]
+async fn query_and_path_passthrough() {
+    let app = setup_app().await;
+    let shorten = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let key = |link: &Value| link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let (status, docs) = send_json(
+        app.clone(),
+        shorten(json!({ "url": "https://example.com/docs?lang=en", "forwardQuery": "replace", "forwardPath": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(docs["forwardQuery"], "replace");
+    let (_, plain) = send_json(app.clone(), shorten(json!({ "url": "https://example.com/plain" }))).await;
+    let (docs, plain) = (key(&docs), key(&plain));
+
+    let deep = app
+        .clone()
+        .oneshot(visit("localhost:8080", &format!("/{docs}/guides/setup?lang=de&ref=twitter")))
+        .await
+        .unwrap();
+    let ignored = app.clone().oneshot(visit("localhost:8080", &format!("/{plain}?ref=twitter"))).await.unwrap();
+    let no_path = app.clone().oneshot(visit("localhost:8080", &format!("/{plain}/guides"))).await.unwrap();
+
+    assert_eq!(deep.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(deep.headers()["location"], "https://example.com/docs/guides/setup?lang=de&ref=twitter");
+    assert_eq!(ignored.headers()["location"], "https://example.com/plain");
+    assert_eq!(no_path.status(), StatusCode::NOT_FOUND);
+}
+
+// a workspace template plus per-link fields tag the destination; the same tags find the same link
+#[tokio::test]
+async fn shorten_applies_utm_templates() {
+    let app = setup_app().await;
+    let template = Request::builder()
+        .method("PUT")
+        .uri("/admin/utm-templates/newsletter")
+        .header("Authorization", "Bearer s3cret")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "workspace": "acme", "source": "newsletter", "medium": "email" }).to_string()))
+        .unwrap();
+    let (status, saved) = send_json(app.clone(), template).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(saved["workspaceId"], "acme");
+    let shorten = |campaign: &str, template: &str| {
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Authorization", "Bearer s3cret")
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                json!({
+                    "url": "https://example.com/sale",
+                    "workspace": "acme",
+                    "utmTemplate": template,
+                    "utm": { "campaign": campaign }
+                })
+                .to_string(),
+            ))
+            .unwrap()
+    };
+
+    let (status, first) = send_json(app.clone(), shorten("spring", "newsletter")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        first["originalUrl"],
+        "https://example.com/sale?utm_source=newsletter&utm_medium=email&utm_campaign=spring"
+    );
+    let (_, again) = send_json(app.clone(), shorten("spring", "newsletter")).await;
+    assert_eq!(again["shortUrl"], first["shortUrl"]);
+    let (_, summer) = send_json(app.clone(), shorten("summer", "newsletter")).await;
+    assert_ne!(summer["shortUrl"], first["shortUrl"]);
+    let (status, missing) = send_json(app.clone(), shorten("spring", "missing")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(missing["code"], "unknown_utm_template");
+
+    // Only callers of the workspace can use its templates
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let globex = ApiKeyService::new(PostgresApiKeyRepository::new(pool)).mint("globex", "ci").await.unwrap();
+    let borrow = |bearer: Option<&str>, workspace: Option<&str>| {
+        let mut body = json!({ "url": "https://example.com/borrowed", "utmTemplate": "newsletter" });
+        if let Some(workspace) = workspace {
+            body["workspace"] = json!(workspace);
+        }
+        let mut request = Request::builder().method("POST").uri("/shorten").header("Content-Type", "application/json");
+        if let Some(bearer) = bearer {
+            request = request.header("Authorization", format!("Bearer {bearer}"));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+    let (status, _) = send_json(app.clone(), borrow(None, Some("acme"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, anonymous) = send_json(app.clone(), borrow(None, None)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(anonymous["code"], "unknown_utm_template");
+    let (status, _) = send_json(app.clone(), borrow(Some(&globex.secret), Some("acme"))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, foreign) = send_json(app, borrow(Some(&globex.secret), None)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(foreign["code"], "unknown_utm_template");
+}
+
+// adding `+` to a link shows where it goes instead of redirecting, without counting a click
+#[tokio::test]
+async fn plus_suffix_previews_link() {
+    let app = setup_app().await;
+    let shorten = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let key = |link: &Value| link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let (_, open) = send_json(app.clone(), shorten(json!({ "url": "https://example.com/search?q=<b>" }))).await;
+    let (_, locked) =
+        send_json(app.clone(), shorten(json!({ "url": "https://example.com/secret", "password": "hunter22" }))).await;
+    let (open, locked) = (key(&open), key(&locked));
+    app.clone().oneshot(visit("localhost:8080", &format!("/{open}"))).await.unwrap();
+
+    let preview = app.clone().oneshot(visit("localhost:8080", &format!("/{open}+"))).await.unwrap();
+    assert_eq!(preview.status(), StatusCode::OK);
+    assert_eq!(preview.headers()["cache-control"], "private, no-store");
+    let page = body_text(preview).await;
+    assert!(page.contains("https://example.com/search?q=&lt;b&gt;"));
+    assert!(!page.contains("<b>"));
+    assert!(!page.contains("<dt>Owner</dt>"));
+    assert!(!page.contains("<dt>Clicks</dt>"));
+
+    let hidden = body_text(app.clone().oneshot(visit("localhost:8080", &format!("/{locked}+"))).await.unwrap()).await;
+    assert!(hidden.contains("password protected"));
+    assert!(!hidden.contains("example.com/secret"));
+    let missing = app.clone().oneshot(visit("localhost:8080", "/absent1+")).await.unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    let as_admin = visit("localhost:8080", &format!("/{open}+"));
+    let (mut parts, body) = as_admin.into_parts();
+    parts.headers.insert("Authorization", "Bearer s3cret".parse().unwrap());
+    let again = body_text(app.clone().oneshot(Request::from_parts(parts, body)).await.unwrap()).await;
+    assert!(again.contains("<dt>Owner</dt><dd>default</dd>"));
+    assert!(again.contains("<dt>Clicks</dt><dd>1</dd>"));
+}
+
+// a preview's owner and clicks are for the owning workspace; other API keys see the public page
+#[tokio::test]
+async fn preview_stats_need_the_owning_workspace() {
+    let app = setup_app().await;
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let keys = ApiKeyService::new(PostgresApiKeyRepository::new(pool));
+    let (_, link) = send_json(
+        app.clone(),
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Authorization", "Bearer s3cret")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({ "url": "https://example.com/owned", "workspace": "acme" }).to_string()))
+            .unwrap(),
+    )
+    .await;
+    let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let preview_as = |secret: &str| {
+        Request::builder()
+            .uri(format!("/{key}+"))
+            .header("Host", "localhost:8080")
+            .header("Authorization", format!("Bearer {secret}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let own = keys.mint("acme", "ci").await.unwrap();
+    let page = body_text(app.clone().oneshot(preview_as(&own.secret)).await.unwrap()).await;
+    assert!(page.contains("<dt>Owner</dt><dd>acme</dd>"));
+    assert!(page.contains("<dt>Clicks</dt><dd>0</dd>"));
+
+    let other = keys.mint("globex", "ci").await.unwrap();
+    let page = body_text(app.clone().oneshot(preview_as(&other.secret)).await.unwrap()).await;
+    assert!(page.contains("https://example.com/owned"));
+    assert!(!page.contains("<dt>Owner</dt>"));
+    assert!(!page.contains("<dt>Clicks</dt>"));
+
+    let page = body_text(app.oneshot(preview_as("wrong")).await.unwrap()).await;
+    assert!(!page.contains("<dt>Owner</dt>"));
+}
+
+// only web destinations become links on the preview page; anything else is shown as text
+#[test]
+fn preview_page_links_only_web_destinations() {
+    let preview = |destination: &str| LinkPreview {
+        short_url: "http://localhost:8080/abc1234".to_string(),
+        destination: Some(destination.to_string()),
+        routed: false,
+        created_at: chrono::Utc::now(),
+        owner: None,
+        clicks: None,
+    };
+
+    let page = preview_page(&preview("https://example.com/"));
+    assert!(page.contains(r#"<a href="https://example.com/""#));
+
+    let page = preview_page(&preview("javascript:alert(1)"));
+    assert!(page.contains("<code>javascript:alert(1)</code>"));
+    assert!(!page.contains(r#"href="javascript"#));
+    let page = preview_page(&preview("data:text/html,<script>alert(1)</script>"));
+    assert!(!page.contains(r#"href="data"#));
+    assert!(!page.contains("<script>"));
+}
+
+// warning pages stand in for the redirect on links, domains and listed destinations that ask for them
+#[tokio::test]
+async fn interstitials_replace_the_redirect() {
+    let app = setup_app().await;
+    let shorten = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let key = |link: &Value| link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let (status, counted) =
+        send_json(app.clone(), shorten(json!({ "url": "https://example.com/docs", "interstitial": { "countdown": 5 } }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(counted["interstitial"]["countdown"], 5);
+    let (_, flagged) = send_json(app.clone(), shorten(json!({ "url": "https://flagged.example/prize" }))).await;
+    let (counted, flagged) = (key(&counted), key(&flagged));
+
+    let page = app.clone().oneshot(visit("localhost:8080", &format!("/{counted}"))).await.unwrap();
+    assert_eq!(page.status(), StatusCode::OK);
+    assert_eq!(page.headers()["cache-control"], "private, no-store");
+    let page = body_text(page).await;
+    assert!(page.contains(r#"<meta http-equiv="refresh" content="5;url=https://example.com/docs">"#));
+    assert!(page.contains(r#"href="https://example.com/docs""#));
+    let warned = body_text(app.clone().oneshot(visit("localhost:8080", &format!("/{flagged}"))).await.unwrap()).await;
+    assert!(warned.contains("warning list"));
+    assert!(!warned.contains("http-equiv"));
+
+    configure_domain(app.clone(), "acme.link", json!({})).await;
+    let (_, on_acme) = send_json(app.clone(), shorten(json!({ "url": "https://example.com/acme", "domain": "acme.link" }))).await;
+    let on_acme = key(&on_acme);
+    let toggle = |method: &str| {
+        Request::builder()
+            .method(method)
+            .uri("/admin/domains/acme.link/interstitial")
+            .header("Authorization", "Bearer s3cret")
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap()
+    };
+    let (status, domain) = send_json(app.clone(), toggle("PUT")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(domain["interstitial"]["countdown"], 0);
+    let leaving = app.clone().oneshot(visit("acme.link", &format!("/{on_acme}"))).await.unwrap();
+    assert_eq!(leaving.status(), StatusCode::OK);
+    assert!(body_text(leaving).await.contains("You are leaving"));
+
+    send_json(app.clone(), toggle("DELETE")).await;
+    let direct = app.clone().oneshot(visit("acme.link", &format!("/{on_acme}"))).await.unwrap();
+    assert_eq!(direct.status(), StatusCode::MOVED_PERMANENTLY);
+}
+
+// QR codes draw the short URL in the negotiated format and revalidate by ETag
+#[tokio::test]
+async fn qr_codes_are_cacheable_images() {
+    let app = setup_app().await;
+    let (_, link) = send_json(
+        app.clone(),
+        Request::builder()
+            .method("POST")
+            .uri("/shorten")
+            .header("Content-Type", "application/json")
+            .body(Body::from(json!({ "url": "https://example.com/qr" }).to_string()))
+            .unwrap(),
+    )
+    .await;
+    let key = link["shortUrl"].as_str().unwrap().rsplit('/').next().unwrap().to_string();
+    let qr = |query: &str, headers: &[(&str, &str)]| {
+        let mut request = Request::builder().uri(format!("/{key}/qr{query}")).header("Host", "localhost:8080");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let svg = app.clone().oneshot(qr("", &[])).await.unwrap();
+    assert_eq!(svg.status(), StatusCode::OK);
+    assert_eq!(svg.headers()["content-type"], "image/svg+xml");
+    assert_eq!(svg.headers()["cache-control"], "public, max-age=86400");
+    let etag = svg.headers()["etag"].to_str().unwrap().to_string();
+    assert!(body_text(svg).await.starts_with("<svg"));
+
+    let png = app.clone().oneshot(qr("?size=128&ec=H&fg=%23336699", &[("Accept", "image/png")])).await.unwrap();
+    assert_eq!(png.headers()["content-type"], "image/png");
+    assert_ne!(png.headers()["etag"], etag.as_str());
+    let bytes = axum::body::to_bytes(png.into_body(), usize::MAX).await.unwrap();
+    assert!(bytes.starts_with(b"\x89PNG"));
+
+    let cached = app.clone().oneshot(qr("", &[("If-None-Match", &etag)])).await.unwrap();
+    assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(cached.headers()["etag"], etag.as_str());
+
+    let bad = app.clone().oneshot(qr("?size=10", &[])).await.unwrap();
+    assert_eq!(bad.status(), StatusCode::BAD_REQUEST);
+    let missing = app.clone().oneshot(visit("localhost:8080", "/absent1/qr")).await.unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,104 @@
+#!/usr/bin/env python3
+"""Writes geoip-country-test.mmdb, a tiny MaxMind DB (GeoIP2-Country layout) for the tests.
+
+Only the networks below are in it; every other address has no data. Rerun this after
+changing them:
+
+    python3 tests/fixtures/make_geoip_fixture.py
+"""
+import ipaddress
+import os
+import struct
+
+NETWORKS = {
+    "81.2.69.0/24": "GB",
+    "89.160.20.0/24": "SE",
+    "216.160.83.0/24": "US",
+    "2001:218::/32": "JP",
+}
+
+RECORD_SIZE = 24
+
+
+def control(type_, size):
+    extended = type_ > 7
+    first = 0 if extended else type_ << 5
+    if size < 29:
+        head, tail = bytes([first | size]), b""
+    elif size < 29 + 256:
+        head, tail = bytes([first | 29]), bytes([size - 29])
+    else:
+        head, tail = bytes([first | 30]), struct.pack(">H", size - 285)
+    return head + (bytes([type_ - 7]) if extended else b"") + tail
+
+
+def encode(value):
+    if isinstance(value, str):
+        raw = value.encode()
+        return control(2, len(raw)) + raw
+    if isinstance(value, dict):
+        return control(7, len(value)) + b"".join(encode(k) + encode(v) for k, v in value.items())
+    if isinstance(value, list):
+        return control(11, len(value)) + b"".join(encode(v) for v in value)
+    if isinstance(value, tuple):  # (type, int): 5 = uint16, 6 = uint32, 9 = uint64
+        type_, number = value
+        raw = number.to_bytes((number.bit_length() + 7) // 8, "big")
+        return control(type_, len(raw)) + raw
+    raise TypeError(value)
+
+
+def main():
+    data = b""
+    offsets = {}
+    for country in sorted(set(NETWORKS.values())):
+        offsets[country] = len(data)
+        data += encode({"country": {"iso_code": country}})
+
+    # Binary trie over 128-bit addresses; IPv4 lives under ::/96 as MaxMind expects
+    nodes = [[None, None]]
+    for cidr, country in NETWORKS.items():
+        network = ipaddress.ip_network(cidr)
+        bits = int(network.network_address)
+        length = network.prefixlen
+        if network.version == 4:
+            length += 96
+        node = 0
+        for i in range(length):
+            bit = (bits >> (127 - i)) & 1
+            if i == length - 1:
+                nodes[node][bit] = ("data", country)
+            else:
+                if not isinstance(nodes[node][bit], int):
+                    nodes.append([None, None])
+                    nodes[node][bit] = len(nodes) - 1
+                node = nodes[node][bit]
+
+    node_count = len(nodes)
+
+    def record(value):
+        if value is None:
+            return node_count
+        if isinstance(value, int):
+            return value
+        return node_count + 16 + offsets[value[1]]
+
+    tree = b"".join(record(left).to_bytes(3, "big") + record(right).to_bytes(3, "big") for left, right in nodes)
+    metadata = encode({
+        "binary_format_major_version": (5, 2),
+        "binary_format_minor_version": (5, 0),
+        "build_epoch": (9, 1_700_000_000),
+        "database_type": "GeoIP2-Country",
+        "description": {"en": "redirect-service test fixture"},
+        "ip_version": (5, 6),
+        "languages": ["en"],
+        "node_count": (6, node_count),
+        "record_size": (5, RECORD_SIZE),
+    })
+
+    out = os.path.join(os.path.dirname(os.path.abspath(__file__)), "geoip-country-test.mmdb")
+    with open(out, "wb") as f:
+        f.write(tree + bytes(16) + data + b"\xab\xcd\xefMaxMind.com" + metadata)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,69 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use redirect_service::geo::GeoLocator;
+use redirect_service::model::country::{normalize_country, CountryRoutes};
+use redirect_service::model::DomainError;
+use std::net::IpAddr;
+
+// generated by tests/fixtures/make_geoip_fixture.py
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/geoip-country-test.mmdb");
+
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+fn fixture() -> GeoLocator {
+    GeoLocator::default().with_database(FIXTURE).unwrap()
+}
+
+// v4, v6 and v4-mapped v6 addresses resolve from the database
+#[test]
+fn database_lookup_finds_countries() {
+    let geo = fixture();
+
+    assert_eq!(geo.lookup(ip("81.2.69.142")).as_deref(), Some("GB"));
+    assert_eq!(geo.lookup(ip("216.160.83.60")).as_deref(), Some("US"));
+    assert_eq!(geo.lookup(ip("2001:218::1")).as_deref(), Some("JP"));
+    assert_eq!(geo.lookup(ip("::ffff:89.160.20.115")).as_deref(), Some("SE"));
+}
+
+// addresses outside the database, like private ranges, have no country
+#[test]
+fn unknown_addresses_have_no_country() {
+    let geo = fixture();
+
+    assert_eq!(geo.lookup(ip("10.0.0.1")), None);
+    assert_eq!(geo.lookup(ip("::1")), None);
+    assert_eq!(GeoLocator::default().lookup(ip("81.2.69.142")), None);
+}
+
+// a trusted header wins over the database, unless it says it doesn't know
+#[test]
+fn trusted_header_wins_over_database() {
+    let header = HeaderName::from_static("cf-ipcountry");
+    let geo = fixture().with_country_header(header.clone());
+    let with_header = |value: &'static str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header.clone(), HeaderValue::from_static(value));
+        headers
+    };
+
+    assert_eq!(geo.country(&with_header("de"), Some(ip("81.2.69.142"))).as_deref(), Some("DE"));
+    assert_eq!(geo.country(&with_header("XX"), Some(ip("81.2.69.142"))).as_deref(), Some("GB"));
+    assert_eq!(geo.country(&HeaderMap::new(), None), None);
+    // without being told to trust it, the header is ignored
+    assert_eq!(fixture().country(&with_header("DE"), Some(ip("81.2.69.142"))).as_deref(), Some("GB"));
+}
+
+// country codes are two letters, in either case
+#[test]
+fn country_codes_are_normalized_and_validated() {
+    assert_eq!(normalize_country(" fr ").as_deref(), Some("FR"));
+    assert_eq!(normalize_country("T1"), None);
+    assert_eq!(normalize_country("GBR"), None);
+
+    let routes = CountryRoutes::default().with("de", "https://example.de").normalized();
+    assert_eq!(routes.destination("DE"), Some("https://example.de"));
+    assert!(routes.validate().is_ok());
+    let bad_code = CountryRoutes::default().with("Germany", "https://example.de");
+    assert!(matches!(bad_code.validate(), Err(DomainError::MalformedInput(_))));
+}
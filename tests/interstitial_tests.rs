@@ -0,0 +1,63 @@
+use redirect_service::api::pages::interstitial_page;
+use redirect_service::model::interstitial::{Interstitial, Warning, WarningList, MAX_INTERSTITIAL_COUNTDOWN};
+use redirect_service::model::DomainError;
+
+// a listed host matches itself and its subdomains, but not hosts that merely end the same way
+#[test]
+fn warning_list_matches_hosts_and_subdomains() {
+    let list = WarningList::new(["bad.example"]);
+
+    assert!(list.matches("https://bad.example/login"));
+    assert!(list.matches("https://www.BAD.example./login"));
+    assert!(!list.matches("https://notbad.example/"));
+    assert!(!list.matches("https://bad.example.com/"));
+    assert!(!list.matches("not a url"));
+}
+
+// entries may be separated by commas or whitespace, and wildcards and dots are tolerated
+#[test]
+fn warning_list_parses_loose_lists() {
+    let list = WarningList::parse(" *.bad.example, .worse.example\nfree-prizes.test ,,");
+
+    assert!(list.matches("https://a.bad.example/"));
+    assert!(list.matches("http://worse.example/"));
+    assert!(list.matches("https://free-prizes.test/claim"));
+    assert!(WarningList::parse(" , ").is_empty());
+}
+
+// countdowns are capped so a warning page can't stall visitors indefinitely
+#[test]
+fn countdown_is_capped() {
+    assert!(Interstitial::new(0).validate().is_ok());
+    assert!(Interstitial::new(MAX_INTERSTITIAL_COUNTDOWN).validate().is_ok());
+    assert!(matches!(
+        Interstitial::new(MAX_INTERSTITIAL_COUNTDOWN + 1).validate(),
+        Err(DomainError::MalformedInput(_))
+    ));
+}
+
+// an empty object asks for a page that waits for the visitor
+#[test]
+fn countdown_defaults_to_waiting() {
+    let interstitial: Interstitial = serde_json::from_str("{}").unwrap();
+    assert_eq!(interstitial, Interstitial::new(0));
+}
+
+// only web destinations get the button and the automatic refresh; anything else is shown as text
+#[test]
+fn interstitial_links_only_web_destinations() {
+    let page = interstitial_page("https://example.com/docs", Warning::Leaving(Interstitial::new(5)));
+    assert!(page.contains(r#"href="https://example.com/docs""#));
+    assert!(page.contains(r#"content="5;url=https://example.com/docs""#));
+
+    for destination in ["javascript:alert(1)", "data:text/html,<script>alert(1)</script>"] {
+        let page = interstitial_page(destination, Warning::Leaving(Interstitial::new(5)));
+        assert!(!page.contains(r#"class="button""#));
+        assert!(!page.contains("http-equiv"));
+        assert!(!page.contains("<script>"));
+        let flagged = interstitial_page(destination, Warning::Flagged);
+        assert!(!flagged.contains(r#"class="button""#));
+    }
+    let page = interstitial_page("javascript:alert(1)", Warning::Flagged);
+    assert!(page.contains("<code>javascript:alert(1)</code>"));
+}
@@ -0,0 +1,85 @@
+use axum::{body::Body, body::Bytes, extract::State, http::Request, http::Uri, Router};
+use redirect_service::api::handler::redirect_to_url;
+use redirect_service::api::metrics::track_metrics;
+use redirect_service::api::request_context::request_id;
+use redirect_service::metrics::Metrics;
+use redirect_service::repository::metered_url_repository::MeteredUrlRepository;
+use redirect_service::repository::url_repository::PostgresUrlRepository;
+use redirect_service::service::url_service::UrlService;
+use redirect_service::telemetry::{init_tracing, LogFormat};
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+
+type Received = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+// an in-process stand-in for an OTLP/HTTP collector that keeps every request body
+async fn start_collector() -> (String, Received) {
+    let received: Received = Arc::default();
+    let collector = Router::new()
+        .fallback(|State(received): State<Received>, uri: Uri, body: Bytes| async move {
+            received.lock().unwrap().push((uri.path().to_string(), body.to_vec()));
+        })
+        .with_state(received.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, collector).await.unwrap() });
+    (format!("http://{address}"), received)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+fn bodies_for(received: &Received, path: &str) -> Vec<u8> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(p, _)| p == path)
+        .flat_map(|(_, body)| body.clone())
+        .collect()
+}
+
+// a redirect exports handler, service and SQL spans under the caller's trace, plus latency metrics
+#[tokio::test(flavor = "multi_thread")]
+async fn redirect_spans_and_metrics_reach_collector() {
+    dotenv::dotenv().ok();
+    let (endpoint, received) = start_collector().await;
+    let telemetry = init_tracing(LogFormat::Json, Some(&endpoint));
+
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let metrics = Arc::new(Metrics::new());
+    let repo = MeteredUrlRepository::new(PostgresUrlRepository::new(pool), metrics.clone());
+    let app = Router::new()
+        .route("/{short_key}", axum::routing::get(redirect_to_url))
+        .layer(axum::middleware::from_fn_with_state(metrics, track_metrics))
+        .layer(axum::middleware::from_fn(request_id))
+        .with_state(Arc::new(UrlService::new(repo)));
+
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    app.oneshot(
+        Request::builder()
+            .uri("/zzzzzz")
+            .header("traceparent", format!("00-{trace_id}-00f067aa0ba902b7-01"))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    // Shutdown flushes both exporters; they block, so keep them off the async workers
+    tokio::task::spawn_blocking(move || telemetry.shutdown()).await.unwrap();
+
+    let traces = bodies_for(&received, "/v1/traces");
+    assert!(contains(&traces, b"GET /{short_key}"));
+    assert!(contains(&traces, b"visit"));
+    assert!(contains(&traces, b"find_by_short_key"));
+    assert!(contains(&traces, b"redirect-service"));
+    assert!(contains(&traces, &hex::decode(trace_id).unwrap()));
+
+    let metrics = bodies_for(&received, "/v1/metrics");
+    assert!(contains(&metrics, b"http.server.request.duration"));
+    assert!(contains(&metrics, b"db.client.operation.duration"));
+}
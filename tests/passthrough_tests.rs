@@ -0,0 +1,47 @@
+use redirect_service::model::passthrough::{forward, validate_extra_path, QueryPolicy};
+
+const DESTINATION: &str = "https://example.com/landing?utm_source=newsletter";
+
+// links without passthrough options redirect exactly where they point
+#[test]
+fn nothing_to_forward_leaves_the_destination_alone() {
+    assert_eq!(forward(DESTINATION, None, Some("ref=twitter"), None), DESTINATION);
+    assert_eq!(forward(DESTINATION, Some(""), None, Some(QueryPolicy::Keep)), DESTINATION);
+}
+
+// the policy decides who wins when both sides have the same parameter
+#[test]
+fn query_policies_settle_conflicts() {
+    let incoming = Some("utm_source=twitter&ref=abc");
+
+    assert_eq!(
+        forward(DESTINATION, None, incoming, Some(QueryPolicy::Keep)),
+        "https://example.com/landing?utm_source=newsletter&ref=abc"
+    );
+    assert_eq!(
+        forward(DESTINATION, None, incoming, Some(QueryPolicy::Replace)),
+        "https://example.com/landing?utm_source=twitter&ref=abc"
+    );
+    assert_eq!(
+        forward(DESTINATION, None, incoming, Some(QueryPolicy::Append)),
+        "https://example.com/landing?utm_source=newsletter&utm_source=twitter&ref=abc"
+    );
+}
+
+// extra path goes under the destination's path, before its query string
+#[test]
+fn extra_path_is_appended() {
+    assert_eq!(
+        forward(DESTINATION, Some("docs/install"), None, None),
+        "https://example.com/landing/docs/install?utm_source=newsletter"
+    );
+    assert_eq!(forward("https://example.com/", Some("a b"), None, None), "https://example.com/a%20b");
+}
+
+// dot segments could climb out of the destination's path, so they don't resolve
+#[test]
+fn dot_segments_are_rejected() {
+    assert!(validate_extra_path("docs/install").is_ok());
+    assert!(validate_extra_path("../admin").is_err());
+    assert!(validate_extra_path("docs/./x").is_err());
+}
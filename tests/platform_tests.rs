@@ -0,0 +1,57 @@
+use redirect_service::model::platform::{Platform, PlatformRoutes};
+
+const IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 \
+                      (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1";
+const IPAD: &str = "Mozilla/5.0 (iPad; CPU OS 16_6 like Mac OS X) AppleWebKit/605.1.15 \
+                    (KHTML, like Gecko) Version/16.6 Mobile/15E148 Safari/604.1";
+const ANDROID: &str = "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 \
+                       (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36";
+const WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                       (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+const MAC: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4) AppleWebKit/605.1.15 \
+                   (KHTML, like Gecko) Version/17.4 Safari/605.1.15";
+const LINUX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0";
+const WINDOWS_PHONE: &str = "Mozilla/5.0 (Windows Phone 10.0; Android 6.0.1; Microsoft; Lumia 950) \
+                             AppleWebKit/537.36 (KHTML, like Gecko) Chrome/52.0 Mobile Safari/537.36 Edge/15.15063";
+const BINGBOT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) \
+                       Chrome/116.0.1938.76 Safari/537.36; compatible; bingbot/2.0";
+
+// iPhones and iPads are iOS
+#[test]
+fn apple_mobile_devices_are_ios() {
+    assert_eq!(Platform::from_user_agent(IPHONE), Some(Platform::Ios));
+    assert_eq!(Platform::from_user_agent(IPAD), Some(Platform::Ios));
+}
+
+// android phones are android even though their UA says Linux
+#[test]
+fn android_is_not_desktop_linux() {
+    assert_eq!(Platform::from_user_agent(ANDROID), Some(Platform::Android));
+}
+
+// windows, mac and linux browsers are desktop
+#[test]
+fn desktop_browsers_are_desktop() {
+    for user_agent in [WINDOWS, MAC, LINUX] {
+        assert_eq!(Platform::from_user_agent(user_agent), Some(Platform::Desktop));
+    }
+}
+
+// anything we can't place gets the default destination
+#[test]
+fn bots_clients_and_other_phones_are_unknown() {
+    for user_agent in [WINDOWS_PHONE, BINGBOT, "curl/8.5.0", ""] {
+        assert_eq!(Platform::from_user_agent(user_agent), None);
+    }
+}
+
+// platforms without a route have no destination of their own
+#[test]
+fn routes_only_cover_configured_platforms() {
+    let routes = PlatformRoutes::default().with(Platform::Ios, "https://apps.apple.com/app/id1");
+
+    assert_eq!(routes.destination(Platform::Ios), Some("https://apps.apple.com/app/id1"));
+    assert_eq!(routes.destination(Platform::Android), None);
+    assert!(!routes.is_empty());
+    assert!(PlatformRoutes::default().is_empty());
+}
@@ -0,0 +1,58 @@
+use redirect_service::model::qr::{qr_etag, render_qr, MAX_QR_MARGIN, MAX_QR_SIZE, MIN_QR_SIZE};
+use redirect_service::model::{DomainError, QrColor, QrFormat, QrOptions};
+
+// PNG is only chosen when the client ranks it above SVG
+#[test]
+fn accept_header_picks_format() {
+    assert_eq!(QrFormat::from_accept("image/png"), QrFormat::Png);
+    assert_eq!(QrFormat::from_accept("image/svg+xml;q=0.5, image/png"), QrFormat::Png);
+    assert_eq!(QrFormat::from_accept("image/png, image/svg+xml"), QrFormat::Svg);
+    assert_eq!(QrFormat::from_accept("*/*"), QrFormat::Svg);
+    assert_eq!(QrFormat::from_name("PNG"), Some(QrFormat::Png));
+    assert_eq!(QrFormat::from_name("gif"), None);
+}
+
+// colours are hex with an optional alpha channel
+#[test]
+fn colours_parse_from_hex() {
+    assert_eq!(QrColor::parse("#000000").unwrap(), QrColor::BLACK);
+    assert_eq!(QrColor::parse("FFFFFFff").unwrap(), QrColor::WHITE);
+    for bad in ["fff", "#12345", "zzzzzz", "#1234567"] {
+        assert!(matches!(QrColor::parse(bad), Err(DomainError::MalformedInput(_))), "{bad}");
+    }
+}
+
+// sizes, margins and error-correction levels are checked before drawing
+#[test]
+fn options_are_validated() {
+    assert!(QrOptions::default().validate().is_ok());
+    for options in [
+        QrOptions { size: MIN_QR_SIZE - 1, ..QrOptions::default() },
+        QrOptions { size: MAX_QR_SIZE + 1, ..QrOptions::default() },
+        QrOptions { margin: MAX_QR_MARGIN + 1, ..QrOptions::default() },
+    ] {
+        assert!(matches!(options.validate(), Err(DomainError::MalformedInput(_))));
+    }
+    assert!(QrOptions::parse_ec_level("h").is_ok());
+    assert!(QrOptions::parse_ec_level("X").is_err());
+}
+
+// the same link and options always render the same bytes under the same tag
+#[test]
+fn rendering_is_deterministic() {
+    let options = QrOptions::default();
+    let url = "https://sho.rt/abc1234";
+
+    let png = render_qr(url, &options, QrFormat::Png).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(png, render_qr(url, &options, QrFormat::Png).unwrap());
+    let svg = String::from_utf8(render_qr(url, &options, QrFormat::Svg).unwrap()).unwrap();
+    assert!(svg.contains(r#"width="256""#));
+    assert!(svg.contains(r##"fill="#000000""##));
+
+    let tag = qr_etag(url, &options, QrFormat::Svg);
+    assert_eq!(tag, qr_etag(url, &options, QrFormat::Svg));
+    assert_ne!(tag, qr_etag(url, &options, QrFormat::Png));
+    assert_ne!(tag, qr_etag(url, &QrOptions { margin: 2, ..options }, QrFormat::Svg));
+    assert_ne!(tag, qr_etag("https://sho.rt/other00", &options, QrFormat::Svg));
+}
@@ -1,6 +1,25 @@
-use redirect_service::model::{ShortKey, Url};
+use chrono::{NaiveDate, TimeZone, Utc};
+use redirect_service::model::{ActiveWindow, AuditAction, AuditContext, AuditFilter, ClickLimit, CountryRoutes, Domain, DomainError, DomainFallbacks, Interstitial, Platform, PlatformRoutes, QuotaKind, QueryPolicy, ShortKey, Url, Utm, UtmTemplate, Variants};
+use futures::TryStreamExt;
+use redirect_service::model::api_key::hash_secret;
+use redirect_service::model::domain::DEFAULT_DOMAIN;
+use redirect_service::model::url::DEFAULT_WORKSPACE;
+use redirect_service::model::import::MAX_IMPORT_CHUNK_SIZE;
+use redirect_service::model::{ClickEvent, DataFormat, NewLink, QuotaLimits, Visit};
+use redirect_service::repository::api_key_repository::PostgresApiKeyRepository;
+use redirect_service::repository::audit_repository::{AuditRepository, PostgresAuditRepository};
+use redirect_service::repository::buffered_click_repository::BufferedClickRepository;
+use redirect_service::repository::utm_template_repository::{PostgresUtmTemplateRepository, UtmTemplateRepository};
+use redirect_service::repository::click_repository::{ClickRepository, PostgresClickRepository};
+use redirect_service::repository::domain_repository::{DomainRepository, PostgresDomainRepository};
 use redirect_service::repository::url_repository::{PostgresUrlRepository, UrlRepository};
+use redirect_service::repository::usage_repository::{PostgresUsageRepository, UsageRepository};
+use redirect_service::service::api_key_service::ApiKeyService;
+use redirect_service::service::url_service::UrlService;
+use redirect_service::shutdown::ShutdownCoordinator;
 use sqlx::PgPool;
+use std::collections::HashSet;
+use std::time::Duration;
 
 //connects to the test db and returns a great repo
 async fn setup() -> PostgresUrlRepository {
@@ -30,10 +49,12 @@ async fn insert_and_find_by_original_url() {
         "https://leetcode.com/problems/add-two-numbers/description/".to_string(),
     );
 
-    repo.insert(&url).await.unwrap();
+    repo.insert(&url, &AuditContext::system()).await.unwrap();
 
     let found = repo
         .find_by_original_url(
+            DEFAULT_DOMAIN,
+            DEFAULT_WORKSPACE,
             "https://leetcode.com/problems/add-two-numbers/description/",
         )
         .await
@@ -56,9 +77,9 @@ async fn insert_and_find_by_short_key() {
         "https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html".to_string(),
     );
 
-    repo.insert(&url).await.unwrap();
+    repo.insert(&url, &AuditContext::system()).await.unwrap();
 
-    let found = repo.find_by_short_key(&short_key).await.unwrap();
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &short_key).await.unwrap();
 
     assert!(found.is_some());
     assert_eq!(
@@ -73,7 +94,7 @@ async fn find_by_original_url_returns_none_when_not_found() {
     let repo = setup().await;
 
     let found = repo
-        .find_by_original_url("https://this-url-was-never-inserted.com")
+        .find_by_original_url(DEFAULT_DOMAIN, DEFAULT_WORKSPACE, "https://this-url-was-never-inserted.com")
         .await
         .unwrap();
 
@@ -86,7 +107,7 @@ async fn find_by_short_key_returns_none_when_not_found() {
     let repo = setup().await;
     let key = ShortKey::new("zzzzzz".to_string()).unwrap();
 
-    let found = repo.find_by_short_key(&key).await.unwrap();
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &key).await.unwrap();
 
     assert!(found.is_none());
 }
@@ -106,8 +127,8 @@ async fn inserting_duplicate_original_url_returns_error() {
         "https://leetcode.com/problems/two-sum/description/".to_string(),
     );
 
-    repo.insert(&url_one).await.unwrap();
-    let result = repo.insert(&url_two).await;
+    repo.insert(&url_one, &AuditContext::system()).await.unwrap();
+    let result = repo.insert(&url_two, &AuditContext::system()).await;
 
     assert!(result.is_err());
 }
@@ -126,13 +147,871 @@ async fn insert_multiple_urls_and_retrieve_each() {
     for (key, url) in &entries {
         let short_key = ShortKey::new(key.to_string()).unwrap();
         let url = Url::new(short_key, url.to_string());
-        repo.insert(&url).await.unwrap();
+        repo.insert(&url, &AuditContext::system()).await.unwrap();
     }
 
     for (key, original_url) in &entries {
         let short_key = ShortKey::new(key.to_string()).unwrap();
-        let found = repo.find_by_short_key(&short_key).await.unwrap();
+        let found = repo.find_by_short_key(DEFAULT_DOMAIN, &short_key).await.unwrap();
         assert!(found.is_some());
         assert_eq!(&found.unwrap().original_url, original_url);
     }
-}
\ No newline at end of file
+}
+//connects to the test db and returns a clean usage repo
+async fn setup_usage() -> PostgresUsageRepository {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL")
+        .expect("DATABASE_URL must be set in .env");
+
+    let pool = PgPool::connect(&database_url).await
+        .expect("Failed to connect to database");
+
+    sqlx::query("DELETE FROM usage")
+        .execute(&pool)
+        .await
+        .expect("Failed to clean usage table");
+
+    PostgresUsageRepository::new(pool)
+}
+
+fn period() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 2, 1).unwrap()
+}
+
+// usage for a workspace that never did anything is all zeros
+#[tokio::test]
+async fn usage_find_returns_zero_when_nothing_counted() {
+    let repo = setup_usage().await;
+
+    let usage = repo.find("acme", period()).await.unwrap();
+
+    assert_eq!(usage.links_created, 0);
+    assert_eq!(usage.clicks_tracked, 0);
+}
+
+// increments stop once the limit is reached
+#[tokio::test]
+async fn usage_try_increment_refuses_past_limit() {
+    let repo = setup_usage().await;
+
+    assert!(repo.try_increment("acme", period(), QuotaKind::Links, Some(2)).await.unwrap());
+    assert!(repo.try_increment("acme", period(), QuotaKind::Links, Some(2)).await.unwrap());
+    assert!(!repo.try_increment("acme", period(), QuotaKind::Links, Some(2)).await.unwrap());
+    assert!(repo.try_increment("acme", period(), QuotaKind::Clicks, None).await.unwrap());
+
+    let usage = repo.find("acme", period()).await.unwrap();
+    assert_eq!(usage.links_created, 2);
+    assert_eq!(usage.clicks_tracked, 1);
+}
+
+//connects to the test db and returns url + audit repos sharing a clean pool
+async fn setup_audited() -> (PostgresUrlRepository, PostgresAuditRepository) {
+    dotenv::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL")
+        .expect("DATABASE_URL must be set in .env");
+
+    let pool = PgPool::connect(&database_url).await
+        .expect("Failed to connect to database");
+
+    sqlx::query("DELETE FROM urls")
+        .execute(&pool)
+        .await
+        .expect("Failed to clean urls table");
+
+    // audit_events rejects row deletes, truncate bypasses the trigger
+    sqlx::query("TRUNCATE audit_events")
+        .execute(&pool)
+        .await
+        .expect("Failed to clean audit_events table");
+
+    (PostgresUrlRepository::new(pool.clone()), PostgresAuditRepository::new(pool))
+}
+
+fn audit_filter_for(short_key: &str) -> AuditFilter {
+    AuditFilter {
+        short_key: Some(short_key.to_string()),
+        limit: 50,
+        ..AuditFilter::default()
+    }
+}
+
+// every mutation leaves an audit event with before/after destinations
+#[tokio::test]
+async fn mutations_write_audit_events() {
+    let (repo, audit) = setup_audited().await;
+    let ctx = AuditContext::new("alice", Some("req-123".to_string()));
+    let short_key = ShortKey::new("aUd1t9".to_string()).unwrap();
+    let url = Url::new(short_key.clone(), "https://leetcode.com/problems/two-sum/".to_string());
+
+    repo.insert(&url, &ctx).await.unwrap();
+    repo.retarget(DEFAULT_DOMAIN, &short_key, "https://leetcode.com/problems/3sum/", &ctx).await.unwrap();
+    assert!(repo.delete(DEFAULT_DOMAIN, &short_key, Utc::now(), &ctx).await.unwrap());
+    repo.restore(DEFAULT_DOMAIN, &short_key, &ctx).await.unwrap();
+
+    let records = audit.list(&audit_filter_for("aUd1t9")).await.unwrap();
+    let actions: Vec<AuditAction> = records.iter().map(|r| r.event.action).collect();
+
+    assert_eq!(
+        actions,
+        vec![AuditAction::Restore, AuditAction::Delete, AuditAction::Retarget, AuditAction::Create]
+    );
+    let retarget = &records[2].event;
+    assert_eq!(retarget.actor, "alice");
+    assert_eq!(retarget.request_id.as_deref(), Some("req-123"));
+    assert_eq!(retarget.before_url.as_deref(), Some("https://leetcode.com/problems/two-sum/"));
+    assert_eq!(retarget.after_url.as_deref(), Some("https://leetcode.com/problems/3sum/"));
+}
+
+// audit events record the link's domain, so the same key on two domains keeps separate history
+#[tokio::test]
+async fn audit_events_are_kept_per_domain() {
+    let (repo, audit) = setup_audited().await;
+    PostgresDomainRepository::new(PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap())
+        .insert(&Domain::new("acme.link", None))
+        .await
+        .unwrap();
+    let ctx = AuditContext::system();
+    let key = ShortKey::new("aUdDom".to_string()).unwrap();
+    repo.insert(&Url::new(key.clone(), "https://example.com/home".to_string()), &ctx).await.unwrap();
+    repo.insert(&Url::new(key.clone(), "https://example.com/brand".to_string()).with_domain("acme.link"), &ctx)
+        .await
+        .unwrap();
+    repo.retarget("acme.link", &key, "https://acme.io/", &ctx).await.unwrap();
+
+    let filter = AuditFilter { domain: Some("acme.link".to_string()), ..audit_filter_for("aUdDom") };
+    let branded = audit.list(&filter).await.unwrap();
+    let all = audit.list(&audit_filter_for("aUdDom")).await.unwrap();
+
+    assert_eq!(branded.len(), 2);
+    assert!(branded.iter().all(|record| record.event.domain.as_deref() == Some("acme.link")));
+    assert_eq!(all.len(), 3);
+}
+
+// a failed mutation leaves no audit event behind
+#[tokio::test]
+async fn failed_insert_does_not_write_audit_event() {
+    let (repo, audit) = setup_audited().await;
+    let ctx = AuditContext::system();
+    let first = Url::new(ShortKey::new("dup001".to_string()).unwrap(), "https://example.com/dup".to_string());
+    let second = Url::new(ShortKey::new("dup002".to_string()).unwrap(), "https://example.com/dup".to_string());
+
+    repo.insert(&first, &ctx).await.unwrap();
+    assert!(repo.insert(&second, &ctx).await.is_err());
+
+    let records = audit.list(&audit_filter_for("dup002")).await.unwrap();
+    assert!(records.is_empty());
+}
+
+// audit rows cannot be rewritten
+#[tokio::test]
+async fn audit_events_are_append_only() {
+    let (repo, _) = setup_audited().await;
+    let url = Url::new(ShortKey::new("imm001".to_string()).unwrap(), "https://example.com/imm".to_string());
+    repo.insert(&url, &AuditContext::system()).await.unwrap();
+
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    let result = sqlx::query("UPDATE audit_events SET actor = 'mallory'")
+        .execute(&pool)
+        .await;
+
+    assert!(result.is_err());
+}
+
+// audit listing pages backwards from the cursor
+#[tokio::test]
+async fn audit_list_paginates_with_cursor() {
+    let (repo, audit) = setup_audited().await;
+    let ctx = AuditContext::system();
+    let short_key = ShortKey::new("pAg3d1".to_string()).unwrap();
+    let url = Url::new(short_key.clone(), "https://example.com/v0".to_string());
+    repo.insert(&url, &ctx).await.unwrap();
+    for i in 1..=4 {
+        repo.retarget(DEFAULT_DOMAIN, &short_key, &format!("https://example.com/v{i}"), &ctx).await.unwrap();
+    }
+
+    let mut filter = audit_filter_for("pAg3d1");
+    filter.limit = 3;
+    let first_page = audit.list(&filter).await.unwrap();
+    filter.before_id = first_page.last().map(|r| r.id);
+    let second_page = audit.list(&filter).await.unwrap();
+
+    assert_eq!(first_page.len(), 3);
+    assert_eq!(second_page.len(), 2);
+    assert_eq!(second_page.last().unwrap().event.action, AuditAction::Create);
+}
+
+// bulk insert then bulk lookup returns every link
+#[tokio::test]
+async fn insert_many_and_find_by_original_urls() {
+    let repo = setup().await;
+    let urls: Vec<Url> = [("bLk001", "https://example.com/a"), ("bLk002", "https://example.com/b")]
+        .iter()
+        .map(|(key, url)| Url::new(ShortKey::new(key.to_string()).unwrap(), url.to_string()))
+        .collect();
+
+    let inserted = repo.insert_many(&urls, &AuditContext::system()).await.unwrap();
+    let found = repo
+        .find_by_original_urls(
+            DEFAULT_DOMAIN,
+            DEFAULT_WORKSPACE,
+            &["https://example.com/a".to_string(), "https://example.com/b".to_string()],
+        )
+        .await
+        .unwrap();
+    let taken = repo
+        .existing_short_keys(DEFAULT_DOMAIN, &["bLk001".to_string(), "free99".to_string()])
+        .await
+        .unwrap();
+
+    assert_eq!(inserted.len(), 2);
+    assert_eq!(found.len(), 2);
+    assert_eq!(taken, vec!["bLk001".to_string()]);
+}
+
+// bulk insert skips destinations that already have a live link
+#[tokio::test]
+async fn insert_many_skips_already_shortened_destinations() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let first = Url::new(ShortKey::new("rAce01".to_string()).unwrap(), "https://example.com/race".to_string());
+    repo.insert(&first, &ctx).await.unwrap();
+
+    let batch = vec![
+        Url::new(ShortKey::new("rAce02".to_string()).unwrap(), "https://example.com/race".to_string()),
+        Url::new(ShortKey::new("rAce03".to_string()).unwrap(), "https://example.com/fresh".to_string()),
+    ];
+    let inserted = repo.insert_many(&batch, &ctx).await.unwrap();
+
+    assert_eq!(inserted.len(), 1);
+    assert_eq!(inserted[0].short_key.as_str(), "rAce03");
+}
+
+// a destination is only taken within its workspace, so another workspace gets its own link
+#[tokio::test]
+async fn live_destinations_are_unique_per_workspace() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let first = Url::new(ShortKey::new("wSp001".to_string()).unwrap(), "https://example.com/shared".to_string());
+    repo.insert(&first, &ctx).await.unwrap();
+
+    let other = Url::new(ShortKey::new("wSp002".to_string()).unwrap(), "https://example.com/shared".to_string())
+        .with_workspace("acme");
+    let inserted = repo.insert_many(&[other], &ctx).await.unwrap();
+    let found = repo
+        .find_by_original_url(DEFAULT_DOMAIN, "acme", "https://example.com/shared")
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(inserted.len(), 1);
+    assert_eq!(found.short_key.as_str(), "wSp002");
+}
+
+// retargeting onto a destination another live link uses is a conflict, not a database failure
+#[tokio::test]
+async fn retarget_onto_taken_destination_conflicts() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let taken = Url::new(ShortKey::new("rtg001".to_string()).unwrap(), "https://example.com/taken".to_string());
+    let moving = Url::new(ShortKey::new("rtg002".to_string()).unwrap(), "https://example.com/moving".to_string());
+    repo.insert_many(&[taken, moving], &ctx).await.unwrap();
+
+    let result = repo.retarget(DEFAULT_DOMAIN, &ShortKey::new("rtg002".to_string()).unwrap(), "https://example.com/taken", &ctx).await;
+
+    assert!(matches!(result, Err(DomainError::DestinationTaken)));
+}
+
+// restoring a link whose destination got a new live link meanwhile is a conflict
+#[tokio::test]
+async fn restore_onto_taken_destination_conflicts() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let key = ShortKey::new("rst001".to_string()).unwrap();
+    repo.insert(&Url::new(key.clone(), "https://example.com/reused".to_string()), &ctx).await.unwrap();
+    repo.delete(DEFAULT_DOMAIN, &key, Utc::now(), &ctx).await.unwrap();
+    repo.insert(&Url::new(ShortKey::new("rst002".to_string()).unwrap(), "https://example.com/reused".to_string()), &ctx)
+        .await
+        .unwrap();
+
+    let result = repo.restore(DEFAULT_DOMAIN, &key, &ctx).await;
+
+    assert!(matches!(result, Err(DomainError::DestinationTaken)));
+}
+
+// bulk usage increments grant only what is left of the limit
+#[tokio::test]
+async fn usage_increment_up_to_grants_remaining_quota() {
+    let repo = setup_usage().await;
+
+    let first = repo.increment_up_to("acme", period(), QuotaKind::Links, 3, Some(5)).await.unwrap();
+    let second = repo.increment_up_to("acme", period(), QuotaKind::Links, 3, Some(5)).await.unwrap();
+    let third = repo.increment_up_to("acme", period(), QuotaKind::Links, 3, Some(5)).await.unwrap();
+
+    assert_eq!((first, second, third), (3, 2, 0));
+    assert_eq!(repo.find("acme", period()).await.unwrap().links_created, 5);
+}
+
+// import keeps the caller's created_at and skips colliding keys
+#[tokio::test]
+async fn import_many_preserves_timestamps_and_skips_conflicts() {
+    let (repo, audit) = setup_audited().await;
+    let ctx = AuditContext::system();
+    let existing = Url::new(ShortKey::new("iMp001".to_string()).unwrap(), "https://example.com/old".to_string());
+    repo.insert(&existing, &ctx).await.unwrap();
+
+    let mut legacy = Url::new(ShortKey::new("iMp002".to_string()).unwrap(), "https://example.com/legacy".to_string());
+    legacy.created_at = chrono::DateTime::parse_from_rfc3339("2018-01-02T03:04:05Z").unwrap().into();
+    let clash = Url::new(ShortKey::new("iMp001".to_string()).unwrap(), "https://example.com/clash".to_string());
+
+    let inserted = repo.import_many(&[legacy, clash], &ctx).await.unwrap();
+    let found = repo
+        .find_by_short_key(DEFAULT_DOMAIN, &ShortKey::new("iMp002".to_string()).unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let events = audit.list(&audit_filter_for("iMp002")).await.unwrap();
+
+    assert_eq!(inserted, vec![(DEFAULT_DOMAIN.to_string(), "iMp002".to_string())]);
+    assert_eq!(found.created_at.to_rfc3339(), "2018-01-02T03:04:05+00:00");
+    assert_eq!(events[0].event.action, AuditAction::Import);
+}
+
+// streaming export walks the cursor across more than one fetch batch
+#[tokio::test]
+async fn stream_live_returns_every_live_link_in_workspace() {
+    let repo = setup().await;
+    let urls: Vec<Url> = (0..1205)
+        .map(|i| {
+            Url::new(ShortKey::new(format!("sTr{i:04}")).unwrap(), format!("https://example.com/s/{i}"))
+                .with_workspace(if i % 5 == 0 { "globex" } else { "acme" })
+        })
+        .collect();
+    repo.insert_many(&urls, &AuditContext::system()).await.unwrap();
+
+    let acme: Vec<Url> = repo.stream_live(Some("acme")).try_collect().await.unwrap();
+    let all: Vec<Url> = repo.stream_live(None).try_collect().await.unwrap();
+
+    assert_eq!(acme.len(), 964);
+    assert_eq!(all.len(), 1205);
+}
+
+// recorded clicks come back from the click stream
+#[tokio::test]
+async fn click_record_and_stream() {
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM click_events").execute(&pool).await.unwrap();
+    let repo = PostgresClickRepository::new(pool);
+
+    repo.record(&ClickEvent::new(DEFAULT_DOMAIN, "cLk001", "acme").with_country(Some("SE".to_string()))).await.unwrap();
+    repo.record(&ClickEvent::new(DEFAULT_DOMAIN, "cLk002", "globex")).await.unwrap();
+    repo.record_many(&[ClickEvent::new(DEFAULT_DOMAIN, "cLk003", "acme")]).await.unwrap();
+
+    let acme: Vec<ClickEvent> = repo.stream(Some("acme")).try_collect().await.unwrap();
+
+    assert_eq!(acme.len(), 2);
+    assert_eq!(acme[0].short_key, "cLk001");
+    assert_eq!(acme[0].country.as_deref(), Some("SE"));
+    assert_eq!(acme[1].country, None);
+}
+
+// clicks still queued when shutdown begins are written before the writer exits
+#[tokio::test]
+async fn buffered_clicks_are_flushed_on_shutdown() {
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM click_events").execute(&pool).await.unwrap();
+    let mut workers = ShutdownCoordinator::new();
+    let (repo, writer) = BufferedClickRepository::spawn(PostgresClickRepository::new(pool.clone()), workers.token());
+    workers.register("click writer", writer);
+
+    for i in 0..750 {
+        repo.record(&ClickEvent::new(DEFAULT_DOMAIN, &format!("bUf{i:03}"), "acme")).await.unwrap();
+    }
+    assert!(workers.shutdown(Duration::from_secs(5)).await);
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM click_events").fetch_one(&pool).await.unwrap();
+    assert_eq!(stored, 750);
+    // the writer is gone; late clicks are dropped without failing the caller
+    assert!(repo.record(&ClickEvent::new(DEFAULT_DOMAIN, "bUfLate", "acme")).await.is_ok());
+}
+
+// a worker that ignores cancellation is aborted at the deadline
+#[tokio::test]
+async fn shutdown_aborts_workers_past_the_deadline() {
+    let mut workers = ShutdownCoordinator::new();
+    workers.register("stuck", tokio::spawn(std::future::pending()));
+
+    assert!(!workers.shutdown(Duration::from_millis(50)).await);
+}
+
+async fn setup_domains() -> (PostgresUrlRepository, PostgresDomainRepository) {
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM urls").execute(&pool).await.unwrap();
+    sqlx::query("DELETE FROM domains WHERE host <> $1").bind(DEFAULT_DOMAIN).execute(&pool).await.unwrap();
+    (PostgresUrlRepository::new(pool.clone()), PostgresDomainRepository::new(pool))
+}
+
+// a host can be registered once; the default domain is seeded by the migration
+#[tokio::test]
+async fn domain_insert_find_and_list() {
+    let (_, domains) = setup_domains().await;
+
+    assert!(domains.insert(&Domain::new("go.acme.io", Some("acme".to_string()))).await.unwrap());
+    assert!(!domains.insert(&Domain::new("go.acme.io", None)).await.unwrap());
+
+    let found = domains.find("go.acme.io").await.unwrap().unwrap();
+    assert_eq!(found.workspace_id.as_deref(), Some("acme"));
+    let hosts: Vec<String> = domains.list().await.unwrap().into_iter().map(|d| d.host).collect();
+    assert_eq!(hosts, vec!["go.acme.io".to_string(), DEFAULT_DOMAIN.to_string()]);
+}
+
+// the password hash is stored with the link by both insert paths
+#[tokio::test]
+async fn password_hash_is_persisted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let hash = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNo".to_string();
+    let single = Url::new(ShortKey::new("pwd001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_password_hash(Some(hash.clone()));
+    let batch = vec![
+        Url::new(ShortKey::new("pwd002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_password_hash(Some(hash.clone())),
+        Url::new(ShortKey::new("pwd003".to_string()).unwrap(), "https://example.com/three".to_string()),
+    ];
+
+    repo.insert(&single, &ctx).await.unwrap();
+    let inserted = repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!(found.password_hash.as_deref(), Some(hash.as_str()));
+    let protected: Vec<&str> = inserted.iter().filter(|u| u.is_protected()).map(|u| u.short_key.as_str()).collect();
+    assert_eq!(protected, vec!["pwd002"]);
+}
+
+// fallbacks are replaced as a whole and only for registered hosts
+#[tokio::test]
+async fn domain_fallbacks_round_trip() {
+    let (_, domains) = setup_domains().await;
+    domains.insert(&Domain::new("go.acme.io", None)).await.unwrap();
+    let fallbacks = DomainFallbacks {
+        root_url: Some("https://acme.io/".to_string()),
+        not_found_url: None,
+        not_found_page: Some("<h1>Nothing here</h1>".to_string()),
+    };
+
+    let updated = domains.set_fallbacks("go.acme.io", &fallbacks).await.unwrap().unwrap();
+    assert_eq!(updated.fallbacks.root_url.as_deref(), Some("https://acme.io/"));
+    let found = domains.find("go.acme.io").await.unwrap().unwrap();
+    assert_eq!(found.fallbacks.not_found_page.as_deref(), Some("<h1>Nothing here</h1>"));
+
+    let cleared = domains.set_fallbacks("go.acme.io", &DomainFallbacks::default()).await.unwrap().unwrap();
+    assert!(cleared.fallbacks.root_url.is_none() && cleared.fallbacks.not_found_page.is_none());
+    assert!(domains.set_fallbacks("nope.io", &fallbacks).await.unwrap().is_none());
+}
+
+// a domain's warning page is set, read back and cleared
+#[tokio::test]
+async fn domain_interstitial_round_trip() {
+    let (_, domains) = setup_domains().await;
+    domains.insert(&Domain::new("go.acme.io", None)).await.unwrap();
+
+    let updated = domains.set_interstitial("go.acme.io", Some(Interstitial::new(5))).await.unwrap().unwrap();
+    assert_eq!(updated.interstitial, Some(Interstitial::new(5)));
+    let found = domains.find("go.acme.io").await.unwrap().unwrap();
+    assert_eq!(found.interstitial, Some(Interstitial::new(5)));
+
+    let cleared = domains.set_interstitial("go.acme.io", None).await.unwrap().unwrap();
+    assert_eq!(cleared.interstitial, None);
+    assert!(domains.set_interstitial("nope.io", None).await.unwrap().is_none());
+}
+
+// the same key and destination can live on two domains independently
+#[tokio::test]
+async fn same_short_key_on_two_domains() {
+    let (repo, domains) = setup_domains().await;
+    let ctx = AuditContext::system();
+    domains.insert(&Domain::new("acme.link", None)).await.unwrap();
+    let key = ShortKey::new("dOm001".to_string()).unwrap();
+
+    let home = Url::new(key.clone(), "https://example.com/".to_string());
+    let branded = Url::new(key.clone(), "https://example.com/".to_string()).with_domain("acme.link");
+    repo.insert(&home, &ctx).await.unwrap();
+    repo.insert(&branded, &ctx).await.unwrap();
+    repo.retarget("acme.link", &key, "https://acme.io/", &ctx).await.unwrap();
+
+    let on_default = repo.find_by_short_key(DEFAULT_DOMAIN, &key).await.unwrap().unwrap();
+    let on_acme = repo.find_by_short_key("acme.link", &key).await.unwrap().unwrap();
+    assert_eq!(on_default.original_url, "https://example.com/");
+    assert_eq!(on_acme.original_url, "https://acme.io/");
+    assert_eq!(on_acme.domain, "acme.link");
+    assert!(repo.find_by_short_key("elsewhere.io", &key).await.unwrap().is_none());
+}
+
+// minted keys store only the hash of the secret and can be revoked once
+#[tokio::test]
+async fn mint_list_and_revoke_api_key() {
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM api_keys").execute(&pool).await.unwrap();
+    let keys = ApiKeyService::new(PostgresApiKeyRepository::new(pool.clone()));
+
+    let minted = keys.mint("acme", "ci").await.unwrap();
+    keys.mint("globex", "ci").await.unwrap();
+
+    let stored: String = sqlx::query_scalar("SELECT secret_hash FROM api_keys WHERE id = $1")
+        .bind(minted.key.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, hash_secret(&minted.secret));
+    assert!(minted.secret.starts_with(&minted.key.prefix));
+
+    let acme = keys.list(Some("acme")).await.unwrap();
+    assert_eq!(acme.len(), 1);
+    assert_eq!(acme[0].name, "ci");
+
+    let ctx = AuditContext::new("alice", Some("req-7".to_string()));
+    assert!(keys.authenticate(&minted.secret).await.unwrap().is_some());
+    assert!(keys.revoke(&ctx, minted.key.id).await.unwrap().is_revoked());
+    assert!(matches!(keys.revoke(&ctx, minted.key.id).await, Err(DomainError::ApiKeyNotFound)));
+    assert!(keys.authenticate(&minted.secret).await.unwrap().is_none());
+
+    let (actor, action): (String, String) =
+        sqlx::query_as("SELECT actor, action FROM audit_events WHERE short_key = $1 AND request_id = 'req-7'")
+            .bind(&minted.key.prefix)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((actor.as_str(), action.as_str()), ("alice", "revoke_key"));
+}
+
+// concurrent visits never spend more clicks than the limit allows
+#[tokio::test]
+async fn take_click_never_exceeds_limit() {
+    let repo = std::sync::Arc::new(setup().await);
+    let key = ShortKey::new("lim001".to_string()).unwrap();
+    let url = Url::new(key.clone(), "https://example.com/limited".to_string())
+        .with_click_limit(Some(ClickLimit::new(5, None)));
+    repo.insert(&url, &AuditContext::system()).await.unwrap();
+
+    let takes: Vec<_> = (0..20)
+        .map(|_| {
+            let repo = repo.clone();
+            let key = key.clone();
+            tokio::spawn(async move { repo.take_click(DEFAULT_DOMAIN, &key).await.unwrap() })
+        })
+        .collect();
+    let mut taken = 0;
+    for take in takes {
+        if take.await.unwrap() {
+            taken += 1;
+        }
+    }
+
+    assert_eq!(taken, 5);
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &key).await.unwrap().unwrap();
+    assert!(found.is_exhausted());
+    assert_eq!(found.click_limit.unwrap().max_clicks, 5);
+}
+
+// visits that lose the race for a link's last click don't cost click quota
+#[tokio::test]
+async fn losing_visits_do_not_spend_click_quota() {
+    let repo = setup().await;
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM usage WHERE workspace_id = 'raceq'").execute(&pool).await.unwrap();
+    let key = ShortKey::new("rAce01".to_string()).unwrap();
+    let url = Url::new(key.clone(), "https://example.com/once".to_string())
+        .with_workspace("raceq")
+        .with_click_limit(Some(ClickLimit::new(1, None)));
+    repo.insert(&url, &AuditContext::system()).await.unwrap();
+    let limits = QuotaLimits { max_links: None, max_clicks: Some(100) };
+    let service = std::sync::Arc::new(UrlService::new(repo).with_quota(PostgresUsageRepository::new(pool), limits));
+
+    let visits: Vec<_> = (0..10)
+        .map(|_| {
+            let service = service.clone();
+            let key = key.clone();
+            tokio::spawn(async move { service.visit(&Visit::to_host(None), &key).await.is_ok() })
+        })
+        .collect();
+    let mut served = 0;
+    for visit in visits {
+        if visit.await.unwrap() {
+            served += 1;
+        }
+    }
+
+    assert_eq!(served, 1);
+    assert_eq!(service.usage("raceq").await.unwrap().0.clicks_tracked, 1);
+}
+
+// an oversized import chunk size is cut down to the ceiling
+#[tokio::test]
+async fn import_chunk_size_is_capped() {
+    let repo = setup().await;
+    let service = UrlService::new(repo);
+    let input: String = (0..=MAX_IMPORT_CHUNK_SIZE)
+        .map(|i| format!("c{i:05},https://example.com/{i}\n"))
+        .collect();
+
+    let report = service
+        .import_links(&AuditContext::system(), input.as_bytes(), DataFormat::Csv, usize::MAX)
+        .await
+        .unwrap();
+
+    assert_eq!(report.imported, MAX_IMPORT_CHUNK_SIZE + 1);
+    assert_eq!(report.chunks_committed, 2);
+}
+
+// concurrent shortens of one destination all get the one link instead of failing on the unique index
+#[tokio::test]
+async fn concurrent_shortens_share_one_link() {
+    let repo = setup().await;
+    let service = std::sync::Arc::new(UrlService::new(repo));
+    let link = NewLink::new("https://example.com/stampede").in_workspace("stampede");
+
+    let shortens: Vec<_> = (0..10)
+        .map(|_| {
+            let service = service.clone();
+            let link = link.clone();
+            tokio::spawn(async move { service.shorten_link(&AuditContext::system(), &link).await })
+        })
+        .collect();
+    let mut keys = HashSet::new();
+    for shorten in shortens {
+        keys.insert(shorten.await.unwrap().unwrap().short_key.as_str().to_string());
+    }
+
+    assert_eq!(keys.len(), 1);
+}
+
+// a visit the click quota refuses doesn't use up a one-time link; it still works once quota resets
+#[tokio::test]
+async fn refused_visits_keep_the_links_click() {
+    let repo = setup().await;
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM usage WHERE workspace_id = 'refund'").execute(&pool).await.unwrap();
+    let open = Url::new(ShortKey::new("rEfnd1".to_string()).unwrap(), "https://example.com/open".to_string())
+        .with_workspace("refund");
+    let once = Url::new(ShortKey::new("rEfnd2".to_string()).unwrap(), "https://example.com/once".to_string())
+        .with_workspace("refund")
+        .with_click_limit(Some(ClickLimit::new(1, None)));
+    repo.insert(&open, &AuditContext::system()).await.unwrap();
+    repo.insert(&once, &AuditContext::system()).await.unwrap();
+    let limits = QuotaLimits { max_links: None, max_clicks: Some(1) };
+    let service = UrlService::new(repo).with_quota(PostgresUsageRepository::new(pool.clone()), limits);
+
+    service.visit(&Visit::to_host(None), &open.short_key).await.unwrap();
+    let refused = service.visit(&Visit::to_host(None), &once.short_key).await;
+    assert!(matches!(refused, Err(DomainError::QuotaExceeded(QuotaKind::Clicks))));
+
+    // A new month starts with nothing used
+    sqlx::query("DELETE FROM usage WHERE workspace_id = 'refund'").execute(&pool).await.unwrap();
+    let served = service.visit(&Visit::to_host(None), &once.short_key).await.unwrap();
+    assert_eq!(served.original_url, "https://example.com/once");
+    assert!(matches!(
+        service.visit(&Visit::to_host(None), &once.short_key).await,
+        Err(DomainError::LinkExhausted(_))
+    ));
+}
+
+// activation windows and their fallbacks survive both insert paths
+#[tokio::test]
+async fn active_window_is_persisted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let start = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+    let window = ActiveWindow {
+        active_from: Some(start),
+        active_until: Some(start + chrono::Duration::days(1)),
+        not_yet_active_url: Some("https://example.com/soon".to_string()),
+        ended_url: None,
+    };
+    let single = Url::new(ShortKey::new("win001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_active_window(Some(window.clone()));
+    let batch = vec![
+        Url::new(ShortKey::new("win002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_active_window(Some(window.clone())),
+        Url::new(ShortKey::new("win003".to_string()).unwrap(), "https://example.com/three".to_string()),
+    ];
+
+    repo.insert(&single, &ctx).await.unwrap();
+    let inserted = repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!(found.active_window, Some(window.clone()));
+    let scheduled: Vec<&str> = inserted
+        .iter()
+        .filter(|u| u.active_window.as_ref() == Some(&window))
+        .map(|u| u.short_key.as_str())
+        .collect();
+    assert_eq!(scheduled, vec!["win002"]);
+}
+
+// platform routes survive both insert paths
+#[tokio::test]
+async fn platform_routes_are_persisted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let routes = PlatformRoutes::default()
+        .with(Platform::Android, "https://play.google.com/store/apps/details?id=com.example")
+        .with(Platform::Desktop, "https://example.com/download");
+    let single = Url::new(ShortKey::new("plt001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_platform_routes(routes.clone());
+    let batch = vec![
+        Url::new(ShortKey::new("plt002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_platform_routes(routes.clone()),
+    ];
+
+    repo.insert(&single, &ctx).await.unwrap();
+    let inserted = repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!(found.platform_routes, routes);
+    assert_eq!(inserted[0].platform_routes, routes);
+}
+
+// country routes survive both insert paths; links without them read back empty
+#[tokio::test]
+async fn country_routes_are_persisted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let routes = CountryRoutes::default()
+        .with("DE", "https://example.de")
+        .with("FR", "https://example.fr");
+    let single = Url::new(ShortKey::new("geo001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_country_routes(routes.clone());
+    let batch = vec![
+        Url::new(ShortKey::new("geo002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_country_routes(routes.clone()),
+        Url::new(ShortKey::new("geo003".to_string()).unwrap(), "https://example.com/three".to_string()),
+    ];
+
+    repo.insert(&single, &ctx).await.unwrap();
+    repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!(found.country_routes, routes);
+    let two = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[0].short_key).await.unwrap().unwrap();
+    assert_eq!(two.country_routes, routes);
+    let three = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[1].short_key).await.unwrap().unwrap();
+    assert!(three.country_routes.is_empty());
+}
+
+// variants survive both insert paths, and clicks are counted per variant and per domain
+#[tokio::test]
+async fn variants_are_persisted_and_counted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let variants = Variants::default()
+        .with("https://example.com/a", 70)
+        .with("https://example.com/b", 30);
+    let single = Url::new(ShortKey::new("spl001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_variants(variants.clone());
+    let batch = vec![
+        Url::new(ShortKey::new("spl002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_variants(variants.clone()),
+    ];
+    repo.insert(&single, &ctx).await.unwrap();
+    repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM click_events").execute(&pool).await.unwrap();
+    let clicks = PostgresClickRepository::new(pool);
+    clicks.record(&ClickEvent::new(DEFAULT_DOMAIN, "spl001", "default").with_variant(Some(1))).await.unwrap();
+    clicks
+        .record_many(&[
+            ClickEvent::new(DEFAULT_DOMAIN, "spl001", "default").with_variant(Some(0)),
+            ClickEvent::new(DEFAULT_DOMAIN, "spl001", "default").with_variant(Some(1)),
+            ClickEvent::new(DEFAULT_DOMAIN, "spl001", "default"),
+            ClickEvent::new(DEFAULT_DOMAIN, "spl002", "default").with_variant(Some(0)),
+            ClickEvent::new("acme.link", "spl001", "default").with_variant(Some(0)),
+        ])
+        .await
+        .unwrap();
+
+    let found = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!(found.variants, variants);
+    let two = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[0].short_key).await.unwrap().unwrap();
+    assert_eq!(two.variants, variants);
+    assert_eq!(clicks.count_by_variant(DEFAULT_DOMAIN, "default", "spl001").await.unwrap(), vec![(0, 1), (1, 2)]);
+    assert_eq!(clicks.count(DEFAULT_DOMAIN, "default", "spl001").await.unwrap(), 4);
+    assert_eq!(clicks.count("acme.link", "default", "spl001").await.unwrap(), 1);
+}
+
+// passthrough options survive both insert paths
+#[tokio::test]
+async fn passthrough_options_are_persisted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let single = Url::new(ShortKey::new("fwd001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_forwarding(Some(QueryPolicy::Replace), true);
+    let batch = vec![
+        Url::new(ShortKey::new("fwd002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_forwarding(Some(QueryPolicy::Append), false),
+        Url::new(ShortKey::new("fwd003".to_string()).unwrap(), "https://example.com/three".to_string()),
+    ];
+
+    repo.insert(&single, &ctx).await.unwrap();
+    repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let one = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!((one.forward_query, one.forward_path), (Some(QueryPolicy::Replace), true));
+    let two = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[0].short_key).await.unwrap().unwrap();
+    assert_eq!((two.forward_query, two.forward_path), (Some(QueryPolicy::Append), false));
+    let three = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[1].short_key).await.unwrap().unwrap();
+    assert_eq!((three.forward_query, three.forward_path), (None, false));
+}
+
+// a link's warning page survives both insert paths
+#[tokio::test]
+async fn link_interstitials_are_persisted() {
+    let repo = setup().await;
+    let ctx = AuditContext::system();
+    let single = Url::new(ShortKey::new("wrn001".to_string()).unwrap(), "https://example.com/one".to_string())
+        .with_interstitial(Some(Interstitial::new(0)));
+    let batch = vec![
+        Url::new(ShortKey::new("wrn002".to_string()).unwrap(), "https://example.com/two".to_string())
+            .with_interstitial(Some(Interstitial::new(10))),
+        Url::new(ShortKey::new("wrn003".to_string()).unwrap(), "https://example.com/three".to_string()),
+    ];
+
+    repo.insert(&single, &ctx).await.unwrap();
+    repo.insert_many(&batch, &ctx).await.unwrap();
+
+    let one = repo.find_by_short_key(DEFAULT_DOMAIN, &single.short_key).await.unwrap().unwrap();
+    assert_eq!(one.interstitial, Some(Interstitial::new(0)));
+    let two = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[0].short_key).await.unwrap().unwrap();
+    assert_eq!(two.interstitial, Some(Interstitial::new(10)));
+    let three = repo.find_by_short_key(DEFAULT_DOMAIN, &batch[1].short_key).await.unwrap().unwrap();
+    assert_eq!(three.interstitial, None);
+}
+
+// saving a template twice replaces it, and templates are listed per workspace by name
+#[tokio::test]
+async fn utm_templates_are_upserted_per_workspace() {
+    dotenv::dotenv().ok();
+    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+    sqlx::query("DELETE FROM utm_templates").execute(&pool).await.unwrap();
+    let repo = PostgresUtmTemplateRepository::new(pool);
+    let utm = |source: &str| Utm {
+        source: Some(source.to_string()),
+        medium: Some("email".to_string()),
+        ..Utm::default()
+    };
+
+    repo.save(&UtmTemplate::new(Some("acme"), "newsletter", utm("weekly"))).await.unwrap();
+    repo.save(&UtmTemplate::new(Some("acme"), "newsletter", utm("monthly"))).await.unwrap();
+    repo.save(&UtmTemplate::new(Some("acme"), "ads", utm("google"))).await.unwrap();
+    repo.save(&UtmTemplate::new(Some("globex"), "newsletter", utm("daily"))).await.unwrap();
+
+    let found = repo.find("acme", "newsletter").await.unwrap().unwrap();
+    assert_eq!(found.utm, utm("monthly"));
+    assert!(repo.find("acme", "missing").await.unwrap().is_none());
+    let names: Vec<String> = repo.list("acme").await.unwrap().into_iter().map(|t| t.name).collect();
+    assert_eq!(names, vec!["ads", "newsletter"]);
+}
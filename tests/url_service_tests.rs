@@ -1,14 +1,26 @@
 use async_trait::async_trait;
-use redirect_service::model::{DomainError, ShortKey, Url};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use redirect_service::model::domain::DEFAULT_DOMAIN;
+use redirect_service::model::{
+    AuditContext, ClickEvent, DataFormat, Domain, DomainError, DomainFallbacks, Fallback, Interstitial, NewLink, Platform, QueryPolicy, QuotaKind, QuotaLimits, ShortKey, Url,
+    Usage, Utm, UtmTemplate, Visit, Warning, WarningList,
+};
+use redirect_service::repository::click_repository::ClickRepository;
+use redirect_service::repository::domain_repository::DomainRepository;
 use redirect_service::repository::url_repository::UrlRepository;
-use redirect_service::service::url_service::UrlService;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use redirect_service::repository::usage_repository::UsageRepository;
+use redirect_service::repository::utm_template_repository::UtmTemplateRepository;
+use redirect_service::service::unlock::PasswordAttempts;
+use redirect_service::service::url_service::{UrlService, MAX_BATCH_LIMIT};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 //----------- Repo for Mock --------------//
 
 struct MockUrlRepository {
-    store: Mutex<HashMap<String, Url>>,
+    store: Mutex<HashMap<(String, String), Url>>,
 }
 
 impl MockUrlRepository {
@@ -19,49 +31,386 @@ impl MockUrlRepository {
     }
 }
 
+fn link_id(domain: &str, short_key: &str) -> (String, String) {
+    (domain.to_string(), short_key.to_string())
+}
+
 #[async_trait]
 impl UrlRepository for MockUrlRepository {
-    async fn find_by_original_url(&self, original_url: &str) -> Result<Option<Url>, DomainError> {
+    async fn find_by_original_url(&self, domain: &str, workspace_id: &str, original_url: &str) -> Result<Option<Url>, DomainError> {
         let store = self.store.lock().unwrap();
         let found = store
             .values()
-            .find(|url| url.original_url == original_url)
-            .map(|url| Url {
-                short_key: url.short_key.clone(),
-                original_url: url.original_url.clone(),
-                created_at: url.created_at,
-            });
+            .find(|url| {
+                url.domain == domain && url.workspace_id == workspace_id && url.original_url == original_url && !url.is_deleted()
+            })
+            .cloned();
+        Ok(found)
+    }
+
+    async fn find_by_short_key(&self, domain: &str, short_key: &ShortKey) -> Result<Option<Url>, DomainError> {
+        let store = self.store.lock().unwrap();
+        let found = store.get(&link_id(domain, short_key.as_str())).cloned();
         Ok(found)
     }
 
-    async fn find_by_short_key(&self, short_key: &ShortKey) -> Result<Option<Url>, DomainError> {
+    async fn find_by_original_urls(&self, domain: &str, workspace_id: &str, original_urls: &[String]) -> Result<Vec<Url>, DomainError> {
         let store = self.store.lock().unwrap();
-        let found = store.get(short_key.as_str()).map(|url| Url {
-            short_key: url.short_key.clone(),
-            original_url: url.original_url.clone(),
-            created_at: url.created_at,
-        });
+        let found = store
+            .values()
+            .filter(|url| {
+                url.domain == domain
+                    && url.workspace_id == workspace_id
+                    && original_urls.contains(&url.original_url)
+                    && !url.is_deleted()
+            })
+            .cloned()
+            .collect();
+        Ok(found)
+    }
+
+    async fn existing_short_keys(&self, domain: &str, short_keys: &[String]) -> Result<Vec<String>, DomainError> {
+        let store = self.store.lock().unwrap();
+        let found = short_keys
+            .iter()
+            .filter(|key| store.contains_key(&link_id(domain, key)))
+            .cloned()
+            .collect();
+        Ok(found)
+    }
+
+    async fn insert(&self, url: &Url, _ctx: &AuditContext) -> Result<(), DomainError> {
+        let mut store = self.store.lock().unwrap();
+        store.insert(link_id(&url.domain, url.short_key.as_str()), url.clone());
+        Ok(())
+    }
+
+    async fn insert_many(&self, urls: &[Url], _ctx: &AuditContext) -> Result<Vec<Url>, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        for url in urls {
+            store.insert(link_id(&url.domain, url.short_key.as_str()), url.clone());
+        }
+        Ok(urls.to_vec())
+    }
+
+    async fn import_many(&self, urls: &[Url], _ctx: &AuditContext) -> Result<Vec<(String, String)>, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        let mut inserted = Vec::new();
+        for url in urls {
+            let id = link_id(&url.domain, url.short_key.as_str());
+            let key_taken = store.contains_key(&id);
+            let url_taken = store.values().any(|existing| {
+                existing.domain == url.domain
+                    && existing.workspace_id == url.workspace_id
+                    && existing.original_url == url.original_url
+                    && !existing.is_deleted()
+            });
+            if !key_taken && !url_taken {
+                store.insert(id.clone(), url.clone());
+                inserted.push(id);
+            }
+        }
+        Ok(inserted)
+    }
+
+    async fn retarget(&self, domain: &str, short_key: &ShortKey, original_url: &str, _ctx: &AuditContext) -> Result<Option<Url>, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        let found = store
+            .get_mut(&link_id(domain, short_key.as_str()))
+            .filter(|url| !url.is_deleted())
+            .map(|url| {
+                url.original_url = original_url.to_string();
+                url.clone()
+            });
         Ok(found)
     }
 
-    async fn insert(&self, url: &Url) -> Result<(), DomainError> {
+    async fn delete(&self, domain: &str, short_key: &ShortKey, deleted_at: DateTime<Utc>, _ctx: &AuditContext) -> Result<bool, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        match store.get_mut(&link_id(domain, short_key.as_str())).filter(|url| !url.is_deleted()) {
+            Some(url) => {
+                url.deleted_at = Some(deleted_at);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn restore(&self, domain: &str, short_key: &ShortKey, _ctx: &AuditContext) -> Result<Option<Url>, DomainError> {
         let mut store = self.store.lock().unwrap();
-        store.insert(
-            url.short_key.as_str().to_string(),
-            Url {
-                short_key: url.short_key.clone(),
-                original_url: url.original_url.clone(),
-                created_at: url.created_at,
-            },
-        );
+        let found = store
+            .get_mut(&link_id(domain, short_key.as_str()))
+            .filter(|url| url.is_deleted())
+            .map(|url| {
+                url.deleted_at = None;
+                url.clone()
+            });
+        Ok(found)
+    }
+
+    async fn take_click(&self, domain: &str, short_key: &ShortKey) -> Result<bool, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        let limit = store
+            .get_mut(&link_id(domain, short_key.as_str()))
+            .filter(|url| !url.is_deleted())
+            .and_then(|url| url.click_limit.as_mut())
+            .filter(|limit| limit.remaining > 0);
+        match limit {
+            Some(limit) => {
+                limit.remaining -= 1;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn return_click(&self, domain: &str, short_key: &ShortKey) -> Result<(), DomainError> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(limit) = store
+            .get_mut(&link_id(domain, short_key.as_str()))
+            .and_then(|url| url.click_limit.as_mut())
+            .filter(|limit| limit.remaining < limit.max_clicks)
+        {
+            limit.remaining += 1;
+        }
         Ok(())
     }
+
+    fn stream_live(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<Url, DomainError>> {
+        let store = self.store.lock().unwrap();
+        let mut live: Vec<Url> = store
+            .values()
+            .filter(|url| !url.is_deleted() && workspace_id.is_none_or(|w| url.workspace_id == w))
+            .cloned()
+            .collect();
+        live.sort_by_key(|url| url.created_at);
+        stream::iter(live.into_iter().map(Ok)).boxed()
+    }
+}
+
+//----------- Click repo for Mock --------------//
+
+struct MockClickRepository {
+    clicks: Mutex<Vec<ClickEvent>>,
+}
+
+#[async_trait]
+impl ClickRepository for MockClickRepository {
+    async fn record(&self, click: &ClickEvent) -> Result<(), DomainError> {
+        self.clicks.lock().unwrap().push(click.clone());
+        Ok(())
+    }
+
+    async fn record_many(&self, clicks: &[ClickEvent]) -> Result<(), DomainError> {
+        self.clicks.lock().unwrap().extend_from_slice(clicks);
+        Ok(())
+    }
+
+    fn stream(&self, workspace_id: Option<&str>) -> BoxStream<'static, Result<ClickEvent, DomainError>> {
+        let clicks: Vec<ClickEvent> = self
+            .clicks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|click| workspace_id.is_none_or(|w| click.workspace_id == w))
+            .cloned()
+            .collect();
+        stream::iter(clicks.into_iter().map(Ok)).boxed()
+    }
+
+    async fn count_by_variant(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<Vec<(i32, i64)>, DomainError> {
+        let mut counts: BTreeMap<i32, i64> = BTreeMap::new();
+        for click in self.clicks.lock().unwrap().iter() {
+            if click.domain == domain && click.workspace_id == workspace_id && click.short_key == short_key
+                && let Some(variant) = click.variant
+            {
+                *counts.entry(variant).or_default() += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn count(&self, domain: &str, workspace_id: &str, short_key: &str) -> Result<i64, DomainError> {
+        let clicks = self.clicks.lock().unwrap();
+        Ok(clicks
+            .iter()
+            .filter(|click| click.domain == domain && click.workspace_id == workspace_id && click.short_key == short_key)
+            .count() as i64)
+    }
+}
+
+//----------- Domain repo for Mock --------------//
+
+struct MockDomainRepository {
+    domains: Mutex<Vec<Domain>>,
+}
+
+impl MockDomainRepository {
+    fn with(domains: Vec<Domain>) -> Self {
+        Self {
+            domains: Mutex::new(domains),
+        }
+    }
+}
+
+#[async_trait]
+impl DomainRepository for MockDomainRepository {
+    async fn find(&self, host: &str) -> Result<Option<Domain>, DomainError> {
+        Ok(self.domains.lock().unwrap().iter().find(|d| d.host == host).cloned())
+    }
+
+    async fn insert(&self, domain: &Domain) -> Result<bool, DomainError> {
+        let mut domains = self.domains.lock().unwrap();
+        if domains.iter().any(|d| d.host == domain.host) {
+            return Ok(false);
+        }
+        domains.push(domain.clone());
+        Ok(true)
+    }
+
+    async fn list(&self) -> Result<Vec<Domain>, DomainError> {
+        Ok(self.domains.lock().unwrap().clone())
+    }
+
+    async fn set_fallbacks(&self, host: &str, fallbacks: &DomainFallbacks) -> Result<Option<Domain>, DomainError> {
+        let mut domains = self.domains.lock().unwrap();
+        Ok(domains.iter_mut().find(|d| d.host == host).map(|domain| {
+            domain.fallbacks = fallbacks.clone();
+            domain.clone()
+        }))
+    }
+
+    async fn set_interstitial(&self, host: &str, interstitial: Option<Interstitial>) -> Result<Option<Domain>, DomainError> {
+        let mut domains = self.domains.lock().unwrap();
+        Ok(domains.iter_mut().find(|d| d.host == host).map(|domain| {
+            domain.interstitial = interstitial;
+            domain.clone()
+        }))
+    }
+}
+
+//----------- UTM template repo for Mock --------------//
+
+#[derive(Default)]
+struct MockUtmTemplateRepository {
+    templates: Mutex<Vec<UtmTemplate>>,
+}
+
+#[async_trait]
+impl UtmTemplateRepository for MockUtmTemplateRepository {
+    async fn find(&self, workspace_id: &str, name: &str) -> Result<Option<UtmTemplate>, DomainError> {
+        let templates = self.templates.lock().unwrap();
+        Ok(templates.iter().find(|t| t.workspace_id == workspace_id && t.name == name).cloned())
+    }
+
+    async fn save(&self, template: &UtmTemplate) -> Result<(), DomainError> {
+        let mut templates = self.templates.lock().unwrap();
+        templates.retain(|t| !(t.workspace_id == template.workspace_id && t.name == template.name));
+        templates.push(template.clone());
+        Ok(())
+    }
+
+    async fn list(&self, workspace_id: &str) -> Result<Vec<UtmTemplate>, DomainError> {
+        let templates = self.templates.lock().unwrap();
+        Ok(templates.iter().filter(|t| t.workspace_id == workspace_id).cloned().collect())
+    }
+}
+
+//----------- Usage repo for Mock --------------//
+
+struct MockUsageRepository {
+    store: Mutex<HashMap<(String, NaiveDate), Usage>>,
+}
+
+impl MockUsageRepository {
+    fn new() -> Self {
+        Self {
+            store: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl UsageRepository for MockUsageRepository {
+    async fn find(&self, workspace_id: &str, period_start: NaiveDate) -> Result<Usage, DomainError> {
+        let store = self.store.lock().unwrap();
+        let found = store
+            .get(&(workspace_id.to_string(), period_start))
+            .cloned()
+            .unwrap_or_else(|| Usage::empty(workspace_id, period_start));
+        Ok(found)
+    }
+
+    async fn try_increment(
+        &self,
+        workspace_id: &str,
+        period_start: NaiveDate,
+        kind: QuotaKind,
+        limit: Option<i64>,
+    ) -> Result<bool, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        let usage = store
+            .entry((workspace_id.to_string(), period_start))
+            .or_insert_with(|| Usage::empty(workspace_id, period_start));
+        let counter = match kind {
+            QuotaKind::Links => &mut usage.links_created,
+            QuotaKind::Clicks => &mut usage.clicks_tracked,
+        };
+        if limit.is_some_and(|limit| *counter >= limit) {
+            return Ok(false);
+        }
+        *counter += 1;
+        Ok(true)
+    }
+
+    async fn increment_up_to(
+        &self,
+        workspace_id: &str,
+        period_start: NaiveDate,
+        kind: QuotaKind,
+        amount: i64,
+        limit: Option<i64>,
+    ) -> Result<i64, DomainError> {
+        let mut store = self.store.lock().unwrap();
+        let usage = store
+            .entry((workspace_id.to_string(), period_start))
+            .or_insert_with(|| Usage::empty(workspace_id, period_start));
+        let counter = match kind {
+            QuotaKind::Links => &mut usage.links_created,
+            QuotaKind::Clicks => &mut usage.clicks_tracked,
+        };
+        let granted = match limit {
+            Some(limit) => amount.min(limit - *counter).max(0),
+            None => amount,
+        };
+        *counter += granted;
+        Ok(granted)
+    }
 }
 
 fn make_service() -> UrlService {
     UrlService::new(MockUrlRepository::new())
 }
 
+/// `go.acme.io` belongs to the acme workspace; `acme.link` is shared.
+fn make_service_with_domains() -> UrlService {
+    UrlService::new(MockUrlRepository::new()).with_domains(
+        MockDomainRepository::with(vec![
+            Domain::new(DEFAULT_DOMAIN, None),
+            Domain::new("go.acme.io", Some("acme".to_string())),
+            Domain::new("acme.link", None),
+        ]),
+        DEFAULT_DOMAIN,
+    )
+}
+
+fn make_service_with_quota(max_links: Option<i64>, max_clicks: Option<i64>) -> UrlService {
+    UrlService::new(MockUrlRepository::new()).with_quota(
+        MockUsageRepository::new(),
+        QuotaLimits { max_links, max_clicks },
+    )
+}
+
 //--------Test-----------//
 
 #[tokio::test]
@@ -115,7 +464,7 @@ async fn resolve_shortened_rust_book_url_returns_correct_original() {
         "https://doc.rust-lang.org/book/ch21-02-multithreaded.html?search=serde";
 
     let shortened = service.shorten(long_url).await.unwrap();
-    let resolved = service.resolve(&shortened.short_key).await.unwrap();
+    let resolved = service.resolve(None, &shortened.short_key).await.unwrap();
 
     assert_eq!(resolved.original_url, long_url);
 }
@@ -125,7 +474,7 @@ async fn resolve_unknown_key_returns_not_found() {
     let service = make_service();
     let fake_key = ShortKey::new("xxxxxx".to_string()).unwrap();
 
-    let result = service.resolve(&fake_key).await;
+    let result = service.resolve(None, &fake_key).await;
 
     assert!(matches!(result, Err(DomainError::UrlNotFound)));
 }
@@ -148,7 +497,907 @@ async fn multiple_different_urls_all_resolve_to_correct_originals() {
     }
 
     for (i, url) in urls.iter().enumerate() {
-        let resolved = service.resolve(&keys[i]).await.unwrap();
+        let resolved = service.resolve(None, &keys[i]).await.unwrap();
         assert_eq!(&resolved.original_url, url);
     }
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn shorten_beyond_link_quota_returns_quota_exceeded() {
+    let service = make_service_with_quota(Some(2), None);
+
+    service.shorten_for(&AuditContext::system(), "acme", "https://leetcode.com/problems/two-sum/").await.unwrap();
+    service.shorten_for(&AuditContext::system(), "acme", "https://leetcode.com/problems/3sum/").await.unwrap();
+    let result = service.shorten_for(&AuditContext::system(), "acme", "https://leetcode.com/problems/4sum/").await;
+
+    assert!(matches!(result, Err(DomainError::QuotaExceeded(QuotaKind::Links))));
+}
+
+#[tokio::test]
+async fn shorten_existing_url_does_not_count_against_quota() {
+    let service = make_service_with_quota(Some(1), None);
+    let long_url = "https://leetcode.com/problems/two-sum/description/";
+
+    service.shorten_for(&AuditContext::system(), "acme", long_url).await.unwrap();
+    let again = service.shorten_for(&AuditContext::system(), "acme", long_url).await;
+
+    assert!(again.is_ok());
+    let (usage, _) = service.usage("acme").await.unwrap();
+    assert_eq!(usage.links_created, 1);
+}
+
+#[tokio::test]
+async fn link_quota_is_tracked_per_workspace() {
+    let service = make_service_with_quota(Some(1), None);
+
+    service.shorten_for(&AuditContext::system(), "acme", "https://leetcode.com/problems/two-sum/").await.unwrap();
+    let other = service.shorten_for(&AuditContext::system(), "globex", "https://leetcode.com/problems/3sum/").await;
+
+    assert!(other.is_ok());
+}
+
+#[tokio::test]
+async fn same_url_in_another_workspace_gets_its_own_link() {
+    let service = make_service();
+
+    let acme = service.shorten_for(&AuditContext::system(), "acme", "https://example.com/shared").await.unwrap();
+    let globex = service.shorten_for(&AuditContext::system(), "globex", "https://example.com/shared").await.unwrap();
+    let batch = service
+        .shorten_batch(&AuditContext::system(), &[NewLink::new("https://example.com/shared").in_workspace("globex")])
+        .await
+        .unwrap();
+
+    assert_ne!(acme.short_key.as_str(), globex.short_key.as_str());
+    assert_eq!(globex.workspace_id, "globex");
+    assert_eq!(batch[0].as_ref().unwrap().short_key.as_str(), globex.short_key.as_str());
+}
+
+#[tokio::test]
+async fn resolve_beyond_click_quota_returns_quota_exceeded() {
+    let service = make_service_with_quota(None, Some(2));
+    let shortened = service
+        .shorten_for(&AuditContext::system(), "acme", "https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html")
+        .await
+        .unwrap();
+
+    service.resolve(None, &shortened.short_key).await.unwrap();
+    service.resolve(None, &shortened.short_key).await.unwrap();
+    let result = service.resolve(None, &shortened.short_key).await;
+
+    assert!(matches!(result, Err(DomainError::QuotaExceeded(QuotaKind::Clicks))));
+}
+
+#[tokio::test]
+async fn usage_reports_links_and_clicks_with_limits() {
+    let service = make_service_with_quota(Some(10), Some(100));
+    let shortened = service
+        .shorten_for(&AuditContext::system(), "acme", "https://blog.rust-lang.org/2024/11/28/Rust-1.83.0.html")
+        .await
+        .unwrap();
+    service.resolve(None, &shortened.short_key).await.unwrap();
+    service.resolve(None, &shortened.short_key).await.unwrap();
+
+    let (usage, limits) = service.usage("acme").await.unwrap();
+
+    assert_eq!(usage.links_created, 1);
+    assert_eq!(usage.clicks_tracked, 2);
+    assert_eq!(limits.max_links, Some(10));
+    assert_eq!(limits.max_clicks, Some(100));
+}
+
+#[tokio::test]
+async fn retarget_changes_where_key_resolves() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let shortened = service
+        .shorten("https://leetcode.com/problems/two-sum/description/")
+        .await
+        .unwrap();
+
+    service
+        .retarget(&ctx, None, &shortened.short_key, "https://leetcode.com/problems/3sum/description/")
+        .await
+        .unwrap();
+    let resolved = service.resolve(None, &shortened.short_key).await.unwrap();
+
+    assert_eq!(resolved.original_url, "https://leetcode.com/problems/3sum/description/");
+}
+
+// deletions are stamped by the injected clock, like activation windows
+#[tokio::test]
+async fn delete_stamps_the_clock_time() {
+    let deleted_at = Utc.with_ymd_and_hms(2026, 3, 14, 12, 0, 0).unwrap();
+    let service = make_service().with_clock(move || deleted_at);
+    let ctx = AuditContext::system();
+    let url = service.shorten("https://example.com/retired").await.unwrap();
+
+    service.delete(&ctx, None, &url.short_key).await.unwrap();
+
+    assert_eq!(service.lookup(None, &url.short_key).await.unwrap().deleted_at, Some(deleted_at));
+}
+
+#[tokio::test]
+async fn deleted_key_no_longer_resolves_until_restored() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let shortened = service
+        .shorten("https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html")
+        .await
+        .unwrap();
+
+    service.delete(&ctx, None, &shortened.short_key).await.unwrap();
+    let while_deleted = service.resolve(None, &shortened.short_key).await;
+    service.restore(&ctx, None, &shortened.short_key).await.unwrap();
+    let after_restore = service.resolve(None, &shortened.short_key).await;
+
+    assert!(matches!(while_deleted, Err(DomainError::UrlNotFound)));
+    assert!(after_restore.is_ok());
+}
+
+#[tokio::test]
+async fn mutating_unknown_key_returns_not_found() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let fake_key = ShortKey::new("xxxxxx".to_string()).unwrap();
+
+    assert!(matches!(
+        service.retarget(&ctx, None, &fake_key, "https://example.com").await,
+        Err(DomainError::UrlNotFound)
+    ));
+    assert!(matches!(service.delete(&ctx, None, &fake_key).await, Err(DomainError::UrlNotFound)));
+    assert!(matches!(service.restore(&ctx, None, &fake_key).await, Err(DomainError::UrlNotFound)));
+}
+
+#[tokio::test]
+async fn shorten_rejects_non_http_urls() {
+    let service = make_service();
+
+    let result = service.shorten("javascript:alert(1)").await;
+
+    assert!(matches!(result, Err(DomainError::InvalidUrl)));
+}
+
+#[tokio::test]
+async fn shorten_batch_returns_per_item_results_in_order() {
+    let service = make_service();
+    let existing = service
+        .shorten("https://leetcode.com/problems/two-sum/description/")
+        .await
+        .unwrap();
+    let links = vec![
+        NewLink::new("https://leetcode.com/problems/two-sum/description/"),
+        NewLink::new("not a url"),
+        NewLink::new("https://leetcode.com/problems/3sum/description/"),
+        NewLink::new("https://leetcode.com/problems/3sum/description/"),
+    ];
+
+    let results = service.shorten_batch(&AuditContext::system(), &links).await.unwrap();
+
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].as_ref().unwrap().short_key.as_str(), existing.short_key.as_str());
+    assert!(matches!(results[1], Err(DomainError::InvalidUrl)));
+    let third = results[2].as_ref().unwrap();
+    assert_eq!(third.original_url, "https://leetcode.com/problems/3sum/description/");
+    assert_eq!(third.short_key.as_str(), results[3].as_ref().unwrap().short_key.as_str());
+
+    let resolved = service.resolve(None, &third.short_key).await.unwrap();
+    assert_eq!(resolved.original_url, third.original_url);
+}
+
+#[tokio::test]
+async fn shorten_batch_over_limit_is_rejected() {
+    let service = make_service().with_batch_limit(2);
+    let links = vec![
+        NewLink::new("https://example.com/1"),
+        NewLink::new("https://example.com/2"),
+        NewLink::new("https://example.com/3"),
+    ];
+
+    let result = service.shorten_batch(&AuditContext::system(), &links).await;
+
+    assert!(matches!(result, Err(DomainError::BatchTooLarge(2))));
+}
+
+// a configured batch limit can't go past the ceiling
+#[tokio::test]
+async fn batch_limit_is_capped() {
+    let service = make_service().with_batch_limit(usize::MAX);
+    let links = vec![NewLink::new("https://example.com/1"); MAX_BATCH_LIMIT + 1];
+
+    let result = service.shorten_batch(&AuditContext::system(), &links).await;
+
+    assert!(matches!(result, Err(DomainError::BatchTooLarge(MAX_BATCH_LIMIT))));
+}
+
+#[tokio::test]
+async fn shorten_batch_refuses_items_beyond_link_quota() {
+    let service = make_service_with_quota(Some(2), None);
+    let links = vec![
+        NewLink::new("https://example.com/1").in_workspace("acme"),
+        NewLink::new("https://example.com/2").in_workspace("acme"),
+        NewLink::new("https://example.com/3").in_workspace("acme"),
+        NewLink::new("https://example.com/4").in_workspace("globex"),
+    ];
+
+    let results = service.shorten_batch(&AuditContext::system(), &links).await.unwrap();
+
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    assert!(matches!(results[2], Err(DomainError::QuotaExceeded(QuotaKind::Links))));
+    assert!(results[3].is_ok());
+}
+
+#[tokio::test]
+async fn import_csv_preserves_keys_and_timestamps() {
+    let service = make_service();
+    let input = "short_key,original_url,created_at\n\
+                 legacy1,https://example.com/one,2019-05-01T12:00:00Z\n\
+                 legacy2,https://example.com/two,\n";
+
+    let report = service
+        .import_links(&AuditContext::system(), input.as_bytes(), DataFormat::Csv, 100)
+        .await
+        .unwrap();
+    let imported = service
+        .resolve(None, &ShortKey::new("legacy1".to_string()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(report.imported, 2);
+    assert_eq!(imported.original_url, "https://example.com/one");
+    assert_eq!(imported.created_at.to_rfc3339(), "2019-05-01T12:00:00+00:00");
+}
+
+#[tokio::test]
+async fn import_reports_invalid_rows_and_conflicts_by_line() {
+    let service = make_service();
+    service.shorten("https://example.com/taken").await.unwrap();
+    let input = concat!(
+        "{\"shortKey\": \"abc\", \"originalUrl\": \"https://example.com/short-key\"}\n",
+        "{\"shortKey\": \"good001\", \"originalUrl\": \"mailto:someone\"}\n",
+        "not json\n",
+        "{\"shortKey\": \"good002\", \"originalUrl\": \"https://example.com/taken\"}\n",
+        "{\"shortKey\": \"good003\", \"originalUrl\": \"https://example.com/fine\"}\n",
+        "{\"shortKey\": \"good003\", \"originalUrl\": \"https://example.com/again\"}\n",
+    );
+
+    let report = service
+        .import_links(&AuditContext::system(), input.as_bytes(), DataFormat::JsonLines, 2)
+        .await
+        .unwrap();
+
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.invalid_count, 3);
+    let invalid_lines: Vec<usize> = report.invalid.iter().map(|i| i.line).collect();
+    assert_eq!(invalid_lines, vec![1, 2, 3]);
+    assert_eq!(report.conflict_count, 2);
+    let conflict_lines: Vec<usize> = report.conflicts.iter().map(|i| i.line).collect();
+    assert!(conflict_lines.contains(&4));
+    assert!(conflict_lines.contains(&6));
+}
+
+#[tokio::test]
+async fn import_commits_in_chunks() {
+    let service = make_service();
+    let input: String = (1..=5)
+        .map(|i| format!("chunky{i},https://example.com/{i}\n"))
+        .collect();
+
+    let report = service
+        .import_links(&AuditContext::system(), input.as_bytes(), DataFormat::Csv, 2)
+        .await
+        .unwrap();
+
+    assert_eq!(report.imported, 5);
+    assert_eq!(report.chunks_committed, 3);
+}
+
+#[tokio::test]
+async fn resolve_records_a_click_event() {
+    let service = make_service().with_click_log(MockClickRepository {
+        clicks: Default::default(),
+    });
+    let shortened = service
+        .shorten_for(&AuditContext::system(), "acme", "https://example.com/clicked")
+        .await
+        .unwrap();
+
+    service.resolve(None, &shortened.short_key).await.unwrap();
+    service.resolve(None, &shortened.short_key).await.unwrap();
+    let clicks: Vec<ClickEvent> = service.export_clicks(Some("acme")).try_collect().await.unwrap();
+
+    assert_eq!(clicks.len(), 2);
+    assert_eq!(clicks[0].short_key, shortened.short_key.as_str());
+}
+
+#[tokio::test]
+async fn export_links_filters_by_workspace_and_skips_deleted() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    service.shorten_for(&ctx, "acme", "https://example.com/a").await.unwrap();
+    let gone = service.shorten_for(&ctx, "acme", "https://example.com/b").await.unwrap();
+    service.shorten_for(&ctx, "globex", "https://example.com/c").await.unwrap();
+    service.delete(&ctx, None, &gone.short_key).await.unwrap();
+
+    let exported: Vec<Url> = service.export_links(Some("acme")).try_collect().await.unwrap();
+
+    assert_eq!(exported.len(), 1);
+    assert_eq!(exported[0].original_url, "https://example.com/a");
+}
+
+// lookup shows deleted links without counting a click
+#[tokio::test]
+async fn lookup_returns_deleted_link() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let url = service.shorten("https://example.com/inspect").await.unwrap();
+    service.delete(&ctx, None, &url.short_key).await.unwrap();
+
+    let found = service.lookup(None, &url.short_key).await.unwrap();
+
+    assert!(found.is_deleted());
+    assert!(matches!(service.resolve(None, &url.short_key).await, Err(DomainError::UrlNotFound)));
+}
+
+// the same destination gets a separate link per domain, resolved by the request's host
+#[tokio::test]
+async fn links_resolve_by_host() {
+    let service = make_service_with_domains();
+    let ctx = AuditContext::system();
+    let link = NewLink::new("https://example.com/launch").in_workspace("acme");
+
+    let home = service.shorten_link(&ctx, &link).await.unwrap();
+    let branded = service.shorten_link(&ctx, &link.clone().on_domain("Go.Acme.IO")).await.unwrap();
+    service.retarget(&ctx, Some("go.acme.io"), &branded.short_key, "https://acme.io/launch").await.unwrap();
+
+    assert_eq!(home.domain, DEFAULT_DOMAIN);
+    assert_eq!(branded.domain, "go.acme.io");
+    let resolved = service.resolve(Some("GO.ACME.IO"), &branded.short_key).await.unwrap();
+    assert_eq!(resolved.original_url, "https://acme.io/launch");
+    // unregistered hosts are served from the default domain
+    let resolved = service.resolve(Some("10.0.0.7:8080"), &home.short_key).await.unwrap();
+    assert_eq!(resolved.original_url, "https://example.com/launch");
+}
+
+// unknown keys get the serving domain's fallbacks; unregistered hosts get the default domain's
+#[tokio::test]
+async fn fallbacks_follow_the_serving_domain() {
+    let mut home = Domain::new(DEFAULT_DOMAIN, None);
+    home.fallbacks.root_url = Some("https://example.com/".to_string());
+    let mut acme = Domain::new("go.acme.io", Some("acme".to_string()));
+    acme.fallbacks.not_found_url = Some("https://acme.io/missing?src=short".to_string());
+    let service = UrlService::new(MockUrlRepository::new())
+        .with_domains(MockDomainRepository::with(vec![home, acme]), DEFAULT_DOMAIN);
+
+    let branded = service.fallbacks(Some("go.acme.io")).await.unwrap();
+    assert_eq!(
+        branded.not_found("launch"),
+        Some(Fallback::Redirect("https://acme.io/missing?src=short&key=launch".to_string()))
+    );
+    assert_eq!(branded.root(), None);
+
+    let unregistered = service.fallbacks(Some("elsewhere.io")).await.unwrap();
+    assert_eq!(unregistered.root(), Some(Fallback::Redirect("https://example.com/".to_string())));
+    assert_eq!(unregistered.not_found("launch"), None);
+}
+
+// a workspace can only use its own or shared domains
+#[tokio::test]
+async fn shorten_rejects_foreign_and_unknown_domains() {
+    let service = make_service_with_domains();
+    let ctx = AuditContext::system();
+    let link = NewLink::new("https://example.com/").in_workspace("globex");
+
+    let foreign = service.shorten_link(&ctx, &link.clone().on_domain("go.acme.io")).await;
+    let unknown = service.shorten_link(&ctx, &link.clone().on_domain("nope.io")).await;
+    let invalid = service.shorten_link(&ctx, &link.clone().on_domain("https://acme.link/x")).await;
+    let shared = service.shorten_link(&ctx, &link.on_domain("acme.link")).await.unwrap();
+
+    assert!(matches!(foreign, Err(DomainError::UnknownDomain(host)) if host == "go.acme.io"));
+    assert!(matches!(unknown, Err(DomainError::UnknownDomain(_))));
+    assert!(matches!(invalid, Err(DomainError::InvalidDomain)));
+    assert_eq!(shared.domain, "acme.link");
+}
+
+// batch items are placed per domain and fail individually on bad domains
+#[tokio::test]
+async fn shorten_batch_places_links_per_domain() {
+    let service = make_service_with_domains();
+    let links = vec![
+        NewLink::new("https://example.com/a"),
+        NewLink::new("https://example.com/a").on_domain("acme.link"),
+        NewLink::new("https://example.com/a").on_domain("acme.link"),
+        NewLink::new("https://example.com/b").on_domain("nope.io"),
+    ];
+
+    let results = service.shorten_batch(&AuditContext::system(), &links).await.unwrap();
+
+    let first = results[0].as_ref().unwrap();
+    let second = results[1].as_ref().unwrap();
+    assert_eq!(first.domain, DEFAULT_DOMAIN);
+    assert_eq!(second.domain, "acme.link");
+    assert_eq!(second.short_key.as_str(), results[2].as_ref().unwrap().short_key.as_str());
+    assert!(matches!(results[3], Err(DomainError::UnknownDomain(_))));
+}
+
+// a protected link only redirects for a visitor holding a token from the right password
+#[tokio::test]
+async fn protected_link_needs_unlock_token() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let link = NewLink::new("https://example.com/secret").with_password("hunter22");
+    let url = service.shorten_link(&ctx, &link).await.unwrap();
+    let other = service.shorten("https://example.com/public").await.unwrap();
+    assert!(url.is_protected());
+    assert_ne!(url.password_hash.as_deref(), Some("hunter22"));
+
+    assert!(matches!(service.resolve(None, &url.short_key).await, Err(DomainError::PasswordRequired)));
+    assert!(matches!(service.unlock(None, &url.short_key, "hunter2").await, Err(DomainError::WrongPassword)));
+
+    let token = service.unlock(None, &url.short_key, "hunter22").await.unwrap();
+    let visit = Visit::to_host(None).with_unlock_token(token.clone());
+    let resolved = service.visit(&visit, &url.short_key).await.unwrap();
+    assert_eq!(resolved.original_url, "https://example.com/secret");
+
+    let forged = Visit::to_host(None).with_unlock_token(format!("{token}0"));
+    assert!(matches!(service.visit(&forged, &url.short_key).await, Err(DomainError::PasswordRequired)));
+    // tokens are bound to one link, though public links ignore them
+    assert!(service.visit(&visit, &other.short_key).await.is_ok());
+}
+
+// guesses are capped per link, and the cap holds even for the right password
+#[tokio::test]
+async fn password_guesses_are_capped_per_link() {
+    let service = make_service().with_password_attempts(PasswordAttempts::new(2, Duration::from_secs(60)));
+    let ctx = AuditContext::system();
+    let url = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/vault").with_password("open sesame"))
+        .await
+        .unwrap();
+    let other = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/other").with_password("open sesame"))
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        assert!(matches!(service.unlock(None, &url.short_key, "guess").await, Err(DomainError::WrongPassword)));
+    }
+    let locked = service.unlock(None, &url.short_key, "open sesame").await;
+
+    assert!(matches!(locked, Err(DomainError::TooManyAttempts(wait)) if wait > 0 && wait <= 60));
+    assert!(service.unlock(None, &other.short_key, "open sesame").await.is_ok());
+}
+
+// shortening a protected destination again is a password guess too, and shares the cap
+#[tokio::test]
+async fn shorten_password_checks_count_as_guesses() {
+    let service = make_service().with_password_attempts(PasswordAttempts::new(2, Duration::from_secs(60)));
+    let ctx = AuditContext::system();
+    let url = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/vault").with_password("open sesame"))
+        .await
+        .unwrap();
+    let guess = NewLink::new("https://example.com/vault").with_password("guess");
+
+    for _ in 0..2 {
+        assert!(matches!(service.shorten_link(&ctx, &guess).await, Err(DomainError::ProtectionConflict)));
+    }
+    let shortened = service.shorten_link(&ctx, &guess).await;
+    let unlocked = service.unlock(None, &url.short_key, "open sesame").await;
+
+    assert!(matches!(shortened, Err(DomainError::TooManyAttempts(_))));
+    assert!(matches!(unlocked, Err(DomainError::TooManyAttempts(_))));
+}
+
+// a batch hands out the links it just created without checking them against their own request;
+// with no guesses allowed, any password comparison would fail
+#[tokio::test]
+async fn batch_does_not_compare_new_links_with_themselves() {
+    let service = make_service().with_password_attempts(PasswordAttempts::new(0, Duration::from_secs(60)));
+    let ctx = AuditContext::system();
+    let protected = NewLink::new("https://example.com/locker").with_password("open sesame");
+
+    let results = service.shorten_batch(&ctx, &[protected.clone(), protected]).await.unwrap();
+
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(DomainError::TooManyAttempts(_))));
+}
+
+// shortening a protected destination again needs the same password
+#[tokio::test]
+async fn shorten_keeps_password_protection() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let protected = NewLink::new("https://example.com/payroll").with_password("s3cret!");
+    let first = service.shorten_link(&ctx, &protected).await.unwrap();
+
+    let again = service.shorten_link(&ctx, &protected).await.unwrap();
+    let bare = service.shorten_link(&ctx, &NewLink::new("https://example.com/payroll")).await;
+    let other = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/payroll").with_password("guess"))
+        .await;
+    let short = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/short").with_password("abc"))
+        .await;
+    let batch = service
+        .shorten_batch(&ctx, &[protected.clone(), NewLink::new("https://example.com/payroll")])
+        .await
+        .unwrap();
+
+    assert_eq!(again.short_key.as_str(), first.short_key.as_str());
+    assert!(matches!(bare, Err(DomainError::ProtectionConflict)));
+    assert!(matches!(other, Err(DomainError::ProtectionConflict)));
+    assert!(matches!(short, Err(DomainError::InvalidPassword)));
+    assert_eq!(batch[0].as_ref().unwrap().short_key.as_str(), first.short_key.as_str());
+    assert!(matches!(batch[1], Err(DomainError::ProtectionConflict)));
+}
+
+// a one-time link redirects once, then reports its exhausted URL
+#[tokio::test]
+async fn one_time_link_redirects_once() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let link = NewLink::new("https://example.com/invite")
+        .with_max_clicks(1)
+        .with_exhausted_url("https://example.com/expired");
+    let url = service.shorten_link(&ctx, &link).await.unwrap();
+    let limited = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/promo").with_max_clicks(2))
+        .await
+        .unwrap();
+
+    let first = service.resolve(None, &url.short_key).await.unwrap();
+    let second = service.resolve(None, &url.short_key).await;
+    for _ in 0..2 {
+        assert!(service.resolve(None, &limited.short_key).await.is_ok());
+    }
+    let third = service.resolve(None, &limited.short_key).await;
+
+    assert_eq!(first.original_url, "https://example.com/invite");
+    assert!(matches!(second, Err(DomainError::LinkExhausted(Some(ref to))) if to == "https://example.com/expired"));
+    assert!(matches!(third, Err(DomainError::LinkExhausted(None))));
+    let looked_up = service.lookup(None, &url.short_key).await.unwrap();
+    assert!(looked_up.is_exhausted());
+}
+
+// click limits are validated and, like passwords, must match to reuse a link
+#[tokio::test]
+async fn shorten_keeps_click_limits() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let limited = NewLink::new("https://example.com/drop").with_max_clicks(3);
+    let first = service.shorten_link(&ctx, &limited).await.unwrap();
+
+    let again = service.shorten_link(&ctx, &limited).await.unwrap();
+    let bare = service.shorten_link(&ctx, &NewLink::new("https://example.com/drop")).await;
+    let other = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/drop").with_max_clicks(5))
+        .await;
+    let zero = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/zero").with_max_clicks(0))
+        .await;
+    let orphan = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/orphan").with_exhausted_url("https://example.com/"))
+        .await;
+    let bad_fallback = service
+        .shorten_link(
+            &ctx,
+            &NewLink::new("https://example.com/bad").with_max_clicks(1).with_exhausted_url("ftp://example.com"),
+        )
+        .await;
+
+    assert_eq!(again.short_key.as_str(), first.short_key.as_str());
+    assert!(matches!(bare, Err(DomainError::ClickLimitConflict)));
+    assert!(matches!(other, Err(DomainError::ClickLimitConflict)));
+    assert!(matches!(zero, Err(DomainError::MalformedInput(_))));
+    assert!(matches!(orphan, Err(DomainError::MalformedInput(_))));
+    assert!(matches!(bad_fallback, Err(DomainError::InvalidUrl)));
+}
+
+// a scheduled link is "not yet active" before its window and "ended" after it, by the injected clock
+#[tokio::test]
+async fn scheduled_link_only_redirects_inside_its_window() {
+    let start = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+    let now = Arc::new(Mutex::new(start - chrono::Duration::minutes(1)));
+    let service = make_service().with_clock({
+        let now = now.clone();
+        move || *now.lock().unwrap()
+    });
+    let ctx = AuditContext::system();
+    let campaign = NewLink::new("https://example.com/launch")
+        .active_from(start)
+        .active_until(start + chrono::Duration::days(7))
+        .with_not_yet_active_url("https://example.com/coming-soon");
+    let url = service.shorten_link(&ctx, &campaign).await.unwrap();
+
+    let early = service.resolve(None, &url.short_key).await;
+    *now.lock().unwrap() = start;
+    let live = service.resolve(None, &url.short_key).await;
+    *now.lock().unwrap() = start + chrono::Duration::days(7);
+    let ended = service.resolve(None, &url.short_key).await;
+
+    assert!(matches!(early, Err(DomainError::LinkNotYetActive(Some(ref to))) if to == "https://example.com/coming-soon"));
+    assert_eq!(live.unwrap().original_url, "https://example.com/launch");
+    assert!(matches!(ended, Err(DomainError::LinkEnded(None))));
+    assert!(!url.is_cacheable());
+}
+
+// windows are validated and must match to reuse a link
+#[tokio::test]
+async fn shorten_keeps_schedules() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let start = Utc.with_ymd_and_hms(2026, 11, 1, 9, 0, 0).unwrap();
+    let scheduled = NewLink::new("https://example.com/sale").active_from(start);
+    let first = service.shorten_link(&ctx, &scheduled).await.unwrap();
+
+    let again = service.shorten_link(&ctx, &scheduled).await.unwrap();
+    let moved = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/sale").active_from(start + chrono::Duration::hours(1)))
+        .await;
+    let backwards = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/back").active_from(start).active_until(start))
+        .await;
+    let orphan = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/orphan").with_ended_url("https://example.com/"))
+        .await;
+
+    assert_eq!(again.short_key.as_str(), first.short_key.as_str());
+    assert!(matches!(moved, Err(DomainError::ScheduleConflict)));
+    assert!(matches!(backwards, Err(DomainError::MalformedInput(_))));
+    assert!(matches!(orphan, Err(DomainError::MalformedInput(_))));
+}
+
+// platform routes pick the destination, and must match to reuse a link
+#[tokio::test]
+async fn platform_routes_choose_destination() {
+    let service = make_service();
+    let ctx = AuditContext::system();
+    let app = NewLink::new("https://example.com/app")
+        .route_platform(Platform::Ios, "https://apps.apple.com/app/id1")
+        .route_platform(Platform::Android, "https://play.google.com/store/apps/details?id=com.example");
+    let url = service.shorten_link(&ctx, &app).await.unwrap();
+
+    let again = service.shorten_link(&ctx, &app).await.unwrap();
+    let bare = service.shorten_link(&ctx, &NewLink::new("https://example.com/app")).await;
+    let bad = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/bad").route_platform(Platform::Desktop, "not a url"))
+        .await;
+    let ios = Visit::to_host(None).on_platform(Some(Platform::Ios));
+    let desktop = Visit::to_host(None).on_platform(Some(Platform::Desktop));
+    let on_ios = service.visit(&ios, &url.short_key).await.unwrap();
+    let on_desktop = service.visit(&desktop, &url.short_key).await.unwrap();
+
+    assert_eq!(again.short_key.as_str(), url.short_key.as_str());
+    assert!(matches!(bare, Err(DomainError::RoutingConflict)));
+    assert!(matches!(bad, Err(DomainError::InvalidUrl)));
+    assert_eq!(on_ios.destination(&ios), "https://apps.apple.com/app/id1");
+    assert_eq!(on_desktop.destination(&desktop), "https://example.com/app");
+    assert_eq!(on_desktop.destination(&Visit::default()), "https://example.com/app");
+    assert!(!url.is_cacheable());
+}
+
+// country routes apply when no platform route does, and clicks remember the country
+#[tokio::test]
+async fn country_routes_choose_destination_and_are_recorded() {
+    let service = make_service().with_click_log(MockClickRepository {
+        clicks: Default::default(),
+    });
+    let ctx = AuditContext::system();
+    let shop = NewLink::new("https://example.com/shop")
+        .route_country("DE", "https://example.de/shop")
+        .route_platform(Platform::Ios, "https://apps.apple.com/app/id1");
+    let url = service.shorten_link(&ctx, &shop).await.unwrap();
+    let rerouted = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/shop").route_platform(Platform::Ios, "https://apps.apple.com/app/id1"))
+        .await;
+    let bad = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/bad").route_country("Germany", "https://example.de"))
+        .await;
+
+    let german = Visit::to_host(None).from_country(Some("DE".to_string()));
+    let german_iphone = german.clone().on_platform(Some(Platform::Ios));
+    let french = Visit::to_host(None).from_country(Some("FR".to_string()));
+    service.visit(&german, &url.short_key).await.unwrap();
+    service.visit(&Visit::default(), &url.short_key).await.unwrap();
+    let clicks: Vec<ClickEvent> = service.export_clicks(None).try_collect().await.unwrap();
+
+    assert!(matches!(rerouted, Err(DomainError::RoutingConflict)));
+    assert!(matches!(bad, Err(DomainError::MalformedInput(_))));
+    assert_eq!(url.destination(&german), "https://example.de/shop");
+    assert_eq!(url.destination(&german_iphone), "https://apps.apple.com/app/id1");
+    assert_eq!(url.destination(&french), "https://example.com/shop");
+    let countries: Vec<Option<&str>> = clicks.iter().map(|click| click.country.as_deref()).collect();
+    assert_eq!(countries, vec![Some("DE"), None]);
+}
+
+// Split links send each visitor to one weighted variant, the same one every time,
+// and count clicks per variant
+#[tokio::test]
+async fn variants_split_visitors_stickily_and_count_clicks() {
+    let service = make_service().with_click_log(MockClickRepository {
+        clicks: Default::default(),
+    });
+    let ctx = AuditContext::system();
+    let landing = NewLink::new("https://example.com/landing")
+        .with_variant("https://example.com/landing-a", 9)
+        .with_variant("https://example.com/landing-b", 1);
+    let url = service.shorten_link(&ctx, &landing).await.unwrap();
+    let unsplit = service.shorten_link(&ctx, &NewLink::new("https://example.com/landing")).await;
+    let single = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/one").with_variant("https://example.com/a", 1))
+        .await;
+    let weightless = service
+        .shorten_link(
+            &ctx,
+            &NewLink::new("https://example.com/zero")
+                .with_variant("https://example.com/a", 0)
+                .with_variant("https://example.com/b", 1),
+        )
+        .await;
+
+    let visitors: Vec<Visit> = (0..1000).map(|i| Visit::default().as_visitor(format!("visitor{i}"))).collect();
+    let to_a = visitors
+        .iter()
+        .filter(|visit| url.destination(visit) == "https://example.com/landing-a")
+        .count();
+    let returning = Visit::default().as_visitor("returning");
+    service.visit(&returning, &url.short_key).await.unwrap();
+    service.visit(&returning, &url.short_key).await.unwrap();
+    service.visit(&Visit::default(), &url.short_key).await.unwrap();
+    let clicks: Vec<ClickEvent> = service.export_clicks(None).try_collect().await.unwrap();
+    let stats = service.variant_clicks(None, &url.short_key).await.unwrap();
+
+    assert!(matches!(unsplit, Err(DomainError::RoutingConflict)));
+    assert!(matches!(single, Err(DomainError::MalformedInput(_))));
+    assert!(matches!(weightless, Err(DomainError::MalformedInput(_))));
+    assert!((850..=950).contains(&to_a), "{to_a} of 1000 visitors got the 90% variant");
+    assert!(!url.is_cacheable());
+    assert_eq!(url.destination(&Visit::default()), "https://example.com/landing");
+    let returning_variant = url.variant(&returning).expect("visitor has a variant");
+    let variants: Vec<Option<i32>> = clicks.iter().map(|click| click.variant).collect();
+    assert_eq!(variants, vec![Some(returning_variant as i32), Some(returning_variant as i32), None]);
+    let counts: Vec<(usize, i64)> = stats.iter().map(|stat| (stat.variant, stat.clicks)).collect();
+    let expected: Vec<(usize, i64)> = (0..2).map(|i| (i, if i == returning_variant { 2 } else { 0 })).collect();
+    assert_eq!(counts, expected);
+    assert_eq!(stats[1].weight, 1);
+}
+
+// extra path only resolves on links that forward it, and never climbs out of the destination
+#[tokio::test]
+async fn extra_path_needs_a_forwarding_link() {
+    let service = make_service().with_click_log(MockClickRepository {
+        clicks: Default::default(),
+    });
+    let ctx = AuditContext::system();
+    let docs = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/docs").forwarding_path())
+        .await
+        .unwrap();
+    let plain = service.shorten("https://example.com/plain").await.unwrap();
+    let changed = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/docs").forwarding_query(QueryPolicy::Keep))
+        .await;
+
+    let deep = Visit::default().with_extra_path("guides/setup").with_query(Some("ref=x".to_string()));
+    let resolved = service.visit(&deep, &docs.short_key).await.unwrap();
+    let on_plain = service.visit(&deep, &plain.short_key).await;
+    let escaping = service.visit(&Visit::default().with_extra_path("../../etc"), &docs.short_key).await;
+    let clicks: Vec<ClickEvent> = service.export_clicks(None).try_collect().await.unwrap();
+
+    assert_eq!(resolved.location(&deep), "https://example.com/docs/guides/setup");
+    assert!(matches!(on_plain, Err(DomainError::UrlNotFound)));
+    assert!(matches!(escaping, Err(DomainError::UrlNotFound)));
+    assert!(matches!(changed, Err(DomainError::RoutingConflict)));
+    assert_eq!(clicks.len(), 1);
+}
+
+// UTM fields and workspace templates tag the destination, and the tagged URL is what
+// makes a link the same or a new one
+#[tokio::test]
+async fn utm_tags_are_applied_before_idempotency() {
+    let templates = MockUtmTemplateRepository::default();
+    let newsletter = Utm {
+        source: Some("newsletter".to_string()),
+        medium: Some("email".to_string()),
+        ..Utm::default()
+    };
+    templates.save(&UtmTemplate::new(Some("acme"), "newsletter", newsletter)).await.unwrap();
+    let service = make_service().with_utm_templates(templates);
+    let ctx = AuditContext::system();
+    let spring = |campaign: &str| {
+        NewLink::new("https://example.com/sale?ref=home")
+            .in_workspace("acme")
+            .with_utm_template("newsletter")
+            .with_utm(Utm {
+                campaign: Some(campaign.to_string()),
+                ..Utm::default()
+            })
+    };
+
+    let first = service.shorten_link(&ctx, &spring("spring")).await.unwrap();
+    let again = service.shorten_link(&ctx, &spring("spring")).await.unwrap();
+    let summer = service.shorten_link(&ctx, &spring("summer")).await.unwrap();
+    let elsewhere = service.shorten_link(&ctx, &spring("spring").in_workspace("globex")).await;
+    let batch = service
+        .shorten_batch(&ctx, &[spring("spring"), spring("spring").with_utm_template("missing")])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        first.original_url,
+        "https://example.com/sale?ref=home&utm_source=newsletter&utm_medium=email&utm_campaign=spring"
+    );
+    assert_eq!(again.short_key.as_str(), first.short_key.as_str());
+    assert_ne!(summer.short_key.as_str(), first.short_key.as_str());
+    assert!(matches!(elsewhere, Err(DomainError::UnknownUtmTemplate(name)) if name == "newsletter"));
+    assert_eq!(batch[0].as_ref().unwrap().short_key.as_str(), first.short_key.as_str());
+    assert!(matches!(batch[1], Err(DomainError::UnknownUtmTemplate(_))));
+}
+
+// a preview describes a link without counting a click, and keeps protected destinations hidden
+#[tokio::test]
+async fn preview_reports_link_without_clicking_it() {
+    let service = make_service().with_click_log(MockClickRepository {
+        clicks: Default::default(),
+    });
+    let ctx = AuditContext::system();
+    let url = service.shorten_link(&ctx, &NewLink::new("https://example.com/pricing")).await.unwrap();
+    let locked = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/secret").with_password("hunter22"))
+        .await
+        .unwrap();
+    service.visit(&Visit::default(), &url.short_key).await.unwrap();
+    service.visit(&Visit::default(), &url.short_key).await.unwrap();
+
+    let preview = service.preview(None, &url.short_key).await.unwrap();
+    let again = service.preview(None, &url.short_key).await.unwrap();
+    let hidden = service.preview(None, &locked.short_key).await.unwrap();
+    let missing = service.preview(None, &ShortKey::new("absent1".to_string()).unwrap()).await;
+
+    assert_eq!(preview.destination.as_deref(), Some("https://example.com/pricing"));
+    assert_eq!(preview.owner, Some("default".to_string()));
+    assert_eq!(preview.created_at, url.created_at);
+    assert!(!preview.routed);
+    assert_eq!(preview.clicks, Some(2));
+    assert_eq!(again.clicks, Some(2));
+    assert_eq!(hidden.destination, None);
+    assert!(matches!(missing, Err(DomainError::UrlNotFound)));
+}
+
+// the link's own warning page wins over its domain's, and listed destinations are always flagged
+#[tokio::test]
+async fn interstitials_come_from_link_domain_or_warning_list() {
+    let mut acme = Domain::new("acme.link", None);
+    acme.interstitial = Some(Interstitial::new(5));
+    let service = UrlService::new(MockUrlRepository::new())
+        .with_domains(MockDomainRepository::with(vec![Domain::new(DEFAULT_DOMAIN, None), acme]), DEFAULT_DOMAIN)
+        .with_warning_list(WarningList::parse("bad.example"));
+    let ctx = AuditContext::system();
+    let own = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/own").on_domain("acme.link").with_interstitial(Interstitial::new(0)))
+        .await
+        .unwrap();
+    let inherited = service.shorten_link(&ctx, &NewLink::new("https://example.com/inherited").on_domain("acme.link")).await.unwrap();
+    let plain = service.shorten("https://example.com/plain").await.unwrap();
+    let flagged = service.shorten("https://login.bad.example/").await.unwrap();
+    let too_slow = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/slow").with_interstitial(Interstitial::new(31)))
+        .await;
+    let changed = service
+        .shorten_link(&ctx, &NewLink::new("https://example.com/plain").with_interstitial(Interstitial::new(0)))
+        .await;
+
+    let warning = |url: &Url| service.warning(url, &url.original_url);
+    let on_acme = Visit::to_host(Some("acme.link"));
+    let own = service.visit(&on_acme, &own.short_key).await.unwrap();
+    let inherited = service.visit(&on_acme, &inherited.short_key).await.unwrap();
+    let plain = service.visit(&Visit::default(), &plain.short_key).await.unwrap();
+    let flagged = service.visit(&Visit::default(), &flagged.short_key).await.unwrap();
+
+    assert_eq!(warning(&own), Some(Warning::Leaving(Interstitial::new(0))));
+    assert_eq!(warning(&inherited), Some(Warning::Leaving(Interstitial::new(5))));
+    assert_eq!(warning(&plain), None);
+    assert_eq!(warning(&flagged), Some(Warning::Flagged));
+    assert!(matches!(too_slow, Err(DomainError::MalformedInput(_))));
+    assert!(matches!(changed, Err(DomainError::RoutingConflict)));
+}
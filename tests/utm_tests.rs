@@ -0,0 +1,59 @@
+use redirect_service::model::utm::{Utm, UtmTemplate};
+
+fn campaign(source: &str, campaign: &str) -> Utm {
+    Utm {
+        source: Some(source.to_string()),
+        campaign: Some(campaign.to_string()),
+        ..Utm::default()
+    }
+}
+
+// parameters come out in a fixed order, after the destination's own query
+#[test]
+fn tags_are_appended_in_a_fixed_order() {
+    let utm = Utm {
+        content: Some("hero".to_string()),
+        medium: Some("email".to_string()),
+        ..campaign("newsletter", "spring")
+    };
+
+    assert_eq!(
+        utm.apply("https://example.com/sale?ref=home").unwrap(),
+        "https://example.com/sale?ref=home&utm_source=newsletter&utm_medium=email&utm_campaign=spring&utm_content=hero"
+    );
+}
+
+// tagging replaces UTM parameters the destination already had, so it can't duplicate them
+#[test]
+fn existing_tags_are_replaced() {
+    let tagged = campaign("newsletter", "spring")
+        .apply("https://example.com/?utm_source=old&utm_medium=social")
+        .unwrap();
+
+    assert_eq!(tagged, "https://example.com/?utm_medium=social&utm_source=newsletter&utm_campaign=spring");
+}
+
+// request fields win over the template's; the template fills the gaps
+#[test]
+fn template_fills_unset_fields() {
+    let template = Utm {
+        medium: Some("email".to_string()),
+        ..campaign("newsletter", "evergreen")
+    };
+    let merged = Utm {
+        campaign: Some("spring".to_string()),
+        ..Utm::default()
+    }
+    .or(&template);
+
+    assert_eq!(merged, Utm { medium: Some("email".to_string()), ..campaign("newsletter", "spring") });
+}
+
+// template names are slugs and templates need at least one parameter
+#[test]
+fn templates_are_validated() {
+    assert!(UtmTemplate::new(None, "newsletter", campaign("newsletter", "spring")).validate().is_ok());
+    assert!(UtmTemplate::new(None, "news letter", campaign("newsletter", "spring")).validate().is_err());
+    assert!(UtmTemplate::new(None, "empty", Utm::default()).validate().is_err());
+    assert!(UtmTemplate::new(None, "blank", campaign(" ", "spring")).validate().is_err());
+}